@@ -1,9 +1,8 @@
-use crate::config::Config;
-use crate::error::{ConfigGuardError, ConfigGuardResult};
-use crate::reporting::{format_validation_result, ReportFormat};
-use crate::schema::Schema;
-use crate::validation::{validate, ValidationResult};
 use clap::{Arg, ArgAction, Command};
+use configguard::{
+    format_validation_report, validation_report_json, Config, ConfigGuardError, ConfigGuardResult,
+    ReportFormat, Severity, ValidationError, Validator,
+};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -15,6 +14,9 @@ pub enum RunResult {
 
     /// Command execution failed
     Failure(ConfigGuardError),
+
+    /// Command execution failed, and the failure was already printed
+    Reported(ConfigGuardError),
 }
 
 /// Create the command-line interface definition
@@ -55,6 +57,13 @@ pub fn cli() -> Command {
                         .help("Enable strict mode (reject unknown keys)")
                         .action(ArgAction::SetTrue),
                 )
+                .arg(
+                    Arg::new("fail-on")
+                        .long("fail-on")
+                        .help("Lowest severity that fails validation (warning or error)")
+                        .default_value("error")
+                        .value_parser(["warning", "error"]),
+                )
                 .arg(
                     Arg::new("directory")
                         .short('d')
@@ -63,6 +72,30 @@ pub fn cli() -> Command {
                         .action(ArgAction::SetTrue),
                 ),
         )
+        .subcommand(
+            Command::new("resolve")
+                .about("Print a configuration with schema defaults filled in")
+                .arg(
+                    Arg::new("config")
+                        .help("Path to the configuration file to resolve")
+                        .required(true)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new("schema")
+                        .short('s')
+                        .long("schema")
+                        .help("Path to the schema file")
+                        .required(true)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new("strict")
+                        .long("strict")
+                        .help("Enable strict mode (reject unknown keys)")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
 }
 
 /// Run the CLI command
@@ -83,12 +116,17 @@ pub fn run(matches: &clap::ArgMatches) -> RunResult {
                 _ => ReportFormat::Text,
             };
 
+            let fail_on = match sub_matches.get_one::<String>("fail-on").map(String::as_str) {
+                Some("warning") => Severity::Warning,
+                _ => Severity::Error,
+            };
+
             let strict_mode = sub_matches.get_flag("strict");
             let directory_mode = sub_matches.get_flag("directory");
 
             // Load schema
-            let schema = match Schema::from_file(schema_path) {
-                Ok(schema) => schema,
+            let validator = match Validator::from_schema_file(schema_path) {
+                Ok(validator) => validator.strict(strict_mode),
                 Err(err) => return RunResult::Failure(err),
             };
 
@@ -100,9 +138,30 @@ pub fn run(matches: &clap::ArgMatches) -> RunResult {
 
             // Process each configuration file
             if directory_mode {
-                validate_directories(&config_paths, &schema, strict_mode, &output_format)
+                validate_directories(&config_paths, &validator, &output_format, fail_on)
             } else {
-                validate_configs(&config_paths, &schema, strict_mode, &output_format)
+                validate_configs(&config_paths, &validator, &output_format, fail_on)
+            }
+        }
+        Some(("resolve", sub_matches)) => {
+            let schema_path = sub_matches
+                .get_one::<String>("schema")
+                .expect("Schema is required");
+            let config_path = sub_matches
+                .get_one::<String>("config")
+                .expect("Config is required");
+
+            let validator = match Validator::from_schema_file(schema_path) {
+                Ok(validator) => validator.strict(sub_matches.get_flag("strict")),
+                Err(err) => return RunResult::Failure(err),
+            };
+
+            match resolve_config(config_path, &validator) {
+                Ok(resolved) => {
+                    print!("{}", resolved);
+                    RunResult::Success
+                }
+                Err(err) => RunResult::Failure(err),
             }
         }
         _ => {
@@ -115,14 +174,16 @@ pub fn run(matches: &clap::ArgMatches) -> RunResult {
 /// Validate a list of individual configuration files
 fn validate_configs(
     config_paths: &[&String],
-    schema: &Schema,
-    strict: bool,
+    validator: &Validator,
     format: &ReportFormat,
+    fail_on: Severity,
 ) -> RunResult {
     let mut errors_found = false;
 
     for config_path in config_paths {
-        match validate_single_config(config_path, schema, strict, format) {
+        let config = Config::from_file(config_path);
+        match config.and_then(|config| validate_single_config(&config, validator, format, fail_on))
+        {
             Ok(false) => {
                 // Valid
                 if config_paths.len() > 1 && *format == ReportFormat::Text {
@@ -167,61 +228,96 @@ fn validate_configs(
 }
 
 /// Validate a single configuration file and print results
+///
+/// The configuration fails when it has a diagnostic of at least `fail_on` severity.
 fn validate_single_config(
-    config_path: &str,
-    schema: &Schema,
-    strict: bool,
+    config: &Config,
+    validator: &Validator,
     output_format: &ReportFormat,
+    fail_on: Severity,
 ) -> ConfigGuardResult<bool> {
-    // Load configuration
-    let config = Config::from_file(config_path)?;
+    let report = validator.validate(config)?;
 
-    // Validate against schema
-    match validate(&config, schema, strict) {
-        Ok(ValidationResult::Valid) => {
-            // Generate report for valid result
-            match format_validation_result(&ValidationResult::Valid, output_format) {
-                Ok(report) => {
-                    // Print report to stdout
-                    println!("{}", report);
-                    Ok(false) // No errors
-                }
-                Err(err) => Err(err),
-            }
-        }
-        // This case should not happen with current implementation, but needed for exhaustive matching
-        Ok(ValidationResult::Invalid(errors)) => {
-            // Create report for invalid result
-            let result = ValidationResult::Invalid(errors);
-            match format_validation_result(&result, output_format) {
-                Ok(report) => {
-                    println!("{}", report);
-                    Ok(true) // Has errors but not a failure
-                }
-                Err(err) => Err(err),
-            }
-        }
+    if !report.fails_on(fail_on) {
+        // Generate report (including any warnings) and print it to stdout
+        let report = format_validation_report(&report, fail_on, output_format)?;
+        println!("{}", report);
+        Ok(false) // No errors
+    } else {
         // Always propagate AllValidationErrors directly to main for JSON formatting
-        Err(err @ ConfigGuardError::AllValidationErrors { .. }) => Err(err),
-        Err(err) => Err(err),
+        Err(ConfigGuardError::AllValidationErrors {
+            errors: report.diagnostics().cloned().collect(),
+        })
+    }
+}
+
+/// Print warnings (e.g. deprecated keys) to stderr
+fn print_warnings(warnings: &[ValidationError]) {
+    for warning in warnings {
+        match warning.location() {
+            Some(position) => eprintln!(
+                "Warning at path '{}' ({}): {}",
+                warning.path, position, warning.message
+            ),
+            None => eprintln!("Warning at path '{}': {}", warning.path, warning.message),
+        }
+        eprintln!("   Expected: {}", warning.expected);
+    }
+}
+
+/// Fill in schema defaults and render the result in the configuration's format
+///
+/// The resolved configuration is validated first, so only a configuration the
+/// service would accept is printed.
+fn resolve_config(config_path: &str, validator: &Validator) -> ConfigGuardResult<String> {
+    let config = Config::from_file(config_path)?;
+    let resolved = validator.resolve(&config)?;
+
+    let report = validator.validate(&resolved)?;
+    print_warnings(&report.warnings);
+    if !report.is_valid() {
+        return Err(ConfigGuardError::AllValidationErrors {
+            errors: report.errors,
+        });
     }
+
+    resolved.render()
+}
+
+/// Files read while validating directories
+#[derive(Default)]
+struct DirectoryFiles {
+    /// Configurations that could be loaded, for the `project` constraints
+    configs: Vec<Config>,
+
+    /// Files that could not be loaded, with the reason
+    unreadable: Vec<(PathBuf, String)>,
+
+    /// The result of each file, for the JSON output of `project` constraints
+    reports: Vec<serde_json::Value>,
 }
 
 /// Validate all compatible files in the given directories
+///
+/// With JSON output, each directory prints its own summary. When the schema
+/// has `project` constraints, everything (the result of each file and
+/// directory, and of the project constraints) is instead printed as a single
+/// JSON object at the end.
 fn validate_directories(
     dir_paths: &[&String],
-    schema: &Schema,
-    strict: bool,
+    validator: &Validator,
     format: &ReportFormat,
+    fail_on: Severity,
 ) -> RunResult {
     let mut errors_found = false;
     let mut processed_files = 0;
     let mut valid_files = 0;
     let mut skipped_files = 0;
     let mut results = Vec::new();
+    let mut files = DirectoryFiles::default();
 
     for dir_path in dir_paths {
-        match process_directory(dir_path, schema, strict, format) {
+        match process_directory(dir_path, validator, format, fail_on, &mut files) {
             Ok((processed, valid, skipped)) => {
                 processed_files += processed;
                 valid_files += valid;
@@ -243,6 +339,29 @@ fn validate_directories(
                 if *format == ReportFormat::Text {
                     eprintln!("Error processing directory {}: {}", dir_path, err);
                 }
+                results.push(serde_json::json!({
+                    "directory": dir_path,
+                    "error": err.to_string()
+                }));
+                errors_found = true;
+            }
+        }
+    }
+
+    // Constraints across files run once every file has been read
+    let has_project = !validator.schema().project.is_empty();
+    let mut project = None;
+    if has_project {
+        match validate_project(&files, validator, format, fail_on) {
+            Ok((passed, report)) => {
+                errors_found |= !passed;
+                project = report;
+            }
+            Err(err) => {
+                if *format == ReportFormat::Text {
+                    eprintln!("Error checking project constraints: {}", err);
+                }
+                project = Some(serde_json::json!({ "error": err.to_string() }));
                 errors_found = true;
             }
         }
@@ -258,24 +377,37 @@ fn validate_directories(
             "  Skipped: {} files (incompatible extension)",
             skipped_files
         );
-    } else if *format == ReportFormat::Json {
-        // Only output JSON summary if not already output by process_directory
-        if dir_paths.len() > 1 {
-            let summary = serde_json::json!({
-                "directories": results,
-                "total": {
-                    "processed": processed_files,
-                    "valid": valid_files,
-                    "invalid": processed_files - valid_files,
-                    "skipped": skipped_files
-                }
-            });
-            println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+    } else if has_project {
+        let mut summary = serde_json::json!({
+            "directories": results,
+            "files": files.reports,
+            "total": {
+                "processed": processed_files,
+                "valid": valid_files,
+                "invalid": processed_files - valid_files,
+                "skipped": skipped_files
+            }
+        });
+        if let Some(project) = project {
+            summary["project"] = project;
         }
+        println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+    } else if dir_paths.len() > 1 {
+        // Each directory has printed its own summary; add the totals
+        let summary = serde_json::json!({
+            "directories": results,
+            "total": {
+                "processed": processed_files,
+                "valid": valid_files,
+                "invalid": processed_files - valid_files,
+                "skipped": skipped_files
+            }
+        });
+        println!("{}", serde_json::to_string_pretty(&summary).unwrap());
     }
 
     if errors_found {
-        RunResult::Failure(ConfigGuardError::ValidationErrors {
+        let err = ConfigGuardError::ValidationErrors {
             count: processed_files - valid_files,
             source: Box::new(ConfigGuardError::Validation {
                 message: "One or more configurations failed validation".to_string(),
@@ -283,18 +415,65 @@ fn validate_directories(
                 expected: "Valid configuration".to_string(),
                 actual: "Invalid configuration".to_string(),
             }),
-        })
+        };
+        if *format == ReportFormat::Json && has_project {
+            // The JSON summary above already holds every problem
+            RunResult::Reported(err)
+        } else {
+            RunResult::Failure(err)
+        }
     } else {
         RunResult::Success
     }
 }
 
+/// Check the schema's `project` constraints across the files that were read
+///
+/// A file that could not be loaded is reported as a project error too, since
+/// the constraints could not be checked against it. Returns whether the
+/// project passed, and the JSON report for JSON output.
+fn validate_project(
+    files: &DirectoryFiles,
+    validator: &Validator,
+    format: &ReportFormat,
+    fail_on: Severity,
+) -> ConfigGuardResult<(bool, Option<serde_json::Value>)> {
+    let mut report = validator.validate_project(&files.configs)?;
+    for (path, reason) in &files.unreadable {
+        let mut error = ValidationError::new(
+            "",
+            "File could not be checked against the project constraints",
+            "A configuration that can be loaded",
+            reason.as_str(),
+        );
+        error.file = Some(path.display().to_string());
+        report.errors.push(error);
+    }
+    let passed = !report.fails_on(fail_on);
+
+    if *format == ReportFormat::Json {
+        return Ok((passed, Some(validation_report_json(&report, fail_on)?)));
+    }
+    if passed {
+        println!("✅ Project constraints: Valid");
+    } else {
+        eprintln!("❌ Project constraints: Invalid");
+        eprint!("{}", format_validation_report(&report, fail_on, format)?);
+    }
+
+    Ok((passed, None))
+}
+
 /// Process a directory and validate all compatible files
+///
+/// Every configuration file found is added to `files`, whether or not it
+/// could be loaded.
 fn process_directory(
     dir_path: &str,
-    schema: &Schema,
-    strict: bool,
+    validator: &Validator,
     output_format: &ReportFormat,
+    fail_on: Severity,
+    files: &mut DirectoryFiles,
 ) -> ConfigGuardResult<(usize, usize, usize)> {
     let dir = Path::new(dir_path);
 
@@ -312,7 +491,8 @@ fn process_directory(
         println!("Processing directory: {}", dir_path);
         println!(
             "Using schema: {}",
-            schema
+            validator
+                .schema()
                 .root
                 .description
                 .as_deref()
@@ -320,7 +500,11 @@ fn process_directory(
         );
         println!(
             "Strict mode: {}",
-            if strict { "enabled" } else { "disabled" }
+            if validator.is_strict() {
+                "enabled"
+            } else {
+                "disabled"
+            }
         );
     }
 
@@ -337,36 +521,70 @@ fn process_directory(
         }
     };
 
+    // Sorted, so reports (and which file a duplicate is reported in) are stable
+    let mut paths = Vec::new();
     for entry in entries {
         let entry = entry.map_err(|e| ConfigGuardError::IO(e.to_string()))?;
-        let path = entry.path();
+        paths.push(entry.path());
+    }
+    paths.sort();
 
+    for path in paths {
         if path.is_file() {
             if let Some(ext) = path.extension() {
                 let ext_str = ext.to_string_lossy().to_lowercase();
 
-                if ext_str == "yaml" || ext_str == "yml" || ext_str == "json" {
+                if ext_str == "yaml" || ext_str == "yml" || ext_str == "json" || ext_str == "toml" {
                     let path_str = path.to_string_lossy();
+                    processed += 1;
 
-                    match validate_single_config(&path_str, schema, strict, &ReportFormat::Text) {
-                        Ok(has_errors) => {
-                            processed += 1;
-                            if !has_errors {
-                                valid += 1;
-                                if *output_format == ReportFormat::Text {
+                    let config = match Config::from_file(&path) {
+                        Ok(config) => config,
+                        Err(err) => {
+                            if *output_format == ReportFormat::Text {
+                                eprintln!("❌ {}: Error - {}", path_str, err);
+                            }
+                            files.reports.push(serde_json::json!({
+                                "file": path_str,
+                                "valid": false,
+                                "error": err.to_string()
+                            }));
+                            files.unreadable.push((path.clone(), err.to_string()));
+                            continue;
+                        }
+                    };
+
+                    if *output_format == ReportFormat::Json {
+                        let mut json = match validator.validate(&config) {
+                            Ok(report) => {
+                                if !report.fails_on(fail_on) {
+                                    valid += 1;
+                                }
+                                validation_report_json(&report, fail_on)?
+                            }
+                            Err(err) => serde_json::json!({
+                                "valid": false,
+                                "error": err.to_string()
+                            }),
+                        };
+                        json["file"] = serde_json::Value::from(path_str.as_ref());
+                        files.reports.push(json);
+                    } else {
+                        match validate_single_config(&config, validator, output_format, fail_on) {
+                            Ok(has_errors) => {
+                                if !has_errors {
+                                    valid += 1;
                                     println!("✅ {}: Valid", path_str);
+                                } else {
+                                    eprintln!("❌ {}: Invalid", path_str);
                                 }
-                            } else if *output_format == ReportFormat::Text {
-                                eprintln!("❌ {}: Invalid", path_str);
                             }
-                        }
-                        Err(err) => {
-                            processed += 1;
-                            if *output_format == ReportFormat::Text {
+                            Err(err) => {
                                 eprintln!("❌ {}: Error - {}", path_str, err);
                             }
                         }
                     }
+                    files.configs.push(config);
                 } else {
                     skipped += 1;
                 }
@@ -374,8 +592,11 @@ fn process_directory(
         }
     }
 
-    // Generate final report if JSON format is requested
-    if matches!(output_format, ReportFormat::Json) && processed > 0 {
+    // Without project constraints there is no combined report to wait for
+    if *output_format == ReportFormat::Json
+        && processed > 0
+        && validator.schema().project.is_empty()
+    {
         let summary = serde_json::json!({
             "directory": dir_path,
             "processed": processed,
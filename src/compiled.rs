@@ -0,0 +1,499 @@
+use crate::error::{ConfigGuardError, ConfigGuardResult};
+use crate::expression::{self, Expr};
+use crate::quantity::Quantity;
+use crate::schema::{definition_key, Schema, SchemaRule};
+use crate::source::key_path;
+use crate::validation::display_value;
+use regex::Regex;
+use serde_yaml::Value;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// A schema prepared for repeated validation
+///
+/// Compiling resolves everything that does not depend on the configuration
+/// being validated (regexes, numeric bounds, required keys) exactly once, so
+/// a single `CompiledSchema` can be reused across validation runs and shared
+/// between threads.
+#[derive(Debug, Clone)]
+pub struct CompiledSchema {
+    /// The schema, which is never modified or moved once compiled
+    schema: Arc<Schema>,
+
+    /// Compiled state of every rule in `schema`, keyed by [`rule_key`]
+    rules: HashMap<usize, CompiledRule>,
+}
+
+/// Precomputed validation state for a single schema rule
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CompiledRule {
+    /// Compiled `pattern` regex
+    pub(crate) pattern: Option<Regex>,
+
+    /// Compiled `key_pattern` regex
+    pub(crate) key_pattern: Option<Regex>,
+
+    /// Compiled `pattern_keys` regexes with their source pattern, in sorted order
+    pub(crate) pattern_keys: Vec<(Regex, String)>,
+
+    /// `min` bound as a number
+    pub(crate) min: Option<Numeric>,
+
+    /// `max` bound as a number
+    pub(crate) max: Option<Numeric>,
+
+    /// `exclusive_min` bound as a number
+    pub(crate) exclusive_min: Option<Numeric>,
+
+    /// `exclusive_max` bound as a number
+    pub(crate) exclusive_max: Option<Numeric>,
+
+    /// `multiple_of` as a number
+    pub(crate) multiple_of: Option<Numeric>,
+
+    /// Names of the required child keys, in sorted order
+    pub(crate) required_keys: Vec<String>,
+
+    /// Names of child keys with `required_if` or `forbidden_if`, in sorted order
+    pub(crate) conditional_keys: Vec<String>,
+
+    /// Name of the definition referenced by `$ref`
+    pub(crate) reference: Option<String>,
+
+    /// Parsed `assert` expressions, in the order of the rule's `assertions`
+    pub(crate) assertions: Vec<Expr>,
+
+    /// Parsed `ref_to` path
+    pub(crate) ref_to: Option<DocumentPath>,
+}
+
+/// A path selecting values in a configuration, such as `$.backends[*].name`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DocumentPath(Vec<PathSegment>);
+
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    /// `.name` or `["name"]`
+    Key(String),
+
+    /// `[0]`
+    Index(usize),
+
+    /// `[*]` or `.*`: every item of a list or value of an object
+    Wildcard,
+}
+
+/// A number from a schema or configuration, kept exact when it is an integer
+///
+/// Comparing integers as `f64` loses precision above 2^53, which matters for
+/// large `u64` IDs, so integers are compared as `i128` instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Numeric {
+    Int(i128),
+    Float(f64),
+}
+
+impl Numeric {
+    /// Read a YAML number, or `None` for any other value
+    pub(crate) fn from_value(value: &Value) -> Option<Numeric> {
+        let Value::Number(n) = value else {
+            return None;
+        };
+        if let Some(i) = n.as_i64() {
+            Some(Numeric::Int(i.into()))
+        } else if let Some(u) = n.as_u64() {
+            Some(Numeric::Int(u.into()))
+        } else {
+            n.as_f64().map(Numeric::Float)
+        }
+    }
+
+    /// The number as `f64` (possibly rounded)
+    pub(crate) fn as_f64(self) -> f64 {
+        match self {
+            Numeric::Int(i) => i as f64,
+            Numeric::Float(f) => f,
+        }
+    }
+
+    /// Compare two numbers, exactly when both are integers
+    pub(crate) fn compare(self, other: Numeric) -> Option<Ordering> {
+        match (self, other) {
+            (Numeric::Int(a), Numeric::Int(b)) => Some(a.cmp(&b)),
+            (a, b) => a.as_f64().partial_cmp(&b.as_f64()),
+        }
+    }
+}
+
+impl fmt::Display for Numeric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Numeric::Int(i) => write!(f, "{}", i),
+            Numeric::Float(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+impl DocumentPath {
+    /// Parse a path starting at the document root `$`
+    pub(crate) fn parse(path: &str) -> Result<DocumentPath, String> {
+        let mut rest = path
+            .strip_prefix('$')
+            .ok_or_else(|| "the path must start with '$'".to_string())?;
+        let mut segments = Vec::new();
+
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                let segment = match &after[..end] {
+                    "" => return Err(format!("expected a key after '.' in '{}'", path)),
+                    "*" => PathSegment::Wildcard,
+                    key => PathSegment::Key(key.to_string()),
+                };
+                segments.push(segment);
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = after
+                    .find(']')
+                    .ok_or_else(|| format!("missing ']' in '{}'", path))?;
+                let inner = after[..end].trim();
+                let quoted = ['"', '\'']
+                    .iter()
+                    .find_map(|q| inner.strip_prefix(*q)?.strip_suffix(*q));
+                let segment = match (inner, quoted) {
+                    (_, Some(key)) => PathSegment::Key(key.to_string()),
+                    ("*", None) => PathSegment::Wildcard,
+                    (index, None) => PathSegment::Index(index.parse().map_err(|_| {
+                        format!(
+                            "expected '*', an index or a quoted key in '[{}]' in '{}'",
+                            index, path
+                        )
+                    })?),
+                };
+                segments.push(segment);
+                rest = &after[end + 1..];
+            } else {
+                return Err(format!("expected '.' or '[' at '{}' in '{}'", rest, path));
+            }
+        }
+
+        if segments.is_empty() {
+            return Err("the path must select values below '$'".to_string());
+        }
+        Ok(DocumentPath(segments))
+    }
+
+    /// Every value the path selects in a document
+    pub(crate) fn select<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        self.select_with_paths(root)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    /// Every value the path selects, with its concrete path (e.g. `.backends[1].name`)
+    pub(crate) fn select_with_paths<'a>(&self, root: &'a Value) -> Vec<(String, &'a Value)> {
+        let mut current = vec![(String::new(), root)];
+        for segment in &self.0 {
+            let mut next = Vec::new();
+            for (path, value) in current {
+                match (segment, value) {
+                    (PathSegment::Key(key), Value::Mapping(map)) => {
+                        if let Some(child) = map.get(key.as_str()) {
+                            next.push((key_path(&path, key), child));
+                        }
+                    }
+                    (PathSegment::Index(i), Value::Sequence(items)) => {
+                        if let Some(child) = items.get(*i) {
+                            next.push((format!("{}[{}]", path, i), child));
+                        }
+                    }
+                    (PathSegment::Wildcard, Value::Sequence(items)) => {
+                        for (i, child) in items.iter().enumerate() {
+                            next.push((format!("{}[{}]", path, i), child));
+                        }
+                    }
+                    (PathSegment::Wildcard, Value::Mapping(map)) => {
+                        for (key, child) in map {
+                            let key = match key {
+                                Value::String(key) => key.clone(),
+                                other => display_value(other),
+                            };
+                            next.push((key_path(&path, &key), child));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            current = next;
+        }
+        current
+    }
+}
+
+impl CompiledSchema {
+    /// The schema this was compiled from
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// The compiled root rule
+    pub fn root(&self) -> &SchemaRule {
+        &self.schema.root
+    }
+
+    /// Look up a compiled definition by name
+    pub(crate) fn definition(&self, name: &str) -> Option<&SchemaRule> {
+        self.schema.definitions.get(name)
+    }
+
+    /// The compiled state of a rule of this schema
+    ///
+    /// A rule from anywhere else has none, which is an internal error rather
+    /// than a reason to skip its checks.
+    pub(crate) fn rule(&self, rule: &SchemaRule) -> ConfigGuardResult<&CompiledRule> {
+        self.rules.get(&rule_key(rule)).ok_or_else(|| {
+            ConfigGuardError::Internal(format!(
+                "Rule for field of type {} is not part of the compiled schema",
+                rule.data_type
+            ))
+        })
+    }
+}
+
+/// Identify a rule by its address
+///
+/// The schema is kept behind an `Arc` and never mutated after compiling, so
+/// the address of each of its rules is stable for the life of the `CompiledSchema`.
+fn rule_key(rule: &SchemaRule) -> usize {
+    rule as *const SchemaRule as usize
+}
+
+impl Schema {
+    /// Compile the schema for repeated validation
+    ///
+    /// The schema is checked for correctness first, so invalid patterns or
+    /// conflicting constraints are reported here rather than during validation.
+    pub fn compile(self) -> ConfigGuardResult<CompiledSchema> {
+        self.validate_schema()?;
+        let schema = Arc::new(self);
+        let mut rules = HashMap::new();
+        compile_rule(&schema.root, &mut rules)?;
+        for definition in schema.definitions.values() {
+            compile_rule(definition, &mut rules)?;
+        }
+
+        Ok(CompiledSchema { schema, rules })
+    }
+}
+
+/// Compile a rule and all of its child rules into `rules`
+fn compile_rule(
+    rule: &SchemaRule,
+    rules: &mut HashMap<usize, CompiledRule>,
+) -> ConfigGuardResult<()> {
+    let mut compiled = CompiledRule::default();
+
+    if let Some(pattern) = &rule.pattern {
+        let regex = Regex::new(pattern).map_err(|e| {
+            ConfigGuardError::Pattern(format!("Invalid regex pattern '{}': {}", pattern, e))
+        })?;
+        compiled.pattern = Some(regex);
+    }
+
+    if let Some(pattern) = &rule.key_pattern {
+        let regex = Regex::new(pattern).map_err(|e| {
+            ConfigGuardError::Pattern(format!("Invalid regex key_pattern '{}': {}", pattern, e))
+        })?;
+        compiled.key_pattern = Some(regex);
+    }
+
+    if let Some(pattern_keys) = &rule.pattern_keys {
+        let mut patterns: Vec<&String> = pattern_keys.keys().collect();
+        patterns.sort();
+        for pattern in patterns {
+            let regex = Regex::new(pattern).map_err(|e| {
+                ConfigGuardError::Pattern(format!("Invalid regex pattern '{}': {}", pattern, e))
+            })?;
+            compiled.pattern_keys.push((regex, pattern.clone()));
+        }
+    }
+
+    for assertion in rule.assertions.iter().flatten() {
+        let expr = expression::parse(&assertion.expr).map_err(|e| {
+            ConfigGuardError::Schema(format!(
+                "Invalid 'assert' expression '{}': {}",
+                assertion.expr, e
+            ))
+        })?;
+        compiled.assertions.push(expr);
+    }
+
+    compiled.ref_to = match &rule.ref_to {
+        Some(path) => Some(DocumentPath::parse(path).map_err(|e| {
+            ConfigGuardError::Schema(format!("Invalid 'ref_to' path '{}': {}", path, e))
+        })?),
+        None => None,
+    };
+
+    compiled.reference = rule
+        .reference
+        .as_deref()
+        .and_then(definition_key)
+        .map(str::to_string);
+
+    // Quantity bounds are normalized (seconds, bytes, percent) like the values they limit
+    let quantity = rule.data_type.members().iter().find_map(Quantity::of);
+    let bound = |value: &Value| match quantity {
+        Some(quantity) => quantity.parse_value(value).ok().map(Numeric::Float),
+        None => Numeric::from_value(value),
+    };
+    compiled.min = rule.min.as_ref().and_then(bound);
+    compiled.max = rule.max.as_ref().and_then(bound);
+    compiled.exclusive_min = rule.exclusive_min.as_ref().and_then(bound);
+    compiled.exclusive_max = rule.exclusive_max.as_ref().and_then(bound);
+    compiled.multiple_of = rule.multiple_of.as_ref().and_then(bound);
+
+    for child in rule.children() {
+        compile_rule(child, rules)?;
+    }
+
+    if let Some(keys) = &rule.keys {
+        compiled.required_keys = keys
+            .iter()
+            .filter(|(_, key_rule)| key_rule.required)
+            .map(|(key_name, _)| key_name.clone())
+            .collect();
+        compiled.required_keys.sort();
+
+        compiled.conditional_keys = keys
+            .iter()
+            .filter(|(_, key_rule)| {
+                key_rule.required_if.is_some() || key_rule.forbidden_if.is_some()
+            })
+            .map(|(key_name, _)| key_name.clone())
+            .collect();
+        compiled.conditional_keys.sort();
+    }
+
+    rules.insert(rule_key(rule), compiled);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::SchemaType;
+
+    fn schema_from_yaml(yaml: &str) -> Schema {
+        Schema::new(serde_yaml::from_str(yaml).unwrap())
+    }
+
+    #[test]
+    fn test_compile_caches_rule_state() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+              name:
+                type: string
+                required: true
+                pattern: "^[a-z]+$"
+              port:
+                type: integer
+                required: true
+                min: 1
+                max: 65535
+              tags:
+                type: list
+                items:
+                  type: string
+                  pattern: "^t-"
+        "#,
+        );
+
+        let compiled = schema.compile().unwrap();
+        let state = |rule| compiled.rule(rule).unwrap();
+        let root = compiled.root();
+        assert_eq!(root.data_type, SchemaType::Object);
+        assert_eq!(state(root).required_keys, vec!["name", "port"]);
+
+        let keys = root.keys.as_ref().unwrap();
+        assert!(state(&keys["name"]).pattern.is_some());
+        assert_eq!(state(&keys["port"]).min, Some(Numeric::Int(1)));
+        assert_eq!(state(&keys["port"]).max, Some(Numeric::Int(65535)));
+
+        let items = keys["tags"].items.as_ref().unwrap();
+        assert!(state(items).pattern.as_ref().unwrap().is_match("t-1"));
+
+        // Clones share the schema, so their rules are still found
+        let clone = compiled.clone();
+        assert!(clone.rule(clone.root()).is_ok());
+
+        // A rule that is not part of the schema has no compiled state
+        let outside = SchemaRule::default();
+        assert!(matches!(
+            compiled.rule(&outside),
+            Err(ConfigGuardError::Internal(_))
+        ));
+    }
+
+    #[test]
+    fn test_compile_rejects_invalid_pattern() {
+        let schema = schema_from_yaml(
+            r#"
+            type: string
+            pattern: "*[invalid regex"
+        "#,
+        );
+
+        assert!(matches!(
+            schema.compile(),
+            Err(ConfigGuardError::Pattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_document_path() {
+        let document: Value = serde_yaml::from_str(
+            "backends:\n  - { name: api }\n  - { name: db }\nvolumes: { data: { name: vol-1 } }\n",
+        )
+        .unwrap();
+        let select = |path: &str| -> Vec<Value> {
+            DocumentPath::parse(path)
+                .unwrap()
+                .select(&document)
+                .into_iter()
+                .cloned()
+                .collect()
+        };
+
+        assert_eq!(
+            select("$.backends[*].name"),
+            vec![Value::from("api"), Value::from("db")]
+        );
+        assert_eq!(select("$.backends[1]['name']"), vec![Value::from("db")]);
+        assert_eq!(select("$.volumes.*.name"), vec![Value::from("vol-1")]);
+        assert!(select("$.missing[*].name").is_empty());
+        let paths: Vec<String> = DocumentPath::parse("$.backends[*].name")
+            .unwrap()
+            .select_with_paths(&document)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(paths, vec![".backends[0].name", ".backends[1].name"]);
+
+        for path in ["backends", "$", "$.backends[x]", "$.backends[*", "$..name"] {
+            assert!(DocumentPath::parse(path).is_err(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_compiled_schema_is_shareable() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<CompiledSchema>();
+    }
+}
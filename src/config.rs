@@ -1,5 +1,6 @@
 use crate::error::{ConfigGuardError, ConfigGuardResult};
-use serde_yaml::Value;
+use crate::source::{self, SourceMap};
+use serde_yaml::{Mapping, Value};
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
@@ -11,26 +12,36 @@ pub enum ConfigFormat {
     Yaml,
     /// JSON format (.json)
     Json,
+    /// TOML format (.toml)
+    Toml,
 }
 
 /// Represents a configuration to be validated
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct Config {
     pub data: Value,
     pub format: ConfigFormat,
     pub path: Option<PathBuf>,
     pub content: Option<String>, // Store the original content for line number tracking
+    /// Where each node is in `content`
+    pub source_map: Option<SourceMap>,
 }
 
 impl Config {
     /// Create a new configuration from raw content
     pub fn from_str(content: &str, format: ConfigFormat) -> ConfigGuardResult<Self> {
-        let data = match format {
-            ConfigFormat::Yaml => serde_yaml::from_str(content)
-                .map_err(|e| ConfigGuardError::ParseYaml(e.to_string()))?,
-            ConfigFormat::Json => serde_json::from_str(content)
-                .map_err(|e| ConfigGuardError::ParseJson(e.to_string()))?,
+        let (data, source_map) = match format {
+            ConfigFormat::Yaml => {
+                source::load_yaml(content).map_err(ConfigGuardError::ParseYaml)?
+            }
+            ConfigFormat::Json => {
+                source::load_json(content).map_err(ConfigGuardError::ParseJson)?
+            }
+            ConfigFormat::Toml => {
+                let (data, map) =
+                    source::load_toml(content).map_err(ConfigGuardError::ParseToml)?;
+                (data, Some(map))
+            }
         };
 
         Ok(Self {
@@ -38,6 +49,7 @@ impl Config {
             format,
             path: None,
             content: Some(content.to_string()),
+            source_map,
         })
     }
 
@@ -57,6 +69,33 @@ impl Config {
 
         Ok(config)
     }
+
+    /// Serialize the configuration data in the configuration's own format
+    pub fn render(&self) -> ConfigGuardResult<String> {
+        match self.format {
+            ConfigFormat::Yaml => serde_yaml::to_string(&self.data)
+                .map_err(|e| ConfigGuardError::Serialization(e.to_string())),
+            ConfigFormat::Json => serde_json::to_string_pretty(&self.data)
+                .map(|json| json + "\n")
+                .map_err(|e| ConfigGuardError::Serialization(e.to_string())),
+            ConfigFormat::Toml => {
+                let Value::Mapping(map) = &self.data else {
+                    return Err(ConfigGuardError::Serialization(
+                        "a TOML document must be a table".to_string(),
+                    ));
+                };
+                // Edit the original document rather than writing a new one, so
+                // that it keeps its layout, comments and datetimes
+                let mut document = self
+                    .content
+                    .as_deref()
+                    .and_then(|content| content.parse::<toml_edit::DocumentMut>().ok())
+                    .unwrap_or_default();
+                update_table(document.as_table_mut(), map, false)?;
+                Ok(document.to_string())
+            }
+        }
+    }
 }
 
 /// Detect the format of a configuration file based on its extension
@@ -70,6 +109,7 @@ fn detect_format<P: AsRef<Path>>(path: P) -> ConfigGuardResult<ConfigFormat> {
     match extension.as_deref() {
         Some("yaml") | Some("yml") => Ok(ConfigFormat::Yaml),
         Some("json") => Ok(ConfigFormat::Json),
+        Some("toml") => Ok(ConfigFormat::Toml),
         None => {
             // Try to infer format from file content for files without extensions
             if let Ok(content) = fs::read_to_string(path.as_ref()) {
@@ -92,7 +132,174 @@ fn detect_format<P: AsRef<Path>>(path: P) -> ConfigGuardResult<ConfigFormat> {
     }
 }
 
+/// Bring an item of a TOML document in line with `value`
+///
+/// Whatever already holds the right value is left as it is, so the key order,
+/// comments and datetimes of the original document are kept.
+fn update_item(item: &mut toml_edit::Item, value: &Value) -> ConfigGuardResult<()> {
+    if let Value::Tagged(tagged) = value {
+        return update_item(item, &tagged.value);
+    }
+    match (&mut *item, value) {
+        (toml_edit::Item::Value(current), _) => return update_value(current, value),
+        (toml_edit::Item::Table(table), Value::Mapping(map)) => {
+            return update_table(table, map, false)
+        }
+        (toml_edit::Item::ArrayOfTables(tables), Value::Sequence(items))
+            if tables.len() == items.len() =>
+        {
+            let maps: Vec<&Mapping> = items.iter().filter_map(Value::as_mapping).collect();
+            if maps.len() == items.len() {
+                for (table, map) in tables.iter_mut().zip(maps) {
+                    update_table(table, map, false)?;
+                }
+                return Ok(());
+            }
+        }
+        _ => {}
+    }
+
+    *item = new_item(value, false)?;
+    Ok(())
+}
+
+/// Bring an inline value in line with `value`, keeping the comments around it
+fn update_value(current: &mut toml_edit::Value, value: &Value) -> ConfigGuardResult<()> {
+    if let Value::Tagged(tagged) = value {
+        return update_value(current, &tagged.value);
+    }
+    match (&mut *current, value) {
+        (toml_edit::Value::InlineTable(table), Value::Mapping(map)) => {
+            return update_table(table, map, true)
+        }
+        (toml_edit::Value::Array(array), Value::Sequence(items)) if array.len() == items.len() => {
+            for (element, value) in array.iter_mut().zip(items) {
+                update_value(element, value)?;
+            }
+            return Ok(());
+        }
+        (current, value) if scalar_value(current).as_ref() == Some(value) => return Ok(()),
+        _ => {}
+    }
+
+    let decor = current.decor().clone();
+    *current = value_to_toml(value)?;
+    *current.decor_mut() = decor;
+    Ok(())
+}
+
+/// Bring a table in line with `map`, removing keys it lacks and appending new ones
+fn update_table(
+    table: &mut dyn toml_edit::TableLike,
+    map: &Mapping,
+    inline: bool,
+) -> ConfigGuardResult<()> {
+    let removed: Vec<String> = table
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !map.contains_key(key.as_str()))
+        .collect();
+    for key in removed {
+        table.remove(&key);
+    }
+
+    for (key, value) in map {
+        let key = toml_key(key)?;
+        match table.get_mut(key) {
+            Some(item) => update_item(item, value)?,
+            None => {
+                table.insert(key, new_item(value, inline)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A TOML item for a value, as a table (or array of tables) unless `inline`
+fn new_item(value: &Value, inline: bool) -> ConfigGuardResult<toml_edit::Item> {
+    match value {
+        Value::Mapping(map) if !inline => {
+            let mut table = toml_edit::Table::new();
+            for (key, value) in map {
+                table.insert(toml_key(key)?, new_item(value, false)?);
+            }
+            Ok(toml_edit::Item::Table(table))
+        }
+        Value::Sequence(items)
+            if !inline && !items.is_empty() && items.iter().all(Value::is_mapping) =>
+        {
+            let mut tables = toml_edit::ArrayOfTables::new();
+            for item in items {
+                if let toml_edit::Item::Table(table) = new_item(item, false)? {
+                    tables.push(table);
+                }
+            }
+            Ok(toml_edit::Item::ArrayOfTables(tables))
+        }
+        Value::Tagged(tagged) => new_item(&tagged.value, inline),
+        _ => Ok(toml_edit::Item::Value(value_to_toml(value)?)),
+    }
+}
+
+/// Convert a value into an inline TOML value
+///
+/// TOML has no null and only string keys, so those cannot be written.
+fn value_to_toml(value: &Value) -> ConfigGuardResult<toml_edit::Value> {
+    Ok(match value {
+        Value::String(s) => toml_edit::Value::from(s.as_str()),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => toml_edit::Value::from(i),
+            None => toml_edit::Value::from(n.as_f64().ok_or_else(|| {
+                ConfigGuardError::Serialization(format!("{} does not fit in TOML", n))
+            })?),
+        },
+        Value::Bool(b) => toml_edit::Value::from(*b),
+        Value::Sequence(items) => toml_edit::Value::Array(
+            items
+                .iter()
+                .map(value_to_toml)
+                .collect::<ConfigGuardResult<_>>()?,
+        ),
+        Value::Mapping(map) => {
+            let mut table = toml_edit::InlineTable::new();
+            for (key, val) in map {
+                table.insert(toml_key(key)?, value_to_toml(val)?);
+            }
+            toml_edit::Value::InlineTable(table)
+        }
+        Value::Null => {
+            return Err(ConfigGuardError::Serialization(
+                "TOML has no null value".to_string(),
+            ))
+        }
+        Value::Tagged(tagged) => value_to_toml(&tagged.value)?,
+    })
+}
+
+fn toml_key(key: &Value) -> ConfigGuardResult<&str> {
+    match key {
+        Value::String(key) => Ok(key),
+        _ => Err(ConfigGuardError::Serialization(format!(
+            "TOML keys must be strings, found {:?}",
+            key
+        ))),
+    }
+}
+
+/// The value a TOML scalar was loaded as, with datetimes as strings
+fn scalar_value(value: &toml_edit::Value) -> Option<Value> {
+    Some(match value {
+        toml_edit::Value::String(s) => Value::String(s.value().clone()),
+        toml_edit::Value::Integer(i) => Value::from(*i.value()),
+        toml_edit::Value::Float(f) => Value::from(*f.value()),
+        toml_edit::Value::Boolean(b) => Value::Bool(*b.value()),
+        toml_edit::Value::Datetime(dt) => Value::String(dt.value().to_string()),
+        toml_edit::Value::Array(_) | toml_edit::Value::InlineTable(_) => return None,
+    })
+}
+
 #[cfg(test)]
+#[allow(clippy::needless_borrows_for_generic_args)]
 mod tests {
     use super::*;
     use std::io::Write;
@@ -121,6 +328,9 @@ mod tests {
         // Valid JSON extension
         assert_eq!(detect_format("config.json").unwrap(), ConfigFormat::Json);
 
+        // Valid TOML extension
+        assert_eq!(detect_format("Config.toml").unwrap(), ConfigFormat::Toml);
+
         // Invalid extension
         let result = detect_format("config.txt");
         assert!(result.is_err());
@@ -181,6 +391,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_load_valid_toml() {
+        let toml = r#"
+            name = "test"
+            ratio = 0.5
+            released = 1979-05-27T07:32:00Z
+
+            [spec]
+            replicas = 3
+
+            [[spec.containers]]
+            name = "app"
+            image = "nginx:latest"
+        "#;
+
+        let config = Config::from_str(toml, ConfigFormat::Toml).unwrap();
+        assert_eq!(config.format, ConfigFormat::Toml);
+
+        let spec = &config.data["spec"];
+        assert!(spec["replicas"].as_u64().is_some());
+        assert!(config.data["ratio"].is_f64());
+        assert_eq!(
+            config.data["released"],
+            Value::String("1979-05-27T07:32:00Z".to_string())
+        );
+        assert_eq!(
+            spec["containers"][0]["image"],
+            Value::String("nginx:latest".to_string())
+        );
+    }
+
+    #[test]
+    fn test_load_invalid_toml() {
+        let result = Config::from_str("name = ", ConfigFormat::Toml);
+        assert!(matches!(result, Err(ConfigGuardError::ParseToml(_))));
+    }
+
     #[test]
     fn test_load_invalid_yaml() {
         let invalid_yaml = "this: is: invalid: yaml:";
@@ -268,6 +515,72 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_render_in_input_format() {
+        let yaml = Config::from_str("name: web\nport: 80\n", ConfigFormat::Yaml).unwrap();
+        assert_eq!(yaml.render().unwrap(), "name: web\nport: 80\n");
+
+        let json = Config::from_str(r#"{"name": "web", "port": 80}"#, ConfigFormat::Json).unwrap();
+        assert_eq!(
+            json.render().unwrap(),
+            "{\n  \"name\": \"web\",\n  \"port\": 80\n}\n"
+        );
+
+        let toml =
+            Config::from_str("name = \"web\"\n\n[tls]\nport = 443\n", ConfigFormat::Toml).unwrap();
+        let rendered = toml.render().unwrap();
+        assert_eq!(
+            Config::from_str(&rendered, ConfigFormat::Toml)
+                .unwrap()
+                .data,
+            toml.data
+        );
+
+        let mut null = toml.clone();
+        null.data["name"] = Value::Null;
+        assert!(matches!(
+            null.render(),
+            Err(ConfigGuardError::Serialization(_))
+        ));
+    }
+
+    #[test]
+    fn test_render_toml_keeps_document() {
+        let content = "\
+# Service settings
+zone = \"eu\"
+created = 1979-05-27T07:32:00Z
+
+[server]
+port = 8080 # public port
+hosts = [\"a\", \"b\"]
+";
+        let mut config = Config::from_str(content, ConfigFormat::Toml).unwrap();
+        config.data["api"] = Value::from("v1");
+        config.data["server"]["workers"] = Value::from(4);
+        config.data["server"]["port"] = Value::from(9090);
+        config.data["tls"] = serde_yaml::from_str("enabled: false").unwrap();
+
+        // Keys keep their order, datetimes stay datetimes and new keys are appended
+        assert_eq!(
+            config.render().unwrap(),
+            "\
+# Service settings
+zone = \"eu\"
+created = 1979-05-27T07:32:00Z
+api = \"v1\"
+
+[server]
+port = 9090 # public port
+hosts = [\"a\", \"b\"]
+workers = 4
+
+[tls]
+enabled = false
+"
+        );
+    }
+
     #[test]
     fn test_nonexistent_file() {
         let result = Config::from_file("nonexistent_file.yaml");
@@ -18,7 +18,6 @@ pub enum ConfigGuardError {
     },
 
     /// Error writing to a file
-    #[allow(dead_code)]
     FileWrite {
         /// Path of the file
         path: String,
@@ -38,6 +37,9 @@ pub enum ConfigGuardError {
     /// Error parsing JSON
     ParseJson(String),
 
+    /// Error parsing TOML
+    ParseToml(String),
+
     /// Unsupported file format
     UnsupportedFormat {
         /// Path of the file
@@ -90,7 +92,6 @@ pub enum ConfigGuardError {
     /// Schema validation error
     Schema(String),
 
-    #[allow(dead_code)]
     /// Internal error
     Internal(String),
 }
@@ -113,6 +114,9 @@ impl fmt::Display for ConfigGuardError {
             ConfigGuardError::ParseJson(msg) => {
                 write!(f, "Failed to parse JSON: {}", msg)
             }
+            ConfigGuardError::ParseToml(msg) => {
+                write!(f, "Failed to parse TOML: {}", msg)
+            }
             ConfigGuardError::UnsupportedFormat { path, extension } => {
                 write!(
                     f,
@@ -187,7 +191,9 @@ impl ConfigGuardError {
         match self {
             ConfigGuardError::FileNotFound { .. } => 2,
             ConfigGuardError::FileRead { .. } | ConfigGuardError::FileWrite { .. } => 3,
-            ConfigGuardError::ParseYaml(_) | ConfigGuardError::ParseJson(_) => 4,
+            ConfigGuardError::ParseYaml(_)
+            | ConfigGuardError::ParseJson(_)
+            | ConfigGuardError::ParseToml(_) => 4,
             ConfigGuardError::UnsupportedFormat { .. } => 5,
             ConfigGuardError::Validation { .. }
             | ConfigGuardError::ValidationErrors { .. }
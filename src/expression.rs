@@ -0,0 +1,875 @@
+use crate::compiled::Numeric;
+use crate::validation::{display_value, value_type_name, values_equal};
+use regex::Regex;
+use serde_yaml::{Number, Value};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A parsed `assert` expression, evaluated against the object it belongs to
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expr {
+    /// A number, string, boolean or `null`
+    Literal(Value),
+
+    /// A field of the object, or `self` (the object) or `it` (the current item of `all`/`any`)
+    Name(String),
+
+    /// Field access, e.g. `spec.replicas`
+    Field(Box<Expr>, String),
+
+    /// List or object indexing, e.g. `brokers[0]` or `self["max-size"]`
+    Index(Box<Expr>, Box<Expr>),
+
+    /// A list literal, e.g. `[a, b]`
+    List(Vec<Expr>),
+
+    /// `!` or unary `-`
+    Unary(UnaryOp, Box<Expr>),
+
+    /// Comparison, arithmetic, `&&` or `||`
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+
+    /// A call of one of the built-in `FUNCTIONS`
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum UnaryOp {
+    Not,
+    Neg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+/// Built-in functions with their minimum and maximum number of arguments
+const FUNCTIONS: &[(&str, usize, usize)] = &[
+    ("len", 1, 1),
+    ("sum", 1, 1),
+    ("min", 1, usize::MAX),
+    ("max", 1, usize::MAX),
+    ("all", 2, 2),
+    ("any", 2, 2),
+    ("lower", 1, 1),
+    ("upper", 1, 1),
+    ("trim", 1, 1),
+    ("starts_with", 2, 2),
+    ("ends_with", 2, 2),
+    ("contains", 2, 2),
+    ("matches", 2, 2),
+];
+
+/// Operators, longest first so `<=` is not read as `<`
+const OPERATORS: &[&str] = &[
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "%", "!", "(", ")", "[", "]",
+    ",", ".",
+];
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            BinaryOp::Or => "||",
+            BinaryOp::And => "&&",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(Value),
+    Str(String),
+    Ident(String),
+    Op(&'static str),
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{}", display_value(n)),
+            Token::Str(s) => write!(f, "{:?}", s),
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Op(op) => write!(f, "'{}'", op),
+            Token::End => write!(f, "end of expression"),
+        }
+    }
+}
+
+/// Parse an expression such as `min_replicas <= max_replicas`
+pub(crate) fn parse(source: &str) -> Result<Expr, String> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        pos: 0,
+        predicate_depth: 0,
+    };
+    let expr = parser.parse_or()?;
+    parser.expect_end()?;
+    Ok(expr)
+}
+
+/// Split an expression into tokens, each with its column (starting at 1)
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let is_float = i + 1 < chars.len() && chars[i] == '.' && chars[i + 1].is_ascii_digit();
+            if is_float {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let number = if is_float {
+                text.parse::<f64>().ok().map(Number::from)
+            } else {
+                text.parse::<i64>()
+                    .map(Number::from)
+                    .or_else(|_| text.parse::<u64>().map(Number::from))
+                    .ok()
+            };
+            let number =
+                number.ok_or_else(|| format!("invalid number '{}' at column {}", text, column))?;
+            tokens.push((Token::Number(Value::Number(number)), column));
+        } else if c == '"' || c == '\'' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(format!("unterminated string at column {}", column)),
+                    Some(&q) if q == c => break,
+                    Some('\\') => {
+                        let escaped = match chars.get(i + 1) {
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            Some(&other) => other,
+                            None => {
+                                return Err(format!("unterminated string at column {}", column))
+                            }
+                        };
+                        text.push(escaped);
+                        i += 2;
+                    }
+                    Some(&other) => {
+                        text.push(other);
+                        i += 1;
+                    }
+                }
+            }
+            i += 1;
+            tokens.push((Token::Str(text), column));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((Token::Ident(chars[start..i].iter().collect()), column));
+        } else {
+            let op = OPERATORS
+                .iter()
+                .find(|op| {
+                    op.chars()
+                        .enumerate()
+                        .all(|(j, o)| chars.get(i + j) == Some(&o))
+                })
+                .ok_or_else(|| format!("unexpected character '{}' at column {}", c, column))?;
+            i += op.chars().count();
+            tokens.push((Token::Op(op), column));
+        }
+    }
+
+    tokens.push((Token::End, chars.len() + 1));
+    Ok(tokens)
+}
+
+/// Recursive descent parser, one method per precedence level
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// Number of enclosing `all`/`any` predicates, where `it` is available
+    predicate_depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn error(&self, expected: &str) -> String {
+        let (token, column) = &self.tokens[self.pos];
+        format!(
+            "expected {} at column {}, found {}",
+            expected, column, token
+        )
+    }
+
+    /// Consume the operator `op` if it is next
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Token::Op(o) if *o == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), String> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("'{}'", op)))
+        }
+    }
+
+    fn expect_end(&self) -> Result<(), String> {
+        match self.peek() {
+            Token::End => Ok(()),
+            _ => Err(self.error("an operator")),
+        }
+    }
+
+    /// Parse a left-associative chain of the given operators
+    fn parse_binary(
+        &mut self,
+        ops: &[(&str, BinaryOp)],
+        operand: fn(&mut Self) -> Result<Expr, String>,
+    ) -> Result<Expr, String> {
+        let mut left = operand(self)?;
+        'chain: loop {
+            for (symbol, op) in ops {
+                if self.eat(symbol) {
+                    let right = operand(self)?;
+                    left = Expr::Binary(*op, Box::new(left), Box::new(right));
+                    continue 'chain;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        self.parse_binary(&[("||", BinaryOp::Or)], Self::parse_and)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        self.parse_binary(&[("&&", BinaryOp::And)], Self::parse_equality)
+    }
+
+    fn parse_equality(&mut self) -> Result<Expr, String> {
+        self.parse_binary(
+            &[("==", BinaryOp::Eq), ("!=", BinaryOp::Ne)],
+            Self::parse_comparison,
+        )
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, String> {
+        self.parse_binary(
+            &[
+                ("<=", BinaryOp::Le),
+                (">=", BinaryOp::Ge),
+                ("<", BinaryOp::Lt),
+                (">", BinaryOp::Gt),
+            ],
+            Self::parse_additive,
+        )
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, String> {
+        self.parse_binary(
+            &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+            Self::parse_multiplicative,
+        )
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, String> {
+        self.parse_binary(
+            &[
+                ("*", BinaryOp::Mul),
+                ("/", BinaryOp::Div),
+                ("%", BinaryOp::Rem),
+            ],
+            Self::parse_unary,
+        )
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        if self.eat("!") {
+            Ok(Expr::Unary(UnaryOp::Not, Box::new(self.parse_unary()?)))
+        } else if self.eat("-") {
+            Ok(Expr::Unary(UnaryOp::Neg, Box::new(self.parse_unary()?)))
+        } else {
+            self.parse_postfix()
+        }
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.eat(".") {
+                let Token::Ident(field) = self.peek().clone() else {
+                    return Err(self.error("a field name"));
+                };
+                self.pos += 1;
+                expr = Expr::Field(Box::new(expr), field);
+            } else if self.eat("[") {
+                let index = self.parse_or()?;
+                self.expect("]")?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        match self.peek().clone() {
+            Token::Number(n) => {
+                self.pos += 1;
+                Ok(Expr::Literal(n))
+            }
+            Token::Str(s) => {
+                self.pos += 1;
+                Ok(Expr::Literal(Value::String(s)))
+            }
+            Token::Ident(name) => {
+                self.pos += 1;
+                match name.as_str() {
+                    "true" => Ok(Expr::Literal(Value::Bool(true))),
+                    "false" => Ok(Expr::Literal(Value::Bool(false))),
+                    "null" => Ok(Expr::Literal(Value::Null)),
+                    _ if self.eat("(") => self.parse_call(name),
+                    "it" if self.predicate_depth == 0 => Err(format!(
+                        "'it' at column {} is only available inside all() and any()",
+                        self.tokens[self.pos - 1].1
+                    )),
+                    _ => Ok(Expr::Name(name)),
+                }
+            }
+            Token::Op("(") => {
+                self.pos += 1;
+                let expr = self.parse_or()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Op("[") => {
+                self.pos += 1;
+                let items = self.parse_arguments("]", |_| false)?;
+                Ok(Expr::List(items))
+            }
+            _ => Err(self.error("a value")),
+        }
+    }
+
+    /// Parse the arguments of a function call, after its opening parenthesis
+    fn parse_call(&mut self, name: String) -> Result<Expr, String> {
+        let Some(&(_, min_args, max_args)) = FUNCTIONS.iter().find(|(f, _, _)| *f == name) else {
+            let names: Vec<&str> = FUNCTIONS.iter().map(|(f, _, _)| *f).collect();
+            return Err(format!(
+                "unknown function '{}' (use one of: {})",
+                name,
+                names.join(", ")
+            ));
+        };
+
+        // The second argument of all() and any() is a predicate evaluated per item
+        let is_predicate = name == "all" || name == "any";
+        let args = self.parse_arguments(")", |i| is_predicate && i == 1)?;
+        if args.len() < min_args || args.len() > max_args {
+            let expected = if min_args == max_args {
+                format!("{}", min_args)
+            } else {
+                format!("at least {}", min_args)
+            };
+            return Err(format!(
+                "{}() takes {} argument{}, found {}",
+                name,
+                expected,
+                if max_args == 1 { "" } else { "s" },
+                args.len()
+            ));
+        }
+
+        if let (true, Some(Expr::Literal(Value::String(pattern)))) =
+            (name == "matches", args.get(1))
+        {
+            Regex::new(pattern)
+                .map_err(|e| format!("invalid regex '{}' in matches(): {}", pattern, e))?;
+        }
+
+        Ok(Expr::Call(name, args))
+    }
+
+    /// Parse comma-separated expressions up to the closing `close` operator
+    fn parse_arguments(
+        &mut self,
+        close: &str,
+        is_predicate: impl Fn(usize) -> bool,
+    ) -> Result<Vec<Expr>, String> {
+        let mut args = Vec::new();
+        if self.eat(close) {
+            return Ok(args);
+        }
+        loop {
+            let predicate = is_predicate(args.len());
+            if predicate {
+                self.predicate_depth += 1;
+            }
+            let arg = self.parse_or();
+            if predicate {
+                self.predicate_depth -= 1;
+            }
+            args.push(arg?);
+            if self.eat(close) {
+                return Ok(args);
+            }
+            self.expect(",")
+                .map_err(|_| self.error(&format!("',' or '{}'", close)))?;
+        }
+    }
+}
+
+/// Values visible to an expression
+struct Scope<'a> {
+    /// The object the assertion belongs to
+    object: &'a Value,
+
+    /// The current item inside an `all`/`any` predicate
+    item: Option<&'a Value>,
+}
+
+impl Expr {
+    /// Evaluate the expression against an object
+    pub(crate) fn evaluate(&self, object: &Value) -> Result<Value, String> {
+        self.eval(&Scope { object, item: None })
+    }
+
+    /// Field paths the expression reads (e.g. `spec.replicas`), with the expression reading each
+    ///
+    /// Used to show the values involved when an assertion fails.
+    pub(crate) fn references(&self) -> Vec<(String, &Expr)> {
+        let mut references = Vec::new();
+        self.collect_references(&mut references);
+        references
+    }
+
+    fn collect_references<'a>(&'a self, references: &mut Vec<(String, &'a Expr)>) {
+        if let Some(path) = self.field_path() {
+            if !references.iter().any(|(p, _)| *p == path) {
+                references.push((path, self));
+            }
+            return;
+        }
+        match self {
+            Expr::Literal(_) | Expr::Name(_) => {}
+            Expr::Field(base, _) => base.collect_references(references),
+            Expr::Index(base, index) => {
+                base.collect_references(references);
+                index.collect_references(references);
+            }
+            Expr::Unary(_, operand) => operand.collect_references(references),
+            Expr::Binary(_, left, right) => {
+                left.collect_references(references);
+                right.collect_references(references);
+            }
+            Expr::List(items) | Expr::Call(_, items) => {
+                for item in items {
+                    item.collect_references(references);
+                }
+            }
+        }
+    }
+
+    /// The field path this expression reads, if it is a plain path like `a.b[0]`
+    fn field_path(&self) -> Option<String> {
+        match self {
+            Expr::Name(name) if name != "it" && name != "self" => Some(name.clone()),
+            Expr::Field(base, field) => base.field_path().map(|p| format!("{}.{}", p, field)),
+            Expr::Index(base, index) => match index.as_ref() {
+                Expr::Literal(Value::Number(n)) => {
+                    base.field_path().map(|p| format!("{}[{}]", p, n))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn eval(&self, scope: &Scope<'_>) -> Result<Value, String> {
+        match self {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Name(name) => match (name.as_str(), scope.item) {
+                ("self", _) => Ok(scope.object.clone()),
+                ("it", Some(item)) => Ok(item.clone()),
+                _ => Ok(scope.object.get(name).cloned().unwrap_or(Value::Null)),
+            },
+            Expr::Field(base, field) => match base.eval(scope)? {
+                Value::Mapping(map) => Ok(map.get(field.as_str()).cloned().unwrap_or(Value::Null)),
+                Value::Null => Ok(Value::Null),
+                other => Err(format!(
+                    "cannot read field '{}' of {}",
+                    field,
+                    value_type_name(&other)
+                )),
+            },
+            Expr::Index(base, index) => {
+                let base = base.eval(scope)?;
+                let index = index.eval(scope)?;
+                match (&base, &index) {
+                    (Value::Sequence(items), Value::Number(n)) => Ok(n
+                        .as_u64()
+                        .and_then(|i| items.get(i as usize))
+                        .cloned()
+                        .unwrap_or(Value::Null)),
+                    (Value::Mapping(map), Value::String(key)) => {
+                        Ok(map.get(key.as_str()).cloned().unwrap_or(Value::Null))
+                    }
+                    (Value::Null, _) => Ok(Value::Null),
+                    _ => Err(format!(
+                        "cannot index {} with {}",
+                        value_type_name(&base),
+                        value_type_name(&index)
+                    )),
+                }
+            }
+            Expr::List(items) => Ok(Value::Sequence(
+                items
+                    .iter()
+                    .map(|item| item.eval(scope))
+                    .collect::<Result<_, _>>()?,
+            )),
+            Expr::Unary(UnaryOp::Not, operand) => {
+                Ok(Value::Bool(!as_bool(&operand.eval(scope)?, "'!'")?))
+            }
+            Expr::Unary(UnaryOp::Neg, operand) => {
+                arithmetic(BinaryOp::Sub, &Value::from(0), &operand.eval(scope)?)
+            }
+            Expr::Binary(BinaryOp::And, left, right) => Ok(Value::Bool(
+                as_bool(&left.eval(scope)?, "'&&'")? && as_bool(&right.eval(scope)?, "'&&'")?,
+            )),
+            Expr::Binary(BinaryOp::Or, left, right) => Ok(Value::Bool(
+                as_bool(&left.eval(scope)?, "'||'")? || as_bool(&right.eval(scope)?, "'||'")?,
+            )),
+            Expr::Binary(op, left, right) => {
+                let left = left.eval(scope)?;
+                let right = right.eval(scope)?;
+                binary(*op, &left, &right)
+            }
+            Expr::Call(name, args) => call(name, args, scope),
+        }
+    }
+}
+
+fn as_bool(value: &Value, operator: &str) -> Result<bool, String> {
+    match value {
+        Value::Bool(b) => Ok(*b),
+        other => Err(format!(
+            "{} expects a boolean, found {}",
+            operator,
+            value_type_name(other)
+        )),
+    }
+}
+
+fn binary(op: BinaryOp, left: &Value, right: &Value) -> Result<Value, String> {
+    let ordering = |expected: &[Ordering]| {
+        compare(left, right)
+            .map(|ordering| Value::Bool(expected.contains(&ordering)))
+            .ok_or_else(|| {
+                format!(
+                    "cannot compare {} and {} with '{}'",
+                    value_type_name(left),
+                    value_type_name(right),
+                    op
+                )
+            })
+    };
+    match op {
+        BinaryOp::Eq => Ok(Value::Bool(values_equal(left, right, false))),
+        BinaryOp::Ne => Ok(Value::Bool(!values_equal(left, right, false))),
+        BinaryOp::Lt => ordering(&[Ordering::Less]),
+        BinaryOp::Le => ordering(&[Ordering::Less, Ordering::Equal]),
+        BinaryOp::Gt => ordering(&[Ordering::Greater]),
+        BinaryOp::Ge => ordering(&[Ordering::Greater, Ordering::Equal]),
+        BinaryOp::Add => match (left, right) {
+            (Value::String(a), Value::String(b)) => Ok(Value::String(format!("{}{}", a, b))),
+            (Value::Sequence(a), Value::Sequence(b)) => {
+                Ok(Value::Sequence(a.iter().chain(b).cloned().collect()))
+            }
+            _ => arithmetic(op, left, right),
+        },
+        _ => arithmetic(op, left, right),
+    }
+}
+
+/// Order two numbers or two strings
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => Numeric::from_value(left)?.compare(Numeric::from_value(right)?),
+    }
+}
+
+/// Apply an arithmetic operator, keeping integer results exact
+fn arithmetic(op: BinaryOp, left: &Value, right: &Value) -> Result<Value, String> {
+    let (Some(a), Some(b)) = (Numeric::from_value(left), Numeric::from_value(right)) else {
+        return Err(format!(
+            "'{}' expects numbers, found {} and {}",
+            op,
+            value_type_name(left),
+            value_type_name(right)
+        ));
+    };
+
+    if let (Numeric::Int(x), Numeric::Int(y)) = (a, b) {
+        let exact = match op {
+            BinaryOp::Add => x.checked_add(y),
+            BinaryOp::Sub => x.checked_sub(y),
+            BinaryOp::Mul => x.checked_mul(y),
+            BinaryOp::Div => x.checked_rem(y).filter(|r| *r == 0).map(|_| x / y),
+            BinaryOp::Rem => x.checked_rem(y),
+            _ => None,
+        };
+        if let Some(value) = exact.and_then(integer_value) {
+            return Ok(value);
+        }
+    }
+
+    let (x, y) = (a.as_f64(), b.as_f64());
+    if matches!(op, BinaryOp::Div | BinaryOp::Rem) && y == 0.0 {
+        return Err("division by zero".to_string());
+    }
+    let result = match op {
+        BinaryOp::Add => x + y,
+        BinaryOp::Sub => x - y,
+        BinaryOp::Mul => x * y,
+        BinaryOp::Div => x / y,
+        BinaryOp::Rem => x % y,
+        _ => unreachable!("not an arithmetic operator: {}", op),
+    };
+    Ok(Value::Number(Number::from(result)))
+}
+
+fn integer_value(n: i128) -> Option<Value> {
+    i64::try_from(n)
+        .map(Value::from)
+        .or_else(|_| u64::try_from(n).map(Value::from))
+        .ok()
+}
+
+/// Evaluate a call of a built-in function
+fn call(name: &str, args: &[Expr], scope: &Scope<'_>) -> Result<Value, String> {
+    // all() and any() evaluate their predicate once per item, with `it` bound to the item
+    if name == "all" || name == "any" {
+        let list = args[0].eval(scope)?;
+        let Value::Sequence(items) = &list else {
+            return Err(format!(
+                "{}() expects a list, found {}",
+                name,
+                value_type_name(&list)
+            ));
+        };
+        let want = name == "any";
+        for item in items {
+            let item_scope = Scope {
+                object: scope.object,
+                item: Some(item),
+            };
+            let matched = as_bool(&args[1].eval(&item_scope)?, &format!("{}()", name))?;
+            if matched == want {
+                return Ok(Value::Bool(want));
+            }
+        }
+        return Ok(Value::Bool(!want));
+    }
+
+    let values: Vec<Value> = args
+        .iter()
+        .map(|arg| arg.eval(scope))
+        .collect::<Result<_, _>>()?;
+    let unexpected = || {
+        let types: Vec<String> = values.iter().map(value_type_name).collect();
+        format!("{}() does not accept {}", name, types.join(" and "))
+    };
+
+    match (name, values.as_slice()) {
+        ("len", [Value::String(s)]) => Ok(Value::from(s.chars().count())),
+        ("len", [Value::Sequence(items)]) => Ok(Value::from(items.len())),
+        ("len", [Value::Mapping(map)]) => Ok(Value::from(map.len())),
+        ("sum", [Value::Sequence(items)]) => {
+            items.iter().try_fold(Value::from(0), |total, item| {
+                arithmetic(BinaryOp::Add, &total, item)
+            })
+        }
+        ("min" | "max", _) => {
+            let items = match values.as_slice() {
+                [Value::Sequence(items)] => items.as_slice(),
+                _ => values.as_slice(),
+            };
+            let wanted = if name == "min" {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            };
+            let mut best = items
+                .first()
+                .ok_or_else(|| format!("{}() of an empty list", name))?;
+            for item in &items[1..] {
+                let ordering = compare(item, best).ok_or_else(|| {
+                    format!(
+                        "{}() cannot compare {} and {}",
+                        name,
+                        value_type_name(item),
+                        value_type_name(best)
+                    )
+                })?;
+                if ordering == wanted {
+                    best = item;
+                }
+            }
+            Ok(best.clone())
+        }
+        ("lower", [Value::String(s)]) => Ok(Value::String(s.to_lowercase())),
+        ("upper", [Value::String(s)]) => Ok(Value::String(s.to_uppercase())),
+        ("trim", [Value::String(s)]) => Ok(Value::String(s.trim().to_string())),
+        ("starts_with", [Value::String(s), Value::String(prefix)]) => {
+            Ok(Value::Bool(s.starts_with(prefix.as_str())))
+        }
+        ("ends_with", [Value::String(s), Value::String(suffix)]) => {
+            Ok(Value::Bool(s.ends_with(suffix.as_str())))
+        }
+        ("contains", [Value::String(s), Value::String(part)]) => {
+            Ok(Value::Bool(s.contains(part.as_str())))
+        }
+        ("contains", [Value::Sequence(items), needle]) => Ok(Value::Bool(
+            items.iter().any(|item| values_equal(item, needle, false)),
+        )),
+        ("contains", [Value::Mapping(map), Value::String(key)]) => {
+            Ok(Value::Bool(map.contains_key(key.as_str())))
+        }
+        ("matches", [Value::String(s), Value::String(pattern)]) => Regex::new(pattern)
+            .map(|regex| Value::Bool(regex.is_match(s)))
+            .map_err(|e| format!("invalid regex '{}' in matches(): {}", pattern, e)),
+        _ => Err(unexpected()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str, object: &str) -> Result<Value, String> {
+        parse(source)?.evaluate(&serde_yaml::from_str(object).unwrap())
+    }
+
+    #[test]
+    fn test_evaluate_expressions() {
+        let object = r#"
+        min_replicas: 2
+        max_replicas: 5
+        weights: [20, 30, 50]
+        brokers: [a, b, c]
+        name: Web-Frontend
+        backends:
+          - { name: api, port: 8080 }
+          - { name: db, port: 5432 }
+        "#;
+        for source in [
+            "min_replicas <= max_replicas",
+            "sum(weights) == 100",
+            "len(brokers) >= 3 && len(name) == 12",
+            "max_replicas - min_replicas * 2 == 1",
+            "max_replicas / 2 == 2.5 && max_replicas % 2 == 1",
+            "-min_replicas < 0 && !(min_replicas > max_replicas)",
+            "all(backends, it.port > 1024) && any(backends, it.name == 'db')",
+            "backends[1].name == \"db\" && backends[5] == null && missing.field == null",
+            "lower(name) == 'web-frontend' && starts_with(upper(name), 'WEB')",
+            "contains(name, 'Front') && contains(brokers, 'b') && contains(self, 'weights')",
+            "matches(name, '^[A-Z]') && ends_with(trim('  x '), 'x')",
+            "min(weights) == 20 && max(1, 7, 3) == 7 && [1, 2] + [3] == [1, 2, 3]",
+            "self['max_replicas'] == 5 || missing_key",
+        ] {
+            assert_eq!(eval(source, object), Ok(Value::Bool(true)), "{}", source);
+        }
+
+        assert_eq!(eval("1 / 0", object), Err("division by zero".to_string()));
+        assert_eq!(
+            eval("len(min_replicas)", object),
+            Err("len() does not accept integer".to_string())
+        );
+        assert_eq!(
+            eval("name < 3", object),
+            Err("cannot compare string and integer with '<'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (source, expected) in [
+            (
+                "a <",
+                "expected a value at column 4, found end of expression",
+            ),
+            ("(a", "expected ')' at column 3, found end of expression"),
+            ("a b", "expected an operator at column 3, found 'b'"),
+            ("size(a)", "unknown function 'size'"),
+            ("len(a, b)", "len() takes 1 argument, found 2"),
+            (
+                "it.port > 0",
+                "'it' at column 1 is only available inside all() and any()",
+            ),
+            ("'open", "unterminated string at column 1"),
+            ("a # b", "unexpected character '#' at column 3"),
+            ("matches(a, '[')", "invalid regex '['"),
+        ] {
+            let err = parse(source).unwrap_err();
+            assert!(err.contains(expected), "{}: {}", source, err);
+        }
+    }
+
+    #[test]
+    fn test_references() {
+        let expr = parse("len(brokers) >= spec.replicas && all(items, it > brokers[0])").unwrap();
+        let paths: Vec<String> = expr.references().into_iter().map(|(p, _)| p).collect();
+        assert_eq!(
+            paths,
+            vec!["brokers", "spec.replicas", "items", "brokers[0]"]
+        );
+    }
+}
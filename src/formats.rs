@@ -0,0 +1,700 @@
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A well-known string format that can be checked with the `format` keyword
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum StringFormat {
+    /// RFC 1123 host name, e.g. `db-1.internal.example.com`
+    #[serde(rename = "hostname")]
+    Hostname,
+
+    /// Dotted-quad IPv4 address
+    #[serde(rename = "ipv4")]
+    Ipv4,
+
+    /// IPv6 address
+    #[serde(rename = "ipv6")]
+    Ipv6,
+
+    /// IPv4 or IPv6 address
+    #[serde(rename = "ip")]
+    Ip,
+
+    /// IPv4 or IPv6 network in CIDR notation, e.g. `10.0.0.0/8`
+    #[serde(rename = "cidr")]
+    Cidr,
+
+    /// RFC 3986 URI with a scheme, e.g. `https://example.com/path`
+    #[serde(rename = "uri")]
+    Uri,
+
+    /// Email address
+    #[serde(rename = "email")]
+    Email,
+
+    /// UUID in its 8-4-4-4-12 hex form
+    #[serde(rename = "uuid")]
+    Uuid,
+
+    /// Semantic version, e.g. `1.4.0-rc.1`
+    #[serde(rename = "semver")]
+    Semver,
+
+    /// RFC 3339 date-time, e.g. `2024-05-01T12:00:00Z`
+    #[serde(rename = "date-time")]
+    DateTime,
+
+    /// Kubernetes-style DNS-1123 label, e.g. `my-app`
+    #[serde(rename = "dns-1123-label")]
+    Dns1123Label,
+
+    /// Kubernetes-style DNS-1123 subdomain, e.g. `my-app.example.com`
+    #[serde(rename = "dns-1123-subdomain")]
+    Dns1123Subdomain,
+}
+
+impl StringFormat {
+    /// Human-readable name of the format, used in error messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            StringFormat::Hostname => "hostname",
+            StringFormat::Ipv4 => "IPv4 address",
+            StringFormat::Ipv6 => "IPv6 address",
+            StringFormat::Ip => "IP address",
+            StringFormat::Cidr => "CIDR network",
+            StringFormat::Uri => "URI",
+            StringFormat::Email => "email address",
+            StringFormat::Uuid => "UUID",
+            StringFormat::Semver => "semantic version",
+            StringFormat::DateTime => "RFC 3339 date-time",
+            StringFormat::Dns1123Label => "DNS-1123 label",
+            StringFormat::Dns1123Subdomain => "DNS-1123 subdomain",
+        }
+    }
+
+    /// An example of a valid value
+    pub fn example(&self) -> &'static str {
+        match self {
+            StringFormat::Hostname => "db-1.example.com",
+            StringFormat::Ipv4 => "192.168.0.1",
+            StringFormat::Ipv6 => "2001:db8::1",
+            StringFormat::Ip => "192.168.0.1 or 2001:db8::1",
+            StringFormat::Cidr => "10.0.0.0/8",
+            StringFormat::Uri => "https://example.com/path",
+            StringFormat::Email => "ops@example.com",
+            StringFormat::Uuid => "123e4567-e89b-12d3-a456-426614174000",
+            StringFormat::Semver => "1.4.0-rc.1",
+            StringFormat::DateTime => "2024-05-01T12:00:00Z",
+            StringFormat::Dns1123Label => "my-app",
+            StringFormat::Dns1123Subdomain => "my-app.example.com",
+        }
+    }
+
+    /// Check a value, explaining what is wrong with it on failure
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        match self {
+            StringFormat::Hostname => check_hostname(value),
+            StringFormat::Ipv4 => check_ipv4(value),
+            StringFormat::Ipv6 => check_ipv6(value),
+            StringFormat::Ip => {
+                if value.contains(':') {
+                    check_ipv6(value)
+                } else {
+                    check_ipv4(value)
+                }
+            }
+            StringFormat::Cidr => check_cidr(value),
+            StringFormat::Uri => check_uri(value).map(|_| ()),
+            StringFormat::Email => check_email(value),
+            StringFormat::Uuid => check_uuid(value),
+            StringFormat::Semver => check_semver(value),
+            StringFormat::DateTime => check_date_time(value),
+            StringFormat::Dns1123Label => check_dns1123_label(value),
+            StringFormat::Dns1123Subdomain => check_dns1123_subdomain(value),
+        }
+    }
+}
+
+/// Extract the scheme of a valid URI
+pub(crate) fn uri_scheme(value: &str) -> Option<&str> {
+    check_uri(value).ok()
+}
+
+fn check_hostname(value: &str) -> Result<(), String> {
+    let name = value.strip_suffix('.').unwrap_or(value);
+    if name.is_empty() {
+        return Err("host name is empty".to_string());
+    }
+    if name.len() > 253 {
+        return Err(format!("{} characters is longer than 253", name.len()));
+    }
+
+    for label in name.split('.') {
+        if label.is_empty() {
+            return Err("contains an empty label".to_string());
+        }
+        if label.len() > 63 {
+            return Err(format!("label '{}' is longer than 63 characters", label));
+        }
+        if let Some(c) = label
+            .chars()
+            .find(|c| !c.is_ascii_alphanumeric() && *c != '-')
+        {
+            return Err(format!(
+                "label '{}' contains invalid character '{}'",
+                label, c
+            ));
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err(format!("label '{}' starts or ends with '-'", label));
+        }
+    }
+
+    Ok(())
+}
+
+fn check_dns1123_label(value: &str) -> Result<(), String> {
+    if value.is_empty() {
+        return Err("label is empty".to_string());
+    }
+    if value.len() > 63 {
+        return Err(format!("{} characters is longer than 63", value.len()));
+    }
+    if let Some(c) = value
+        .chars()
+        .find(|c| !c.is_ascii_lowercase() && !c.is_ascii_digit() && *c != '-')
+    {
+        return Err(format!(
+            "invalid character '{}' (only lowercase letters, digits and '-' are allowed)",
+            c
+        ));
+    }
+    if value.starts_with('-') || value.ends_with('-') {
+        return Err("must start and end with a letter or digit".to_string());
+    }
+
+    Ok(())
+}
+
+fn check_dns1123_subdomain(value: &str) -> Result<(), String> {
+    if value.len() > 253 {
+        return Err(format!("{} characters is longer than 253", value.len()));
+    }
+
+    for label in value.split('.') {
+        check_dns1123_label(label).map_err(|reason| format!("label '{}': {}", label, reason))?;
+    }
+
+    Ok(())
+}
+
+fn check_ipv4(value: &str) -> Result<(), String> {
+    let octets: Vec<&str> = value.split('.').collect();
+    if octets.len() != 4 {
+        return Err(format!(
+            "expected 4 dot-separated octets, found {}",
+            octets.len()
+        ));
+    }
+
+    for octet in &octets {
+        if octet.is_empty() || !octet.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("octet '{}' is not a number", octet));
+        }
+        if octet.len() > 1 && octet.starts_with('0') {
+            return Err(format!("octet '{}' has a leading zero", octet));
+        }
+        if octet.parse::<u16>().map_or(true, |n| n > 255) {
+            return Err(format!("octet {} is out of range 0-255", octet));
+        }
+    }
+
+    value
+        .parse::<Ipv4Addr>()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn check_ipv6(value: &str) -> Result<(), String> {
+    if value.matches("::").count() > 1 {
+        return Err("'::' may only appear once".to_string());
+    }
+
+    value
+        .parse::<Ipv6Addr>()
+        .map(|_| ())
+        .map_err(|_| "not a valid IPv6 address".to_string())
+}
+
+fn check_cidr(value: &str) -> Result<(), String> {
+    let Some((address, prefix)) = value.split_once('/') else {
+        return Err("missing '/<prefix length>'".to_string());
+    };
+
+    let (max_prefix, family) = if address.contains(':') {
+        check_ipv6(address)?;
+        (128, "IPv6")
+    } else {
+        check_ipv4(address)?;
+        (32, "IPv4")
+    };
+
+    if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("prefix length '{}' is not a number", prefix));
+    }
+    match prefix.parse::<u32>() {
+        Ok(length) if length <= max_prefix => Ok(()),
+        _ => Err(format!(
+            "prefix length {} is out of range 0-{} for {}",
+            prefix, max_prefix, family
+        )),
+    }
+}
+
+/// Check a URI and return its scheme
+fn check_uri(value: &str) -> Result<&str, String> {
+    let Some((scheme, rest)) = value.split_once(':') else {
+        return Err("missing scheme (e.g. 'https:')".to_string());
+    };
+
+    let mut scheme_chars = scheme.chars();
+    let valid_scheme = scheme_chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && scheme_chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !valid_scheme {
+        return Err(format!("invalid scheme '{}'", scheme));
+    }
+
+    for (i, c) in rest.char_indices() {
+        if c == '%' {
+            let escape = rest.get(i + 1..i + 3).unwrap_or("");
+            if escape.len() != 2 || !escape.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err("'%' must be followed by two hex digits".to_string());
+            }
+        } else if !c.is_ascii_alphanumeric() && !"-._~:/?#[]@!$&'()*+,;=".contains(c) {
+            return Err(format!("invalid character '{}'", c));
+        }
+    }
+
+    // With an authority, the host must be present (except for `file:`) and the port numeric
+    if let Some(after_slashes) = rest.strip_prefix("//") {
+        let authority = after_slashes
+            .split(['/', '?', '#'])
+            .next()
+            .unwrap_or_default();
+        let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+        let (host, port) = if host_port.starts_with('[') {
+            match host_port.split_once(']') {
+                Some((host, port)) => (&host[1..], port.strip_prefix(':')),
+                None => return Err("unterminated IPv6 host".to_string()),
+            }
+        } else {
+            match host_port.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (host_port, None),
+            }
+        };
+
+        if host.is_empty() && !scheme.eq_ignore_ascii_case("file") {
+            return Err("missing host".to_string());
+        }
+        if let Some(port) = port {
+            if !port.chars().all(|c| c.is_ascii_digit()) {
+                return Err(format!("port '{}' is not a number", port));
+            }
+        }
+    }
+
+    Ok(scheme)
+}
+
+fn check_email(value: &str) -> Result<(), String> {
+    let Some((local, domain)) = value.rsplit_once('@') else {
+        return Err("missing '@'".to_string());
+    };
+
+    if local.is_empty() {
+        return Err("missing the part before '@'".to_string());
+    }
+    if local.len() > 64 {
+        return Err(format!(
+            "{} characters before '@' is longer than 64",
+            local.len()
+        ));
+    }
+    if local.starts_with('.') || local.ends_with('.') || local.contains("..") {
+        return Err("dots before '@' must separate non-empty parts".to_string());
+    }
+    if let Some(c) = local
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && !"!#$%&'*+-/=?^_`{|}~.".contains(*c))
+    {
+        return Err(format!("invalid character '{}' before '@'", c));
+    }
+
+    check_hostname(domain).map_err(|reason| format!("invalid domain: {}", reason))
+}
+
+fn check_uuid(value: &str) -> Result<(), String> {
+    let groups: Vec<&str> = value.split('-').collect();
+    let lengths: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+    if lengths != [8, 4, 4, 4, 12] {
+        return Err("expected 8-4-4-4-12 hex digits".to_string());
+    }
+    if let Some(c) = value.chars().find(|c| !c.is_ascii_hexdigit() && *c != '-') {
+        return Err(format!("invalid hex digit '{}'", c));
+    }
+
+    Ok(())
+}
+
+fn check_semver(value: &str) -> Result<(), String> {
+    if value.starts_with(['v', 'V']) {
+        return Err("the 'v' prefix is not part of a semantic version".to_string());
+    }
+
+    let (version, build) = match value.split_once('+') {
+        Some((version, build)) => (version, Some(build)),
+        None => (value, None),
+    };
+    let (core, pre_release) = match version.split_once('-') {
+        Some((core, pre_release)) => (core, Some(pre_release)),
+        None => (version, None),
+    };
+
+    let parts: Vec<&str> = core.split('.').collect();
+    if parts.len() != 3 {
+        return Err("expected MAJOR.MINOR.PATCH".to_string());
+    }
+    for part in parts {
+        check_numeric_identifier(part)?;
+    }
+
+    if let Some(pre_release) = pre_release {
+        for identifier in pre_release.split('.') {
+            check_identifier(identifier, "pre-release")?;
+            if identifier.chars().all(|c| c.is_ascii_digit()) {
+                check_numeric_identifier(identifier)?;
+            }
+        }
+    }
+    if let Some(build) = build {
+        for identifier in build.split('.') {
+            check_identifier(identifier, "build metadata")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn check_numeric_identifier(part: &str) -> Result<(), String> {
+    if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("'{}' is not a number", part));
+    }
+    if part.len() > 1 && part.starts_with('0') {
+        return Err(format!("'{}' has a leading zero", part));
+    }
+    Ok(())
+}
+
+fn check_identifier(identifier: &str, kind: &str) -> Result<(), String> {
+    if identifier.is_empty() {
+        return Err(format!("empty {} identifier", kind));
+    }
+    if let Some(c) = identifier
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && *c != '-')
+    {
+        return Err(format!("invalid character '{}' in {}", c, kind));
+    }
+    Ok(())
+}
+
+fn check_date_time(value: &str) -> Result<(), String> {
+    let Some(separator) = value.find(['T', 't', ' ']) else {
+        return Err("missing 'T' between date and time".to_string());
+    };
+    let (date, time) = (&value[..separator], &value[separator + 1..]);
+
+    check_date(date)?;
+
+    // Split off the UTC offset: `Z` or `+HH:MM`/`-HH:MM`
+    let (clock, offset) = if let Some(clock) = time.strip_suffix(['Z', 'z']) {
+        (clock, None)
+    } else if let Some(position) = time.rfind(['+', '-']) {
+        (&time[..position], Some(&time[position + 1..]))
+    } else {
+        return Err("missing time zone offset ('Z' or '+HH:MM')".to_string());
+    };
+
+    let (clock, fraction) = match clock.split_once('.') {
+        Some((clock, fraction)) => (clock, Some(fraction)),
+        None => (clock, None),
+    };
+    let [hour, minute, second] = fixed_fields(clock, ':', [2, 2, 2])
+        .ok_or_else(|| format!("time '{}' is not in HH:MM:SS form", clock))?;
+    if hour > 23 || minute > 59 || second > 60 {
+        return Err(format!("time {} is out of range", clock));
+    }
+    if let Some(fraction) = fraction {
+        if fraction.is_empty() || !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("fractional seconds '{}' are not digits", fraction));
+        }
+    }
+
+    if let Some(offset) = offset {
+        let [offset_hour, offset_minute] = fixed_fields(offset, ':', [2, 2])
+            .ok_or_else(|| format!("offset '{}' is not in HH:MM form", offset))?;
+        if offset_hour > 23 || offset_minute > 59 {
+            return Err(format!("offset {} is out of range", offset));
+        }
+    }
+
+    Ok(())
+}
+
+/// Check a calendar date in `YYYY-MM-DD` form
+pub(crate) fn check_date(date: &str) -> Result<(), String> {
+    let [year, month, day] = fixed_fields(date, '-', [4, 2, 2])
+        .ok_or_else(|| format!("date '{}' is not in YYYY-MM-DD form", date))?;
+    if !(1..=12).contains(&month) {
+        return Err(format!("month {:02} is out of range 01-12", month));
+    }
+    let days_in_month = match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    if day < 1 || day > days_in_month {
+        return Err(format!(
+            "day {:02} is out of range 01-{} for {:04}-{:02}",
+            day, days_in_month, year, month
+        ));
+    }
+
+    Ok(())
+}
+
+/// Today's date (UTC) in `YYYY-MM-DD` form
+pub(crate) fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Convert days since 1970-01-01 to a (year, month, day) date
+///
+/// This is Howard Hinnant's `civil_from_days` algorithm for the proleptic
+/// Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Split a value into fixed-width numeric fields, e.g. `2024-05-01` into `[2024, 5, 1]`
+fn fixed_fields<const N: usize>(
+    value: &str,
+    separator: char,
+    widths: [usize; N],
+) -> Option<[u32; N]> {
+    let parts: Vec<&str> = value.split(separator).collect();
+    if parts.len() != N {
+        return None;
+    }
+
+    let mut fields = [0; N];
+    for ((field, part), width) in fields.iter_mut().zip(parts).zip(widths) {
+        if part.len() != width || !part.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        *field = part.parse().ok()?;
+    }
+    Some(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_valid(format: StringFormat, values: &[&str]) {
+        for value in values {
+            assert_eq!(
+                format.check(value),
+                Ok(()),
+                "{:?} should accept {}",
+                format,
+                value
+            );
+        }
+    }
+
+    fn assert_invalid(format: StringFormat, values: &[&str]) {
+        for value in values {
+            assert!(
+                format.check(value).is_err(),
+                "{:?} should reject {}",
+                format,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_network_formats() {
+        assert_valid(
+            StringFormat::Hostname,
+            &["localhost", "db-1.example.com", "example.com."],
+        );
+        assert_invalid(
+            StringFormat::Hostname,
+            &["", "-db.example.com", "db..example", "db_1"],
+        );
+
+        assert_valid(
+            StringFormat::Ipv4,
+            &["0.0.0.0", "192.168.0.1", "255.255.255.255"],
+        );
+        assert_invalid(
+            StringFormat::Ipv4,
+            &["256.0.0.1", "10.0.0", "01.2.3.4", "a.b.c.d"],
+        );
+
+        assert_valid(StringFormat::Ipv6, &["::1", "2001:db8::1", "fe80::1:2:3:4"]);
+        assert_invalid(
+            StringFormat::Ipv6,
+            &["2001:db8::1::2", "12345::", "1.2.3.4"],
+        );
+
+        assert_valid(
+            StringFormat::Cidr,
+            &["10.0.0.0/8", "0.0.0.0/0", "2001:db8::/32"],
+        );
+        assert_invalid(
+            StringFormat::Cidr,
+            &["10.0.0.0", "10.0.0.0/33", "::/129", "10.0.0.0/x"],
+        );
+
+        assert_valid(StringFormat::Dns1123Label, &["my-app", "a", "app1"]);
+        assert_invalid(
+            StringFormat::Dns1123Label,
+            &["My-App", "-app", "app.example"],
+        );
+        assert_valid(StringFormat::Dns1123Subdomain, &["my-app.example.com"]);
+        assert_invalid(
+            StringFormat::Dns1123Subdomain,
+            &["my_app.example.com", "a..b"],
+        );
+    }
+
+    #[test]
+    fn test_uri_and_email_formats() {
+        assert_valid(
+            StringFormat::Uri,
+            &[
+                "https://example.com/path?q=1#top",
+                "postgres://user:pw@db:5432/app",
+                "http://[::1]:8080/",
+                "file:///etc/hosts",
+                "mailto:ops@example.com",
+            ],
+        );
+        assert_invalid(
+            StringFormat::Uri,
+            &[
+                "example.com",
+                "https://exa mple.com",
+                "http://:80/",
+                "http://host:port/",
+                "1http://x",
+            ],
+        );
+        assert_eq!(uri_scheme("HTTPS://example.com"), Some("HTTPS"));
+
+        assert_valid(
+            StringFormat::Email,
+            &["ops@example.com", "first.last+tag@mail.example.org"],
+        );
+        assert_invalid(
+            StringFormat::Email,
+            &["ops", "@example.com", "a..b@example.com", "ops@-bad.com"],
+        );
+    }
+
+    #[test]
+    fn test_identifier_formats() {
+        assert_valid(
+            StringFormat::Uuid,
+            &["123e4567-e89b-12d3-a456-426614174000"],
+        );
+        assert_invalid(
+            StringFormat::Uuid,
+            &[
+                "123e4567e89b12d3a456426614174000",
+                "123e4567-e89b-12d3-a456-42661417400g",
+            ],
+        );
+
+        assert_valid(
+            StringFormat::Semver,
+            &["1.0.0", "0.10.2-rc.1", "1.2.3-alpha.1+build.5"],
+        );
+        assert_invalid(
+            StringFormat::Semver,
+            &["v1.0.0", "1.0", "01.0.0", "1.0.0-01", "1.0.0+"],
+        );
+        assert_eq!(
+            StringFormat::Semver.check("v1.2.3"),
+            Err("the 'v' prefix is not part of a semantic version".to_string())
+        );
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19723), (2024, 1, 1));
+        assert!(check_date(&today()).is_ok());
+    }
+
+    #[test]
+    fn test_date_time_format() {
+        assert_valid(
+            StringFormat::DateTime,
+            &[
+                "2024-05-01T12:00:00Z",
+                "2024-02-29T23:59:60.123+05:30",
+                "1979-05-27 07:32:00-08:00",
+            ],
+        );
+        assert_invalid(
+            StringFormat::DateTime,
+            &[
+                "2024-05-01",
+                "2023-02-29T00:00:00Z",
+                "2024-13-01T00:00:00Z",
+                "2024-05-01T24:00:00Z",
+                "2024-05-01T12:00:00",
+            ],
+        );
+        assert_eq!(
+            StringFormat::DateTime.check("2023-02-29T00:00:00Z"),
+            Err("day 29 is out of range 01-28 for 2023-02".to_string())
+        );
+    }
+}
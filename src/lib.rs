@@ -0,0 +1,42 @@
+//! ConfigGuard validates YAML, JSON and TOML configuration files against a
+//! user-defined schema.
+//!
+//! The same rules used by the `configguard` CLI are available in-process:
+//!
+//! ```no_run
+//! use configguard::{ConfigFormat, Validator};
+//!
+//! let validator = Validator::from_schema_file("schema.yaml")?.strict(true);
+//! let report = validator.validate_str("name: web", ConfigFormat::Yaml)?;
+//!
+//! for error in &report.errors {
+//!     eprintln!("{}", error);
+//! }
+//! # Ok::<(), configguard::ConfigGuardError>(())
+//! ```
+
+pub mod compiled;
+pub mod config;
+pub mod error;
+mod expression;
+pub mod formats;
+mod quantity;
+pub mod reporting;
+pub mod schema;
+pub mod source;
+pub mod validation;
+
+pub use compiled::CompiledSchema;
+pub use config::{Config, ConfigFormat};
+pub use error::{ConfigGuardError, ConfigGuardResult};
+pub use formats::StringFormat;
+pub use reporting::{
+    format_validation_report, format_validation_result, validation_report_json, ReportFormat,
+};
+pub use schema::{
+    AdditionalItems, Assertion, CheckSeverity, Discriminator, Schema, SchemaRule, SchemaType,
+};
+pub use source::{Position, SourceMap, Span};
+#[allow(deprecated)]
+pub use validation::validate;
+pub use validation::{Severity, ValidationError, ValidationReport, ValidationResult, Validator};
@@ -1,13 +1,7 @@
-use crate::error::ConfigGuardError;
-use crate::reporting::ReportFormat;
+use configguard::{format_validation_result, ConfigGuardError, ReportFormat, ValidationResult};
 use std::process;
 
 mod cli;
-mod config;
-mod error;
-mod reporting;
-mod schema;
-mod validation;
 
 fn main() {
     // Parse command-line arguments
@@ -24,6 +18,9 @@ fn main() {
         cli::RunResult::Success => {
             process::exit(0);
         }
+        cli::RunResult::Reported(err) => {
+            process::exit(err.exit_code());
+        }
         cli::RunResult::Failure(err) => {
             // If JSON format is requested, output errors in JSON
             if let Some(ReportFormat::Json) = output_format {
@@ -31,26 +28,11 @@ fn main() {
                 match &err {
                     // Direct access to all validation errors - preferred path
                     ConfigGuardError::AllValidationErrors { errors } => {
-                        let json_errors: Vec<serde_json::Value> = errors
-                            .iter()
-                            .map(|e| {
-                                serde_json::json!({
-                                    "path": e.path,
-                                    "message": e.message,
-                                    "expected": e.expected,
-                                    "actual": e.actual,
-                                    "description": e.description,
-                                    "line": e.line
-                                })
-                            })
-                            .collect();
-
-                        let json_error = serde_json::json!({
-                            "valid": false,
-                            "error_count": errors.len(),
-                            "errors": json_errors
-                        });
-                        println!("{}", serde_json::to_string_pretty(&json_error).unwrap());
+                        let result = ValidationResult::Invalid(errors.clone());
+                        match format_validation_result(&result, &ReportFormat::Json) {
+                            Ok(report) => println!("{}", report),
+                            Err(report_err) => eprintln!("Error: {}", report_err),
+                        }
                     }
                     // Other error types
                     _ => {
@@ -83,33 +65,11 @@ fn main() {
             } else {
                 // For text output, print the error message
                 if let ConfigGuardError::AllValidationErrors { errors } = &err {
-                    // Print all validation errors in text format
-                    eprintln!("Error: {} validation errors found:", errors.len());
-                    for (i, error) in errors.iter().enumerate() {
-                        if let Some(line) = error.line {
-                            eprintln!(
-                                "{}. Error at path '{}' (line {}): {}",
-                                i + 1,
-                                error.path,
-                                line,
-                                error.message
-                            );
-                        } else {
-                            eprintln!(
-                                "{}. Error at path '{}': {}",
-                                i + 1,
-                                error.path,
-                                error.message
-                            );
-                        }
-                        eprintln!("   Expected: {}", error.expected);
-                        eprintln!("   Found: {}", error.actual);
-                        if let Some(desc) = &error.description {
-                            eprintln!("   Description: {}", desc);
-                        }
-                        if i < errors.len() - 1 {
-                            eprintln!(); // Blank line between errors
-                        }
+                    // Print all diagnostics, grouped by severity
+                    let result = ValidationResult::Invalid(errors.clone());
+                    match format_validation_result(&result, &ReportFormat::Text) {
+                        Ok(report) => eprint!("Error: {}", report),
+                        Err(report_err) => eprintln!("Error: {}", report_err),
                     }
                 } else {
                     // For non-validation errors, just print the error
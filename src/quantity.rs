@@ -0,0 +1,239 @@
+use crate::schema::SchemaType;
+use serde_yaml::Value;
+
+/// Kinds of quantities written as a number with a unit, e.g. `30s` or `512Mi`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Quantity {
+    /// Durations such as `500ms` or `1h30m`, normalized to seconds
+    Duration,
+
+    /// Sizes such as `512Mi` or `2GB`, normalized to bytes
+    ByteSize,
+
+    /// Percentages such as `75%`, normalized to percent
+    Percentage,
+}
+
+/// Duration units and their length in seconds
+const DURATION_UNITS: &[(&str, f64)] = &[
+    ("ns", 1e-9),
+    ("us", 1e-6),
+    ("µs", 1e-6),
+    ("ms", 1e-3),
+    ("s", 1.0),
+    ("m", 60.0),
+    ("h", 3600.0),
+    ("d", 86400.0),
+];
+
+/// Byte size units (matched case-insensitively) and their size in bytes
+const BYTE_UNITS: &[(&str, f64)] = &[
+    ("b", 1.0),
+    ("k", 1e3),
+    ("kb", 1e3),
+    ("ki", 1024.0),
+    ("kib", 1024.0),
+    ("m", 1e6),
+    ("mb", 1e6),
+    ("mi", 1048576.0),
+    ("mib", 1048576.0),
+    ("g", 1e9),
+    ("gb", 1e9),
+    ("gi", 1073741824.0),
+    ("gib", 1073741824.0),
+    ("t", 1e12),
+    ("tb", 1e12),
+    ("ti", 1099511627776.0),
+    ("tib", 1099511627776.0),
+    ("p", 1e15),
+    ("pb", 1e15),
+    ("pi", 1125899906842624.0),
+    ("pib", 1125899906842624.0),
+];
+
+impl Quantity {
+    /// The quantity measured by a schema type, if it is a quantity type
+    pub(crate) fn of(data_type: &SchemaType) -> Option<Quantity> {
+        match data_type {
+            SchemaType::Duration => Some(Quantity::Duration),
+            SchemaType::ByteSize => Some(Quantity::ByteSize),
+            SchemaType::Percentage => Some(Quantity::Percentage),
+            _ => None,
+        }
+    }
+
+    /// Whether a configuration value has the shape of this quantity
+    ///
+    /// Byte sizes may also be written as a plain integer number of bytes.
+    pub(crate) fn accepts(&self, value: &Value) -> bool {
+        match value {
+            Value::String(_) => true,
+            Value::Number(n) => *self == Quantity::ByteSize && (n.is_u64() || n.is_i64()),
+            _ => false,
+        }
+    }
+
+    /// Parse a configuration or schema value into its normalized amount
+    pub(crate) fn parse_value(&self, value: &Value) -> Result<f64, String> {
+        match value {
+            Value::String(s) => self.parse(s),
+            Value::Number(n) if self.accepts(value) => n
+                .as_f64()
+                .ok_or_else(|| format!("{} is not a valid byte size", n)),
+            other => Err(format!(
+                "expected a {} string, found {:?}",
+                self.name(),
+                other
+            )),
+        }
+    }
+
+    /// Parse a string into its normalized amount (seconds, bytes or percent)
+    pub(crate) fn parse(&self, s: &str) -> Result<f64, String> {
+        let s = s.trim();
+        match self {
+            Quantity::Duration => parse_duration(s),
+            Quantity::ByteSize => parse_byte_size(s),
+            Quantity::Percentage => {
+                let number = s
+                    .strip_suffix('%')
+                    .ok_or_else(|| "missing '%' suffix".to_string())?;
+                parse_number(number.trim_end())
+            }
+        }
+    }
+
+    /// Format a normalized amount for error messages
+    pub(crate) fn describe(&self, amount: f64) -> String {
+        match self {
+            Quantity::Duration => format!("{} seconds", amount),
+            Quantity::ByteSize => format!("{} bytes", amount),
+            Quantity::Percentage => format!("{}%", amount),
+        }
+    }
+
+    /// Name of the quantity for error messages
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Quantity::Duration => "duration",
+            Quantity::ByteSize => "byte size",
+            Quantity::Percentage => "percentage",
+        }
+    }
+}
+
+/// Parse a duration made of one or more `<number><unit>` parts, e.g. `1h30m`
+fn parse_duration(s: &str) -> Result<f64, String> {
+    if s.is_empty() {
+        return Err("duration is empty".to_string());
+    }
+
+    let mut total = 0.0;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        if number_len == 0 {
+            return Err(format!("expected a number before '{}'", rest));
+        }
+        let number = parse_number(&rest[..number_len])?;
+        rest = &rest[number_len..];
+
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let unit = &rest[..unit_len];
+        rest = &rest[unit_len..];
+
+        if unit.is_empty() {
+            return Err(format!(
+                "missing unit after {} (use one of: ns, us, ms, s, m, h, d)",
+                number
+            ));
+        }
+        let (_, seconds) = DURATION_UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .ok_or_else(|| {
+                format!(
+                    "unknown unit '{}' (use one of: ns, us, ms, s, m, h, d)",
+                    unit
+                )
+            })?;
+        total += number * seconds;
+    }
+
+    Ok(total)
+}
+
+/// Parse a byte size such as `512Mi`, `2GB` or `1024`
+fn parse_byte_size(s: &str) -> Result<f64, String> {
+    let number_len = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    if number_len == 0 {
+        return Err("expected a number".to_string());
+    }
+    let number = parse_number(&s[..number_len])?;
+    let unit = s[number_len..].trim_start().to_ascii_lowercase();
+
+    if unit.is_empty() {
+        return Ok(number);
+    }
+    BYTE_UNITS
+        .iter()
+        .find(|(name, _)| *name == unit)
+        .map(|(_, bytes)| number * bytes)
+        .ok_or_else(|| {
+            format!(
+                "unknown unit '{}' (use e.g. B, KB, KiB, MB, MiB, GB, GiB)",
+                &s[number_len..]
+            )
+        })
+}
+
+fn parse_number(s: &str) -> Result<f64, String> {
+    s.parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite() && *n >= 0.0)
+        .ok_or_else(|| format!("'{}' is not a valid number", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(Quantity::Duration.parse("30s"), Ok(30.0));
+        assert_eq!(Quantity::Duration.parse("1h30m"), Ok(5400.0));
+        assert_eq!(Quantity::Duration.parse("1.5m"), Ok(90.0));
+        assert_eq!(Quantity::Duration.parse("250ms"), Ok(0.25));
+        assert!(Quantity::Duration.parse("30").is_err());
+        assert!(Quantity::Duration.parse("30sec").is_err());
+        assert!(Quantity::Duration.parse("").is_err());
+    }
+
+    #[test]
+    fn test_parse_byte_size() {
+        assert_eq!(Quantity::ByteSize.parse("512Mi"), Ok(536870912.0));
+        assert_eq!(Quantity::ByteSize.parse("2GB"), Ok(2e9));
+        assert_eq!(Quantity::ByteSize.parse("1.5 KiB"), Ok(1536.0));
+        assert_eq!(Quantity::ByteSize.parse("100"), Ok(100.0));
+        assert_eq!(
+            Quantity::ByteSize.parse_value(&Value::from(4096)),
+            Ok(4096.0)
+        );
+        assert!(Quantity::ByteSize.parse("12XB").is_err());
+        assert!(Quantity::ByteSize.parse("MB").is_err());
+    }
+
+    #[test]
+    fn test_parse_percentage() {
+        assert_eq!(Quantity::Percentage.parse("75%"), Ok(75.0));
+        assert_eq!(Quantity::Percentage.parse("12.5%"), Ok(12.5));
+        assert!(Quantity::Percentage.parse("75").is_err());
+        assert!(Quantity::Percentage.parse("-5%").is_err());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::error::{ConfigGuardError, ConfigGuardResult};
-use crate::validation::ValidationResult;
+use crate::source::Span;
+use crate::validation::{Severity, ValidationError, ValidationReport, ValidationResult};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::io::Write;
@@ -27,67 +28,157 @@ pub fn format_validation_result(
     result: &ValidationResult,
     format: &ReportFormat,
 ) -> ConfigGuardResult<String> {
+    let (diagnostics, passed) = match result {
+        ValidationResult::Valid => (&[][..], true),
+        ValidationResult::Invalid(diagnostics) => (&diagnostics[..], false),
+    };
+
+    match format {
+        ReportFormat::Text => format_text_report(diagnostics, passed),
+        ReportFormat::Json => format_json_report(diagnostics, passed),
+    }
+}
+
+/// Format a validation report, including its warnings and info
+///
+/// The configuration is reported as failed when it has a diagnostic of at
+/// least the `fail_on` severity.
+pub fn format_validation_report(
+    report: &ValidationReport,
+    fail_on: Severity,
+    format: &ReportFormat,
+) -> ConfigGuardResult<String> {
+    let diagnostics: Vec<ValidationError> = report.diagnostics().cloned().collect();
+    let passed = !report.fails_on(fail_on);
+
     match format {
-        ReportFormat::Text => format_text_report(result),
-        ReportFormat::Json => format_json_report(result),
+        ReportFormat::Text => format_text_report(&diagnostics, passed),
+        ReportFormat::Json => format_json_report(&diagnostics, passed),
     }
 }
 
-/// Format validation results as a text report
-fn format_text_report(result: &ValidationResult) -> ConfigGuardResult<String> {
+/// A validation report as the JSON value printed by `format_validation_report`
+///
+/// Useful for embedding several reports in one JSON document.
+pub fn validation_report_json(
+    report: &ValidationReport,
+    fail_on: Severity,
+) -> ConfigGuardResult<serde_json::Value> {
+    let diagnostics: Vec<ValidationError> = report.diagnostics().cloned().collect();
+    serde_json::to_value(json_report(&diagnostics, !report.fails_on(fail_on))).map_err(|e| {
+        ConfigGuardError::Serialization(format!("Failed to serialize JSON report: {}", e))
+    })
+}
+
+/// Severities in report order, with their group heading and per-diagnostic label
+const SEVERITY_GROUPS: [(Severity, &str, &str); 3] = [
+    (Severity::Error, "Errors", "Error"),
+    (Severity::Warning, "Warnings", "Warning"),
+    (Severity::Info, "Info", "Info"),
+];
+
+/// Diagnostics of one severity, in the order they were found
+fn with_severity(diagnostics: &[ValidationError], severity: Severity) -> Vec<&ValidationError> {
+    diagnostics
+        .iter()
+        .filter(|d| d.severity == severity)
+        .collect()
+}
+
+/// Format validation results as a text report, grouped by severity
+fn format_text_report(diagnostics: &[ValidationError], passed: bool) -> ConfigGuardResult<String> {
     let mut output = Vec::new();
 
-    match result {
-        ValidationResult::Valid => {
-            writeln!(output, "Configuration validation passed.")
-                .map_err(|e| ConfigGuardError::IO(e.to_string()))?;
+    let counts: Vec<String> = SEVERITY_GROUPS
+        .iter()
+        .map(|(severity, heading, label)| {
+            let count = with_severity(diagnostics, *severity).len();
+            // "1 error", "2 errors"
+            let noun = if count == 1 { label } else { heading };
+            (count, noun.to_lowercase())
+        })
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, noun)| format!("{} {}", count, noun))
+        .collect();
+
+    if counts.is_empty() {
+        writeln!(
+            output,
+            "Configuration validation {}.",
+            if passed { "passed" } else { "failed" }
+        )
+        .map_err(|e| ConfigGuardError::IO(e.to_string()))?;
+    } else {
+        writeln!(
+            output,
+            "Configuration validation {} with {}:",
+            if passed { "passed" } else { "failed" },
+            counts.join(", ")
+        )
+        .map_err(|e| ConfigGuardError::IO(e.to_string()))?;
+    }
+
+    for (severity, heading, label) in SEVERITY_GROUPS {
+        let group = with_severity(diagnostics, severity);
+        if group.is_empty() {
+            continue;
         }
-        ValidationResult::Invalid(errors) => {
-            writeln!(
-                output,
-                "Configuration validation failed with {} errors:",
-                errors.len()
-            )
-            .map_err(|e| ConfigGuardError::IO(e.to_string()))?;
-
-            for (i, error) in errors.iter().enumerate() {
-                if let Some(line) = error.line {
-                    writeln!(
-                        output,
-                        "{}. Error at path '{}' (line {}): {}",
-                        i + 1,
-                        error.path,
-                        line,
-                        error.message
-                    )
-                    .map_err(|e| ConfigGuardError::IO(e.to_string()))?;
-                } else {
-                    writeln!(
-                        output,
-                        "{}. Error at path '{}': {}",
-                        i + 1,
-                        error.path,
-                        error.message
-                    )
+
+        writeln!(output, "\n{}:", heading).map_err(|e| ConfigGuardError::IO(e.to_string()))?;
+
+        for (i, error) in group.iter().enumerate() {
+            let location = match &error.file {
+                Some(file) => format!("in '{}' at path '{}'", file, error.path),
+                None => format!("at path '{}'", error.path),
+            };
+            if let Some(position) = error.location() {
+                writeln!(
+                    output,
+                    "{}. {} {} ({}): {}",
+                    i + 1,
+                    label,
+                    location,
+                    position,
+                    error.message
+                )
+                .map_err(|e| ConfigGuardError::IO(e.to_string()))?;
+            } else {
+                writeln!(
+                    output,
+                    "{}. {} {}: {}",
+                    i + 1,
+                    label,
+                    location,
+                    error.message
+                )
+                .map_err(|e| ConfigGuardError::IO(e.to_string()))?;
+            }
+
+            // Include field description if available
+            if let Some(description) = &error.description {
+                writeln!(output, "   Field description: {}", description)
                     .map_err(|e| ConfigGuardError::IO(e.to_string()))?;
-                }
+            }
 
-                // Include field description if available
-                if let Some(description) = &error.description {
-                    writeln!(output, "   Field description: {}", description)
-                        .map_err(|e| ConfigGuardError::IO(e.to_string()))?;
-                }
+            writeln!(output, "   Expected: {}", error.expected)
+                .map_err(|e| ConfigGuardError::IO(e.to_string()))?;
+
+            writeln!(output, "   Found: {}", error.actual)
+                .map_err(|e| ConfigGuardError::IO(e.to_string()))?;
 
-                writeln!(output, "   Expected: {}", error.expected)
+            if let Some(hint) = &error.hint {
+                writeln!(output, "   Hint: {}", hint)
                     .map_err(|e| ConfigGuardError::IO(e.to_string()))?;
+            }
 
-                writeln!(output, "   Found: {}", error.actual)
+            if let Some(docs_url) = &error.docs_url {
+                writeln!(output, "   Docs: {}", docs_url)
                     .map_err(|e| ConfigGuardError::IO(e.to_string()))?;
+            }
 
-                // Add a blank line between errors for readability
-                if i < errors.len() - 1 {
-                    writeln!(output).map_err(|e| ConfigGuardError::IO(e.to_string()))?;
-                }
+            // Add a blank line between errors for readability
+            if i < group.len() - 1 {
+                writeln!(output).map_err(|e| ConfigGuardError::IO(e.to_string()))?;
             }
         }
     }
@@ -100,63 +191,81 @@ fn format_text_report(result: &ValidationResult) -> ConfigGuardResult<String> {
 struct JsonReport {
     valid: bool,
     error_count: usize,
+    warning_count: usize,
+    info_count: usize,
     errors: Vec<JsonValidationError>,
+    warnings: Vec<JsonValidationError>,
+    info: Vec<JsonValidationError>,
 }
 
 /// JSON validation error structure
 #[derive(Serialize, Deserialize)]
 struct JsonValidationError {
+    severity: Severity,
+    file: Option<String>,
     path: String,
     message: String,
     expected: String,
     actual: String,
     description: Option<String>,
     line: Option<usize>,
+    span: Option<Span>,
+    hint: Option<String>,
+    docs_url: Option<String>,
 }
 
-/// Format validation results as a JSON report
-fn format_json_report(result: &ValidationResult) -> ConfigGuardResult<String> {
-    let report = match result {
-        ValidationResult::Valid => JsonReport {
-            valid: true,
-            error_count: 0,
-            errors: Vec::new(),
-        },
-        ValidationResult::Invalid(errors) => {
-            let json_errors = errors
-                .iter()
-                .map(|e| JsonValidationError {
-                    path: e.path.clone(),
-                    message: e.message.clone(),
-                    expected: e.expected.clone(),
-                    actual: e.actual.clone(),
-                    description: e.description.clone(),
-                    line: e.line,
-                })
-                .collect();
-
-            JsonReport {
-                valid: false,
-                error_count: errors.len(),
-                errors: json_errors,
-            }
-        }
-    };
-
-    serde_json::to_string_pretty(&report).map_err(|e| {
+/// Format validation results as a JSON report, grouped by severity
+fn format_json_report(diagnostics: &[ValidationError], passed: bool) -> ConfigGuardResult<String> {
+    serde_json::to_string_pretty(&json_report(diagnostics, passed)).map_err(|e| {
         ConfigGuardError::Serialization(format!("Failed to serialize JSON report: {}", e))
     })
 }
 
+/// Group diagnostics by severity for a JSON report
+fn json_report(diagnostics: &[ValidationError], passed: bool) -> JsonReport {
+    let group = |severity| -> Vec<JsonValidationError> {
+        with_severity(diagnostics, severity)
+            .into_iter()
+            .map(|e| JsonValidationError {
+                severity: e.severity,
+                file: e.file.clone(),
+                path: e.path.clone(),
+                message: e.message.clone(),
+                expected: e.expected.clone(),
+                actual: e.actual.clone(),
+                description: e.description.clone(),
+                line: e.line,
+                span: e.span,
+                hint: e.hint.clone(),
+                docs_url: e.docs_url.clone(),
+            })
+            .collect()
+    };
+    let (errors, warnings, info) = (
+        group(Severity::Error),
+        group(Severity::Warning),
+        group(Severity::Info),
+    );
+
+    JsonReport {
+        valid: passed,
+        error_count: errors.len(),
+        warning_count: warnings.len(),
+        info_count: info.len(),
+        errors,
+        warnings,
+        info,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::validation::ValidationError;
 
     #[test]
     fn test_text_report_valid() {
         let result = ValidationResult::Valid;
-        let report = format_text_report(&result).unwrap();
+        let report = format_validation_result(&result, &ReportFormat::Text).unwrap();
 
         assert!(report.contains("Configuration validation passed"));
     }
@@ -171,6 +280,11 @@ mod tests {
                 actual: "Key is absent".to_string(),
                 description: Some("The name of the resource".to_string()),
                 line: None,
+                span: None,
+                severity: Severity::Error,
+                hint: None,
+                docs_url: None,
+                file: None,
             },
             ValidationError {
                 path: ".spec.containers".to_string(),
@@ -179,11 +293,16 @@ mod tests {
                 actual: "0 items".to_string(),
                 description: None,
                 line: None,
+                span: None,
+                severity: Severity::Error,
+                hint: None,
+                docs_url: None,
+                file: None,
             },
         ];
 
         let result = ValidationResult::Invalid(errors);
-        let report = format_text_report(&result).unwrap();
+        let report = format_validation_result(&result, &ReportFormat::Text).unwrap();
 
         assert!(report.contains("Configuration validation failed with 2 errors"));
         assert!(report.contains("Error at path '.metadata.name'"));
@@ -196,7 +315,7 @@ mod tests {
     #[test]
     fn test_json_report_valid() {
         let result = ValidationResult::Valid;
-        let report = format_json_report(&result).unwrap();
+        let report = format_validation_result(&result, &ReportFormat::Json).unwrap();
 
         let parsed: JsonReport = serde_json::from_str(&report).unwrap();
         assert!(parsed.valid);
@@ -213,10 +332,15 @@ mod tests {
             actual: "Key is absent".to_string(),
             description: Some("The name of the resource".to_string()),
             line: None,
+            span: None,
+            severity: Severity::Error,
+            hint: None,
+            docs_url: None,
+            file: None,
         }];
 
         let result = ValidationResult::Invalid(errors);
-        let report = format_json_report(&result).unwrap();
+        let report = format_validation_result(&result, &ReportFormat::Json).unwrap();
 
         let parsed: JsonReport = serde_json::from_str(&report).unwrap();
         assert!(!parsed.valid);
@@ -229,4 +353,83 @@ mod tests {
             Some("The name of the resource".to_string())
         );
     }
+
+    #[test]
+    fn test_reports_group_by_severity() {
+        let diagnostic = |path: &str, severity| {
+            ValidationError::new(path, "Problem", "Something", "Something else")
+                .with_severity(severity)
+        };
+        let report = ValidationReport {
+            path: None,
+            errors: vec![diagnostic(".a", Severity::Error)],
+            warnings: vec![
+                diagnostic(".b", Severity::Warning),
+                diagnostic(".c", Severity::Warning),
+            ],
+            info: vec![],
+        };
+
+        let text = format_validation_report(&report, Severity::Error, &ReportFormat::Text).unwrap();
+        assert!(text.starts_with("Configuration validation failed with 1 error, 2 warnings:"));
+        let errors_at = text.find("Errors:\n1. Error at path '.a'").unwrap();
+        let warnings_at = text.find("Warnings:\n1. Warning at path '.b'").unwrap();
+        assert!(errors_at < warnings_at);
+        assert!(text.contains("2. Warning at path '.c'"));
+        assert!(!text.contains("Info:"));
+
+        let json = format_validation_report(&report, Severity::Error, &ReportFormat::Json).unwrap();
+        let parsed: JsonReport = serde_json::from_str(&json).unwrap();
+        assert!(!parsed.valid);
+        assert_eq!(
+            (parsed.error_count, parsed.warning_count, parsed.info_count),
+            (1, 2, 0)
+        );
+        assert_eq!(parsed.warnings[1].path, ".c");
+        assert_eq!(parsed.warnings[1].severity, Severity::Warning);
+
+        // Warnings alone only fail the report with a lower threshold
+        let warnings_only = ValidationReport {
+            errors: vec![],
+            ..report
+        };
+        let text =
+            format_validation_report(&warnings_only, Severity::Error, &ReportFormat::Text).unwrap();
+        assert!(text.starts_with("Configuration validation passed with 2 warnings:"));
+
+        let single_warning = ValidationReport {
+            warnings: vec![diagnostic(".b", Severity::Warning)],
+            info: vec![diagnostic(".d", Severity::Info)],
+            ..warnings_only.clone()
+        };
+        let text = format_validation_report(&single_warning, Severity::Error, &ReportFormat::Text)
+            .unwrap();
+        assert!(text.starts_with("Configuration validation passed with 1 warning, 1 info:"));
+        let json = format_validation_report(&warnings_only, Severity::Warning, &ReportFormat::Json)
+            .unwrap();
+        let parsed: JsonReport = serde_json::from_str(&json).unwrap();
+        assert!(!parsed.valid);
+    }
+
+    #[test]
+    fn test_reports_include_hints() {
+        let mut error = ValidationError::new(".apiVersion", "Unsupported version", "v1", "v2");
+        error.hint = Some("Set apiVersion to v1".to_string());
+        error.docs_url = Some("https://example.com/docs".to_string());
+        let result = ValidationResult::Invalid(vec![error]);
+
+        let text = format_validation_result(&result, &ReportFormat::Text).unwrap();
+        assert!(text.contains("   Hint: Set apiVersion to v1\n   Docs: https://example.com/docs"));
+
+        let json = format_validation_result(&result, &ReportFormat::Json).unwrap();
+        let parsed: JsonReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            parsed.errors[0].hint.as_deref(),
+            Some("Set apiVersion to v1")
+        );
+        assert_eq!(
+            parsed.errors[0].docs_url.as_deref(),
+            Some("https://example.com/docs")
+        );
+    }
 }
@@ -1,19 +1,114 @@
+use crate::compiled::{DocumentPath, Numeric};
 use crate::error::{ConfigGuardError, ConfigGuardResult};
+use crate::expression;
+use crate::formats::{check_date, StringFormat};
+use crate::quantity::Quantity;
+use crate::validation::{
+    display_value, placeholders, rule_placeholder, validate_type, value_type_name, values_equal,
+    Severity, RULE_PLACEHOLDERS,
+};
 use regex::Regex;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_yaml::Value;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Prefix of references to rules in the schema's `definitions` map
+const DEFINITIONS_PREFIX: &str = "#/definitions/";
 
 /// Represents a schema for configuration validation
 #[derive(Debug, Clone)]
 pub struct Schema {
     pub root: SchemaRule,
+
+    /// Reusable rules from the top-level `definitions` map, referenced with `$ref`
+    pub definitions: HashMap<String, SchemaRule>,
+
+    /// Severity of built-in checks, from the top-level `check_severity` map
+    pub check_severity: CheckSeverity,
+
+    /// Checks across the files of a project, from the top-level `project` map
+    pub project: ProjectConstraints,
+}
+
+/// Constraints that span every file of a project, checked by `Validator::validate_project`
+///
+/// Paths select values in each file, e.g. `$.metadata.name` or `$.depends_on[*]`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConstraints {
+    /// Paths whose values must be unique across all files
+    pub unique: Vec<String>,
+
+    /// Values that must name a value defined in some file
+    pub references: Vec<ProjectReference>,
+
+    /// Values that exactly one file must have
+    pub exactly_one: Vec<ProjectCount>,
+}
+
+/// A value in one file that must name a value in any file of the project
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectReference {
+    /// Path of the referring values, e.g. `$.depends_on[*]`
+    pub path: String,
+
+    /// Path of the values they must name, e.g. `$.metadata.name`
+    pub ref_to: String,
+}
+
+/// A value exactly one file of the project must have
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectCount {
+    /// Path of the value, e.g. `$.default`
+    pub path: String,
+
+    /// Value a file must have at `path` to count (without it, any non-null value counts)
+    pub equals: Option<Value>,
+}
+
+impl ProjectConstraints {
+    /// Whether no project constraints are defined
+    pub fn is_empty(&self) -> bool {
+        self.unique.is_empty() && self.references.is_empty() && self.exactly_one.is_empty()
+    }
+
+    /// Every path used by the constraints
+    pub(crate) fn paths(&self) -> impl Iterator<Item = &String> {
+        self.unique
+            .iter()
+            .chain(self.references.iter().flat_map(|r| [&r.path, &r.ref_to]))
+            .chain(self.exactly_one.iter().map(|c| &c.path))
+    }
+}
+
+/// Severity of the built-in checks that are not tied to a rule keyword
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CheckSeverity {
+    /// Keys not defined in the schema (strict mode or `allow_unknown_keys: false`)
+    pub unknown_key: Severity,
+
+    /// Keys marked `deprecated` or `renamed_to`, until their `deprecated_until` date
+    pub deprecated: Severity,
+}
+
+impl Default for CheckSeverity {
+    fn default() -> Self {
+        Self {
+            unknown_key: Severity::Error,
+            deprecated: Severity::Warning,
+        }
+    }
 }
 
 /// The type of a schema value
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum SchemaType {
     String,
@@ -22,27 +117,116 @@ pub enum SchemaType {
     Boolean,
     Object,
     List,
+    #[default]
     Any,
     Null,
+    Duration,
+    #[serde(rename = "byte_size")]
+    ByteSize,
+    Percentage,
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    /// Any of several types, written as a list such as `[string, null]`
+    #[serde(skip)]
+    Union(Vec<SchemaType>),
 }
 
 /// A rule in the schema definition
 #[derive(Debug, Clone, Deserialize)]
+#[serde(remote = "Self")]
 pub struct SchemaRule {
-    /// The expected data type
-    #[serde(rename = "type")]
+    /// The expected data type (`any` for a rule that only uses `$ref`)
+    ///
+    /// A rule read without `type` or `$ref` gets an empty union, which
+    /// `Schema::validate_schema_rule` rejects.
+    #[serde(
+        rename = "type",
+        default = "missing_type",
+        deserialize_with = "deserialize_type"
+    )]
     pub data_type: SchemaType,
 
+    /// Whether `null` is accepted in addition to `type` (shorthand for `[type, null]`)
+    #[serde(default)]
+    pub nullable: bool,
+
+    /// Reference to a rule in `definitions` that the value must also satisfy
+    #[serde(rename = "$ref")]
+    pub reference: Option<String>,
+
+    /// Reusable rules (only allowed at the top level of a schema)
+    pub definitions: Option<HashMap<String, SchemaRule>>,
+
+    /// Severity of built-in checks (only allowed at the top level of a schema)
+    pub check_severity: Option<CheckSeverity>,
+
+    /// Checks across the files of a project (only allowed at the top level of a schema)
+    pub project: Option<ProjectConstraints>,
+
+    /// Severity of the problems found by this rule (defaults to `error`)
+    pub severity: Option<Severity>,
+
     /// Optional description of the field/node
     pub description: Option<String>,
 
+    /// Replaces the built-in message of problems found at this field
+    ///
+    /// May use `{value}`, `{path}` and placeholders for the rule's constraints,
+    /// such as `{min}`, `{max}` or `{pattern}`.
+    pub error_message: Option<String>,
+
+    /// Remediation hint shown with problems found at this field (same placeholders)
+    pub hint: Option<String>,
+
+    /// Link to documentation shown with problems found at this field
+    pub docs_url: Option<String>,
+
     /// Whether the key must exist (for object fields)
     #[serde(default)]
     pub required: bool,
 
+    /// Value filled in when the key is absent (see `Validator::resolve`)
+    pub default: Option<Value>,
+
+    /// Marks the key as deprecated, with a migration hint such as "use server.port"
+    pub deprecated: Option<String>,
+
+    /// New location of a renamed key, relative to the containing object
+    pub renamed_to: Option<String>,
+
+    /// Date (`YYYY-MM-DD`) after which a deprecated key is an error instead of a warning
+    pub deprecated_until: Option<String>,
+
+    /// The key must exist when every listed sibling field has the given value
+    pub required_if: Option<HashMap<String, Value>>,
+
+    /// The key must be absent when every listed sibling field has the given value
+    pub forbidden_if: Option<HashMap<String, Value>>,
+
     /// Object-specific: Rules for child keys
     pub keys: Option<HashMap<String, SchemaRule>>,
 
+    /// Object-specific: Rule for every key not listed in `keys` or matched by `pattern_keys`
+    pub values: Option<Box<SchemaRule>>,
+
+    /// Object-specific: Regular expression every key name must match
+    pub key_pattern: Option<String>,
+
+    /// Object-specific: Rules for keys whose names match a regular expression
+    pub pattern_keys: Option<HashMap<String, SchemaRule>>,
+
+    /// Object-specific: Minimum number of keys
+    pub min_keys: Option<usize>,
+
+    /// Object-specific: Maximum number of keys
+    pub max_keys: Option<usize>,
+
     /// Object-specific: Whether to allow keys not defined in the schema
     #[serde(default = "default_allow_unknown_keys")]
     pub allow_unknown_keys: bool,
@@ -50,35 +234,774 @@ pub struct SchemaRule {
     /// List-specific: Validation rules for list items
     pub items: Option<Box<SchemaRule>>,
 
+    /// List-specific: Validation rules for the leading items, by position
+    pub prefix_items: Option<Vec<SchemaRule>>,
+
+    /// List-specific: Whether (or how) items beyond `prefix_items` are allowed
+    pub additional_items: Option<AdditionalItems>,
+
+    /// List-specific: Whether every item must be distinct
+    #[serde(default)]
+    pub unique_items: bool,
+
+    /// List-specific: Object fields whose combined values must be distinct across items
+    #[serde(default, deserialize_with = "deserialize_field_list")]
+    pub unique_by: Option<Vec<String>>,
+
+    /// List-specific: Rule that some items must satisfy (see `min_contains`/`max_contains`)
+    pub contains: Option<Box<SchemaRule>>,
+
+    /// List-specific: Minimum number of items matching `contains` (defaults to 1)
+    pub min_contains: Option<usize>,
+
+    /// List-specific: Maximum number of items matching `contains`
+    pub max_contains: Option<usize>,
+
     /// List/String-specific: Minimum length
     pub min_length: Option<usize>,
 
     /// List/String-specific: Maximum length
     pub max_length: Option<usize>,
 
+    /// String-specific: Well-known format the string must be in
+    pub format: Option<StringFormat>,
+
+    /// String-specific: Schemes allowed by `format: uri`
+    pub uri_schemes: Option<Vec<String>>,
+
     /// String-specific: Regex pattern
     pub pattern: Option<String>,
 
-    /// String/Number-specific: Enum of allowed values
+    /// String-specific: Path of the values in the same document the string must name,
+    /// e.g. `$.backends[*].name`
+    pub ref_to: Option<String>,
+
+    /// Enum of allowed values (of any type, compared structurally)
     #[serde(rename = "enum")]
     pub enum_values: Option<Vec<Value>>,
 
+    /// The only allowed value (of any type, compared structurally)
+    #[serde(rename = "const")]
+    pub const_value: Option<Value>,
+
+    /// Whether strings in `enum` and `const` are matched ignoring case
+    #[serde(default)]
+    pub case_insensitive: bool,
+
     /// Number-specific: Minimum value (inclusive)
     pub min: Option<Value>,
 
     /// Number-specific: Maximum value (inclusive)
     pub max: Option<Value>,
+
+    /// Number-specific: Minimum value (exclusive)
+    pub exclusive_min: Option<Value>,
+
+    /// Number-specific: Maximum value (exclusive)
+    pub exclusive_max: Option<Value>,
+
+    /// Number-specific: The value must be a multiple of this number
+    pub multiple_of: Option<Value>,
+
+    /// Float-specific: Maximum number of digits after the decimal point
+    pub max_decimal_places: Option<usize>,
+
+    /// Combinator: the value must satisfy exactly one of these rules
+    pub one_of: Option<Vec<SchemaRule>>,
+
+    /// Combinator: the value must satisfy at least one of these rules
+    pub any_of: Option<Vec<SchemaRule>>,
+
+    /// Combinator: the value must satisfy all of these rules
+    pub all_of: Option<Vec<SchemaRule>>,
+
+    /// Combinator: the value must not satisfy this rule
+    pub not: Option<Box<SchemaRule>>,
+
+    /// Object-specific: Groups of keys of which at most one may be present
+    pub exclusive_keys: Option<Vec<Vec<String>>>,
+
+    /// Object-specific: Groups of keys that must be present together or not at all
+    pub dependent_keys: Option<Vec<Vec<String>>>,
+
+    /// Object-specific: Groups of keys of which at least one must be present
+    pub one_required_of: Option<Vec<Vec<String>>>,
+
+    /// Object-specific: Select additional key rules by the value of a tag field
+    pub discriminator: Option<Discriminator>,
+
+    /// Object-specific: Expressions over the object's fields that must hold
+    #[serde(rename = "assert")]
+    pub assertions: Option<Vec<Assertion>>,
+
+    /// Conditional: selects whether `then` or `else` applies to the value
+    #[serde(rename = "if")]
+    pub if_rule: Option<Box<SchemaRule>>,
+
+    /// Conditional: rule applied when the value satisfies `if`
+    #[serde(rename = "then")]
+    pub then_rule: Option<Box<SchemaRule>>,
+
+    /// Conditional: rule applied when the value does not satisfy `if`
+    #[serde(rename = "else")]
+    pub else_rule: Option<Box<SchemaRule>>,
+}
+
+/// Items of a list beyond its `prefix_items`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum AdditionalItems {
+    /// `true` allows extra items (checked against `items`, if set), `false` forbids them
+    Allowed(bool),
+
+    /// Extra items must satisfy this rule
+    Rule(Box<SchemaRule>),
+}
+
+/// Selects a variant of an object rule by the value of one of its fields
+///
+/// The keys of the selected variant are checked in addition to the keys of
+/// the object rule itself.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Discriminator {
+    /// Name of the tag field (e.g. `type` or `kind`)
+    pub field: String,
+
+    /// Rule for each allowed tag value
+    pub mapping: HashMap<String, SchemaRule>,
+}
+
+/// A cross-field check on an object, from its `assert` list
+///
+/// Written either as a bare expression or as a map with `expr` and `message`.
+#[derive(Debug, Clone)]
+pub struct Assertion {
+    /// Expression that must evaluate to `true`, e.g. `min_replicas <= max_replicas`
+    pub expr: String,
+
+    /// Message reported when the expression is false
+    pub message: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for Assertion {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Full {
+            expr: String,
+            message: Option<String>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(
+            untagged,
+            expecting = "an expression or a map with 'expr' and 'message'"
+        )]
+        enum AssertionForm {
+            Expr(String),
+            Full(Full),
+        }
+
+        Ok(match AssertionForm::deserialize(deserializer)? {
+            AssertionForm::Expr(expr) => Assertion {
+                expr,
+                message: None,
+            },
+            AssertionForm::Full(Full { expr, message }) => Assertion { expr, message },
+        })
+    }
+}
+
+impl fmt::Display for SchemaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SchemaType::String => "string",
+            SchemaType::Integer => "integer",
+            SchemaType::Float => "float",
+            SchemaType::Boolean => "boolean",
+            SchemaType::Object => "object",
+            SchemaType::List => "list",
+            SchemaType::Any => "any",
+            SchemaType::Null => "null",
+            SchemaType::Duration => "duration",
+            SchemaType::ByteSize => "byte_size",
+            SchemaType::Percentage => "percentage",
+            SchemaType::U8 => "u8",
+            SchemaType::U16 => "u16",
+            SchemaType::U32 => "u32",
+            SchemaType::U64 => "u64",
+            SchemaType::I8 => "i8",
+            SchemaType::I16 => "i16",
+            SchemaType::I32 => "i32",
+            SchemaType::I64 => "i64",
+            SchemaType::Union(types) => {
+                let names: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+                return write!(f, "{}", names.join(" or "));
+            }
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl SchemaType {
+    /// Every type that can be named in a schema
+    pub const NAMED: [SchemaType; 19] = [
+        SchemaType::String,
+        SchemaType::Integer,
+        SchemaType::Float,
+        SchemaType::Boolean,
+        SchemaType::Object,
+        SchemaType::List,
+        SchemaType::Any,
+        SchemaType::Null,
+        SchemaType::Duration,
+        SchemaType::ByteSize,
+        SchemaType::Percentage,
+        SchemaType::U8,
+        SchemaType::U16,
+        SchemaType::U32,
+        SchemaType::U64,
+        SchemaType::I8,
+        SchemaType::I16,
+        SchemaType::I32,
+        SchemaType::I64,
+    ];
+
+    /// Smallest and largest value of a fixed-width integer type
+    pub(crate) fn integer_range(&self) -> Option<(i128, i128)> {
+        match self {
+            SchemaType::U8 => Some((0, u8::MAX.into())),
+            SchemaType::U16 => Some((0, u16::MAX.into())),
+            SchemaType::U32 => Some((0, u32::MAX.into())),
+            SchemaType::U64 => Some((0, u64::MAX.into())),
+            SchemaType::I8 => Some((i8::MIN.into(), i8::MAX.into())),
+            SchemaType::I16 => Some((i16::MIN.into(), i16::MAX.into())),
+            SchemaType::I32 => Some((i32::MIN.into(), i32::MAX.into())),
+            SchemaType::I64 => Some((i64::MIN.into(), i64::MAX.into())),
+            _ => None,
+        }
+    }
+
+    /// The types a value may have: the members of a union, or just this type
+    pub(crate) fn members(&self) -> &[SchemaType] {
+        match self {
+            SchemaType::Union(types) => types,
+            other => std::slice::from_ref(other),
+        }
+    }
+
+    /// Whether values of this type are numbers (`integer`, `float` or a fixed-width integer)
+    pub(crate) fn is_numeric(&self) -> bool {
+        matches!(self, SchemaType::Integer | SchemaType::Float) || self.integer_range().is_some()
+    }
+}
+
+/// Accept either a single type or a list of types
+///
+/// Each entry is parsed on its own so a misspelled type is reported by name
+/// rather than as a failed match of the whole list.
+fn deserialize_type<'de, D>(deserializer: D) -> Result<SchemaType, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    // YAML reads an unquoted `null` as the null value rather than the type name
+    let parse = |value: Value| match value {
+        Value::Null => Ok(SchemaType::Null),
+        value => SchemaType::deserialize(value).map_err(serde::de::Error::custom),
+    };
+    match Value::deserialize(deserializer)? {
+        Value::Sequence(items) if items.is_empty() => Err(serde::de::Error::custom(
+            "'type' must list at least one type",
+        )),
+        Value::Sequence(items) => {
+            let mut types = items
+                .into_iter()
+                .map(parse)
+                .collect::<Result<Vec<_>, _>>()?;
+            if types.len() == 1 {
+                Ok(types.remove(0))
+            } else {
+                Ok(SchemaType::Union(types))
+            }
+        }
+        value => parse(value),
+    }
+}
+
+/// The type-specific keywords set on a rule, with whether each suits `data_type`
+///
+/// Bounds count by their value: `min: 1s` suits a duration but not an integer.
+fn type_keywords(rule: &SchemaRule, data_type: &SchemaType) -> Vec<(&'static str, bool)> {
+    let object = *data_type == SchemaType::Object;
+    let list = *data_type == SchemaType::List;
+    let string = *data_type == SchemaType::String;
+    let numeric = data_type.is_numeric();
+    let quantity = Quantity::of(data_type);
+    let bound_fits = |bound: &Option<Value>| match (bound, quantity) {
+        (None, _) => true,
+        (Some(bound), Some(quantity)) => quantity.parse_value(bound).is_ok(),
+        (Some(bound), None) => numeric && Numeric::from_value(bound).is_some(),
+    };
+
+    [
+        ("keys", rule.keys.is_some(), object),
+        ("values", rule.values.is_some(), object),
+        ("key_pattern", rule.key_pattern.is_some(), object),
+        ("pattern_keys", rule.pattern_keys.is_some(), object),
+        ("min_keys", rule.min_keys.is_some(), object),
+        ("max_keys", rule.max_keys.is_some(), object),
+        ("exclusive_keys", rule.exclusive_keys.is_some(), object),
+        ("dependent_keys", rule.dependent_keys.is_some(), object),
+        ("one_required_of", rule.one_required_of.is_some(), object),
+        ("discriminator", rule.discriminator.is_some(), object),
+        ("assert", rule.assertions.is_some(), object),
+        ("items", rule.items.is_some(), list),
+        ("prefix_items", rule.prefix_items.is_some(), list),
+        ("additional_items", rule.additional_items.is_some(), list),
+        ("unique_items", rule.unique_items, list),
+        ("unique_by", rule.unique_by.is_some(), list),
+        ("contains", rule.contains.is_some(), list),
+        ("min_contains", rule.min_contains.is_some(), list),
+        ("max_contains", rule.max_contains.is_some(), list),
+        ("min_length", rule.min_length.is_some(), list || string),
+        ("max_length", rule.max_length.is_some(), list || string),
+        ("pattern", rule.pattern.is_some(), string),
+        ("format", rule.format.is_some(), string),
+        ("uri_schemes", rule.uri_schemes.is_some(), string),
+        ("ref_to", rule.ref_to.is_some(), string),
+        ("min", rule.min.is_some(), bound_fits(&rule.min)),
+        ("max", rule.max.is_some(), bound_fits(&rule.max)),
+        (
+            "exclusive_min",
+            rule.exclusive_min.is_some(),
+            numeric && bound_fits(&rule.exclusive_min),
+        ),
+        (
+            "exclusive_max",
+            rule.exclusive_max.is_some(),
+            numeric && bound_fits(&rule.exclusive_max),
+        ),
+        (
+            "multiple_of",
+            rule.multiple_of.is_some(),
+            numeric && bound_fits(&rule.multiple_of),
+        ),
+        (
+            "max_decimal_places",
+            rule.max_decimal_places.is_some(),
+            *data_type == SchemaType::Float,
+        ),
+    ]
+    .into_iter()
+    .filter(|(_, is_set, _)| *is_set)
+    .map(|(keyword, _, fits)| (keyword, fits))
+    .collect()
+}
+
+/// The part of a union rule that applies to one of its member types
+///
+/// Keywords for the other members are left out, as are the `default` and the
+/// message templates, which are checked against the whole union.
+fn member_rule(rule: &SchemaRule, data_type: &SchemaType) -> SchemaRule {
+    let mut member = SchemaRule {
+        data_type: data_type.clone(),
+        default: None,
+        error_message: None,
+        hint: None,
+        ..rule.clone()
+    };
+    for (keyword, fits) in type_keywords(rule, data_type) {
+        if fits {
+            continue;
+        }
+        match keyword {
+            "keys" => member.keys = None,
+            "values" => member.values = None,
+            "key_pattern" => member.key_pattern = None,
+            "pattern_keys" => member.pattern_keys = None,
+            "min_keys" => member.min_keys = None,
+            "max_keys" => member.max_keys = None,
+            "exclusive_keys" => member.exclusive_keys = None,
+            "dependent_keys" => member.dependent_keys = None,
+            "one_required_of" => member.one_required_of = None,
+            "discriminator" => member.discriminator = None,
+            "assert" => member.assertions = None,
+            "items" => member.items = None,
+            "prefix_items" => member.prefix_items = None,
+            "additional_items" => member.additional_items = None,
+            "unique_items" => member.unique_items = false,
+            "unique_by" => member.unique_by = None,
+            "contains" => member.contains = None,
+            "min_contains" => member.min_contains = None,
+            "max_contains" => member.max_contains = None,
+            "min_length" => member.min_length = None,
+            "max_length" => member.max_length = None,
+            "pattern" => member.pattern = None,
+            "format" => member.format = None,
+            "uri_schemes" => member.uri_schemes = None,
+            "ref_to" => member.ref_to = None,
+            "min" => member.min = None,
+            "max" => member.max = None,
+            "exclusive_min" => member.exclusive_min = None,
+            "exclusive_max" => member.exclusive_max = None,
+            "multiple_of" => member.multiple_of = None,
+            "max_decimal_places" => member.max_decimal_places = None,
+            _ => unreachable!("unknown type-specific keyword '{}'", keyword),
+        }
+    }
+    member
+}
+
+/// The first keyword of a discriminator variant that would not be applied
+///
+/// Validation adds only the keys, key groups and assertions of the selected
+/// variant to the object rule (and follows a `$ref` of a variant without `keys`).
+fn unsupported_variant_keyword(rule: &SchemaRule) -> Option<&'static str> {
+    [
+        ("$ref", rule.reference.is_some() && rule.keys.is_some()),
+        ("nullable", rule.nullable),
+        ("severity", rule.severity.is_some()),
+        ("error_message", rule.error_message.is_some()),
+        ("hint", rule.hint.is_some()),
+        ("docs_url", rule.docs_url.is_some()),
+        ("required", rule.required),
+        ("default", rule.default.is_some()),
+        ("deprecated", rule.deprecated.is_some()),
+        ("renamed_to", rule.renamed_to.is_some()),
+        ("required_if", rule.required_if.is_some()),
+        ("forbidden_if", rule.forbidden_if.is_some()),
+        ("values", rule.values.is_some()),
+        ("key_pattern", rule.key_pattern.is_some()),
+        ("pattern_keys", rule.pattern_keys.is_some()),
+        ("min_keys", rule.min_keys.is_some()),
+        ("max_keys", rule.max_keys.is_some()),
+        ("allow_unknown_keys", !rule.allow_unknown_keys),
+        ("enum", rule.enum_values.is_some()),
+        ("const", rule.const_value.is_some()),
+        ("one_of", rule.one_of.is_some()),
+        ("any_of", rule.any_of.is_some()),
+        ("all_of", rule.all_of.is_some()),
+        ("not", rule.not.is_some()),
+        ("discriminator", rule.discriminator.is_some()),
+        ("if", rule.if_rule.is_some()),
+    ]
+    .into_iter()
+    .find(|(_, is_set)| *is_set)
+    .map(|(keyword, _)| keyword)
+}
+
+/// The type of a rule that left out `type`, a union of no types
+fn missing_type() -> SchemaType {
+    SchemaType::Union(Vec::new())
+}
+
+impl<'de> Deserialize<'de> for SchemaRule {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // The referenced rule decides the type of a rule that only has a `$ref`
+        let mut rule = SchemaRule::deserialize(deserializer)?;
+        if rule.reference.is_some() && rule.data_type == missing_type() {
+            rule.data_type = SchemaType::Any;
+        }
+        Ok(rule)
+    }
 }
 
 fn default_allow_unknown_keys() -> bool {
     true
 }
 
+/// Accept either a single field name or a list of field names
+fn deserialize_field_list<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum FieldList {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(
+        Option::<FieldList>::deserialize(deserializer)?.map(|fields| match fields {
+            FieldList::One(field) => vec![field],
+            FieldList::Many(fields) => fields,
+        }),
+    )
+}
+
+impl Default for SchemaRule {
+    fn default() -> Self {
+        Self {
+            data_type: SchemaType::Any,
+            nullable: false,
+            reference: None,
+            definitions: None,
+            check_severity: None,
+            project: None,
+            severity: None,
+            description: None,
+            error_message: None,
+            hint: None,
+            docs_url: None,
+            required: false,
+            default: None,
+            deprecated: None,
+            renamed_to: None,
+            deprecated_until: None,
+            required_if: None,
+            forbidden_if: None,
+            keys: None,
+            values: None,
+            key_pattern: None,
+            pattern_keys: None,
+            min_keys: None,
+            max_keys: None,
+            allow_unknown_keys: default_allow_unknown_keys(),
+            items: None,
+            prefix_items: None,
+            additional_items: None,
+            unique_items: false,
+            unique_by: None,
+            contains: None,
+            min_contains: None,
+            max_contains: None,
+            min_length: None,
+            max_length: None,
+            format: None,
+            uri_schemes: None,
+            pattern: None,
+            ref_to: None,
+            enum_values: None,
+            const_value: None,
+            case_insensitive: false,
+            min: None,
+            max: None,
+            exclusive_min: None,
+            exclusive_max: None,
+            multiple_of: None,
+            max_decimal_places: None,
+            one_of: None,
+            any_of: None,
+            all_of: None,
+            not: None,
+            exclusive_keys: None,
+            dependent_keys: None,
+            one_required_of: None,
+            discriminator: None,
+            assertions: None,
+            if_rule: None,
+            then_rule: None,
+            else_rule: None,
+        }
+    }
+}
+
+/// Get the key a `$ref` is stored under in `Schema::definitions`
+///
+/// Local references (`#/definitions/<name>`) use the bare name, while
+/// definitions imported from other files keep their resolved
+/// `<path>#/definitions/<name>` form.
+pub(crate) fn definition_key(reference: &str) -> Option<&str> {
+    if let Some(name) = reference.strip_prefix(DEFINITIONS_PREFIX) {
+        return Some(name).filter(|name| !name.is_empty());
+    }
+
+    match reference.split_once(DEFINITIONS_PREFIX) {
+        Some((file, name)) if !file.is_empty() && !name.is_empty() => Some(reference),
+        _ => None,
+    }
+}
+
+impl SchemaRule {
+    /// Direct child rules (object keys and values, list items, combinator branches,
+    /// conditionals and discriminator variants)
+    pub(crate) fn children_mut(&mut self) -> Vec<&mut SchemaRule> {
+        let mut children: Vec<&mut SchemaRule> = Vec::new();
+        if let Some(keys) = &mut self.keys {
+            children.extend(keys.values_mut());
+        }
+        if let Some(values) = &mut self.values {
+            children.push(values);
+        }
+        if let Some(pattern_keys) = &mut self.pattern_keys {
+            children.extend(pattern_keys.values_mut());
+        }
+        if let Some(items) = &mut self.items {
+            children.push(items);
+        }
+        if let Some(prefix_items) = &mut self.prefix_items {
+            children.extend(prefix_items.iter_mut());
+        }
+        if let Some(AdditionalItems::Rule(additional)) = &mut self.additional_items {
+            children.push(additional);
+        }
+        if let Some(contains) = &mut self.contains {
+            children.push(contains);
+        }
+        for branches in [&mut self.one_of, &mut self.any_of, &mut self.all_of]
+            .into_iter()
+            .flatten()
+        {
+            children.extend(branches.iter_mut());
+        }
+        for branch in [
+            &mut self.not,
+            &mut self.if_rule,
+            &mut self.then_rule,
+            &mut self.else_rule,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            children.push(branch);
+        }
+        if let Some(discriminator) = &mut self.discriminator {
+            children.extend(discriminator.mapping.values_mut());
+        }
+        children
+    }
+
+    /// Every rule nested in this one, for walking the whole schema
+    pub(crate) fn children(&self) -> Vec<&SchemaRule> {
+        let mut children: Vec<&SchemaRule> = self.branches();
+        if let Some(keys) = &self.keys {
+            children.extend(keys.values());
+        }
+        if let Some(values) = &self.values {
+            children.push(values);
+        }
+        if let Some(pattern_keys) = &self.pattern_keys {
+            children.extend(pattern_keys.values());
+        }
+        if let Some(items) = &self.items {
+            children.push(items);
+        }
+        if let Some(prefix_items) = &self.prefix_items {
+            children.extend(prefix_items.iter());
+        }
+        if let Some(AdditionalItems::Rule(additional)) = &self.additional_items {
+            children.push(additional);
+        }
+        if let Some(contains) = &self.contains {
+            children.push(contains);
+        }
+        children
+    }
+
+    /// Rules that apply to the same value as this one (combinator branches,
+    /// conditionals and discriminator variants)
+    pub(crate) fn branches(&self) -> Vec<&SchemaRule> {
+        let mut branches: Vec<&SchemaRule> = Vec::new();
+        for list in [&self.one_of, &self.any_of, &self.all_of]
+            .into_iter()
+            .flatten()
+        {
+            branches.extend(list.iter());
+        }
+        for branch in [&self.not, &self.if_rule, &self.then_rule, &self.else_rule]
+            .into_iter()
+            .flatten()
+        {
+            branches.push(branch);
+        }
+        if let Some(discriminator) = &self.discriminator {
+            branches.extend(discriminator.mapping.values());
+        }
+        branches
+    }
+
+    /// Whether `null` is accepted through `nullable` or a `null` entry in a list of types
+    pub(crate) fn is_nullable(&self) -> bool {
+        self.nullable
+            || matches!(&self.data_type, SchemaType::Union(types) if types.contains(&SchemaType::Null))
+    }
+
+    /// Short human-readable label for the rule, used in error messages
+    pub(crate) fn label(&self) -> String {
+        if let Some(description) = &self.description {
+            description.clone()
+        } else if let Some(reference) = &self.reference {
+            reference.clone()
+        } else {
+            self.data_type.to_string()
+        }
+    }
+}
+
 impl Schema {
+    /// Create a schema from its root rule, taking over its top-level `definitions`,
+    /// `check_severity` and `project`
+    pub fn new(mut root: SchemaRule) -> Self {
+        let definitions = root.definitions.take().unwrap_or_default();
+        let check_severity = root.check_severity.take().unwrap_or_default();
+        let project = root.project.take().unwrap_or_default();
+        Schema {
+            root,
+            definitions,
+            check_severity,
+            project,
+        }
+    }
+
+    /// Look up the rule a `$ref` points to
+    pub fn resolve_reference(&self, reference: &str) -> Option<&SchemaRule> {
+        definition_key(reference).and_then(|name| self.definitions.get(name))
+    }
+
     /// Load a schema from a file
+    ///
+    /// References to other files (`$ref: "./common/tls.yaml#/definitions/tls"`)
+    /// are resolved relative to the file containing them. Every referenced
+    /// file is parsed once and its definitions are added to this schema.
+    /// The rules themselves are checked by [`Schema::compile`].
     pub fn from_file<P: AsRef<Path>>(path: P) -> ConfigGuardResult<Self> {
         let path = path.as_ref();
+        let root = Self::parse_file(path)?;
+        let mut schema = Schema::new(root);
+
+        let root_file = path
+            .canonicalize()
+            .map_err(|e| ConfigGuardError::FileRead {
+                path: path.display().to_string(),
+                error: e.to_string(),
+            })?;
+
+        // Rewrite references in the root file, collecting the files they point to
+        let mut pending = Vec::new();
+        resolve_file_references(&mut schema.root, &root_file, &root_file, &mut pending)?;
+        for rule in schema.definitions.values_mut() {
+            resolve_file_references(rule, &root_file, &root_file, &mut pending)?;
+        }
+
+        // Load each referenced file once; import cycles end at the cache
+        let mut loaded = HashSet::from([root_file.clone()]);
+        while let Some(file) = pending.pop() {
+            if !loaded.insert(file.clone()) {
+                continue;
+            }
+
+            let imported = Self::parse_file(&file)?.definitions.unwrap_or_default();
+            for (name, mut rule) in imported {
+                resolve_file_references(&mut rule, &file, &root_file, &mut pending)?;
+                schema.definitions.insert(
+                    format!("{}{}{}", file.display(), DEFINITIONS_PREFIX, name),
+                    rule,
+                );
+            }
+        }
+
+        Ok(schema)
+    }
 
+    /// Read and parse a single schema file
+    fn parse_file(path: &Path) -> ConfigGuardResult<SchemaRule> {
         let file_content = fs::read_to_string(path).map_err(|e| match e.kind() {
             std::io::ErrorKind::NotFound => ConfigGuardError::FileNotFound {
                 path: path.to_path_buf(),
@@ -91,10 +1014,13 @@ impl Schema {
             Err(e) => {
                 // Provide more detailed error information for schema parsing failures
                 let error_msg = if e.to_string().contains("invalid type") {
+                    let names: Vec<String> =
+                        SchemaType::NAMED.iter().map(|t| t.to_string()).collect();
                     format!(
-                        "Failed to parse schema YAML from {}: {}. Check that all types are valid (string, integer, float, boolean, object, list, any, null).",
+                        "Failed to parse schema YAML from {}: {}. Check that all types are valid ({}), or a list of them such as [string, null].",
                         path.display(),
-                        e
+                        e,
+                        names.join(", ")
                     )
                 } else {
                     format!("Failed to parse schema YAML from {}: {}", path.display(), e)
@@ -103,40 +1029,884 @@ impl Schema {
             }
         };
 
-        // Validate the schema itself
-        Self::validate_schema_rule(&root).map_err(|e| ConfigGuardError::Schema(e.to_string()))?;
+        Ok(root)
+    }
+
+    /// Validate the root rule, every definition and the references between them
+    pub(crate) fn validate_schema(&self) -> ConfigGuardResult<()> {
+        self.validate_schema_rule(&self.root)?;
+
+        for path in self.project.paths() {
+            DocumentPath::parse(path).map_err(|e| {
+                ConfigGuardError::Schema(format!("Invalid 'project' path '{}': {}", path, e))
+            })?;
+        }
+
+        let mut names: Vec<&String> = self.definitions.keys().collect();
+        names.sort();
+        for name in names {
+            self.validate_schema_rule(&self.definitions[name])
+                .map_err(|e| {
+                    ConfigGuardError::Schema(format!("Invalid definition '{}': {}", name, e))
+                })?;
+        }
 
-        Ok(Schema { root })
+        self.check_reference_cycles()
     }
 
-    /// Validate the schema itself for correctness
-    fn validate_schema_rule(rule: &SchemaRule) -> ConfigGuardResult<()> {
-        let context = rule.description.as_ref().map_or_else(
-            || format!("for field of type {:?}", rule.data_type),
-            |desc| format!("for field '{}'", desc),
-        );
+    /// Detect `$ref` cycles that never descend into a child value
+    ///
+    /// A definition may refer to itself through `keys` or `items` (e.g. a tree
+    /// of nodes), but a chain of references that loops back without consuming
+    /// any part of the configuration would never terminate.
+    fn check_reference_cycles(&self) -> ConfigGuardResult<()> {
+        fn visit<'a>(
+            schema: &'a Schema,
+            name: &'a str,
+            stack: &mut Vec<&'a str>,
+            done: &mut HashSet<&'a str>,
+        ) -> ConfigGuardResult<()> {
+            if done.contains(name) {
+                return Ok(());
+            }
+            if let Some(start) = stack.iter().position(|n| *n == name) {
+                let mut cycle: Vec<&str> = stack[start..].to_vec();
+                cycle.push(name);
+                return Err(ConfigGuardError::Schema(format!(
+                    "Reference cycle without an intermediate object or list: {}",
+                    cycle.join(" -> ")
+                )));
+            }
 
-        // Type-specific validation
-        match rule.data_type {
-            SchemaType::Object => {
-                // Object should have keys defined
-                if let Some(keys) = &rule.keys {
-                    // Recursively validate each key's rule
-                    for (key_name, key_rule) in keys {
-                        Self::validate_schema_rule(key_rule).map_err(|e| {
-                            ConfigGuardError::Schema(format!(
-                                "Invalid schema rule for key '{}': {}",
-                                key_name, e
-                            ))
-                        })?;
-                    }
+            stack.push(name);
+            if let Some(rule) = schema.definitions.get(name) {
+                for target in non_consuming_references(rule) {
+                    visit(schema, target, stack, done)?;
                 }
+            }
+            stack.pop();
+            done.insert(name);
 
-                // Validate object-specific properties
-                if rule.items.is_some() {
+            Ok(())
+        }
+
+        let mut names: Vec<&str> = self.definitions.keys().map(String::as_str).collect();
+        names.sort();
+
+        let mut done = HashSet::new();
+        for name in names {
+            visit(self, name, &mut Vec::new(), &mut done)?;
+        }
+
+        Ok(())
+    }
+
+    /// Validate `values`, `key_pattern`, `pattern_keys` and `min_keys`/`max_keys`
+    fn validate_dynamic_keys(&self, rule: &SchemaRule, context: &str) -> ConfigGuardResult<()> {
+        for (keyword, is_set) in [
+            ("values", rule.values.is_some()),
+            ("key_pattern", rule.key_pattern.is_some()),
+            ("pattern_keys", rule.pattern_keys.is_some()),
+            ("min_keys", rule.min_keys.is_some()),
+            ("max_keys", rule.max_keys.is_some()),
+        ] {
+            if is_set && rule.data_type != SchemaType::Object {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'{}' is only valid for type 'object', not '{}' {}",
+                    keyword, rule.data_type, context
+                )));
+            }
+        }
+
+        if let (Some(min), Some(max)) = (rule.min_keys, rule.max_keys) {
+            if min > max {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'min_keys' cannot be greater than 'max_keys' {}",
+                    context
+                )));
+            }
+        }
+
+        if let Some(pattern) = &rule.key_pattern {
+            Regex::new(pattern).map_err(|e| {
+                ConfigGuardError::Pattern(format!(
+                    "Invalid regex key_pattern '{}' {}: {}",
+                    pattern, context, e
+                ))
+            })?;
+        }
+
+        if let Some(values) = &rule.values {
+            self.validate_schema_rule(values).map_err(|e| {
+                ConfigGuardError::Schema(format!(
+                    "Invalid schema rule for 'values' {}: {}",
+                    context, e
+                ))
+            })?;
+        }
+
+        if let Some(pattern_keys) = &rule.pattern_keys {
+            let mut patterns: Vec<&String> = pattern_keys.keys().collect();
+            patterns.sort();
+            for pattern in patterns {
+                Regex::new(pattern).map_err(|e| {
+                    ConfigGuardError::Pattern(format!(
+                        "Invalid regex in pattern_keys '{}' {}: {}",
+                        pattern, context, e
+                    ))
+                })?;
+                self.validate_schema_rule(&pattern_keys[pattern])
+                    .map_err(|e| {
+                        ConfigGuardError::Schema(format!(
+                            "Invalid schema rule for keys matching '{}' {}: {}",
+                            pattern, context, e
+                        ))
+                    })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the placeholders of `error_message` and `hint`
+    fn validate_templates(&self, rule: &SchemaRule, context: &str) -> ConfigGuardResult<()> {
+        for (keyword, template) in [("error_message", &rule.error_message), ("hint", &rule.hint)] {
+            for name in template.iter().flat_map(|t| placeholders(t)) {
+                if name == "value" || name == "path" {
+                    continue;
+                }
+                if !RULE_PLACEHOLDERS.contains(&name) {
                     return Err(ConfigGuardError::Schema(format!(
-                        "'items' is only valid for type 'list', not 'object' {}",
-                        context
+                        "Unknown placeholder '{{{}}}' in '{}' {} (use {{value}}, {{path}} or one of: {})",
+                        name,
+                        keyword,
+                        context,
+                        RULE_PLACEHOLDERS.join(", ")
+                    )));
+                }
+                if rule_placeholder(rule, name).is_none() {
+                    return Err(ConfigGuardError::Schema(format!(
+                        "'{}' uses '{{{}}}' but the rule has no '{}' {}",
+                        keyword, name, name, context
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate `prefix_items` and `additional_items`
+    fn validate_tuple(&self, rule: &SchemaRule, context: &str) -> ConfigGuardResult<()> {
+        for (keyword, is_set) in [
+            ("prefix_items", rule.prefix_items.is_some()),
+            ("additional_items", rule.additional_items.is_some()),
+        ] {
+            if is_set && rule.data_type != SchemaType::List {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'{}' is only valid for type 'list', not '{}' {}",
+                    keyword, rule.data_type, context
+                )));
+            }
+        }
+
+        if rule.additional_items.is_some() && rule.prefix_items.is_none() {
+            return Err(ConfigGuardError::Schema(format!(
+                "'additional_items' requires 'prefix_items' {}",
+                context
+            )));
+        }
+
+        if let Some(prefix_items) = &rule.prefix_items {
+            if prefix_items.is_empty() {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'prefix_items' must list at least one rule {}",
+                    context
+                )));
+            }
+            for (i, item_rule) in prefix_items.iter().enumerate() {
+                self.validate_schema_rule(item_rule).map_err(|e| {
+                    ConfigGuardError::Schema(format!(
+                        "Invalid schema rule for list item #{} {}: {}",
+                        i + 1,
+                        context,
+                        e
+                    ))
+                })?;
+            }
+        }
+
+        if let Some(AdditionalItems::Rule(additional)) = &rule.additional_items {
+            self.validate_schema_rule(additional).map_err(|e| {
+                ConfigGuardError::Schema(format!(
+                    "Invalid schema rule for additional list items {}: {}",
+                    context, e
+                ))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Validate `unique_items`, `unique_by` and `contains` with its bounds
+    fn validate_list_contents(&self, rule: &SchemaRule, context: &str) -> ConfigGuardResult<()> {
+        for (keyword, is_set) in [
+            ("unique_items", rule.unique_items),
+            ("unique_by", rule.unique_by.is_some()),
+            ("contains", rule.contains.is_some()),
+            ("min_contains", rule.min_contains.is_some()),
+            ("max_contains", rule.max_contains.is_some()),
+        ] {
+            if is_set && rule.data_type != SchemaType::List {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'{}' is only valid for type 'list', not '{}' {}",
+                    keyword, rule.data_type, context
+                )));
+            }
+        }
+
+        if rule.unique_by.as_ref().is_some_and(Vec::is_empty) {
+            return Err(ConfigGuardError::Schema(format!(
+                "'unique_by' must name at least one field {}",
+                context
+            )));
+        }
+
+        match &rule.contains {
+            Some(contains) => {
+                self.validate_schema_rule(contains).map_err(|e| {
+                    ConfigGuardError::Schema(format!(
+                        "Invalid schema rule for 'contains' {}: {}",
+                        context, e
+                    ))
+                })?;
+            }
+            None if rule.min_contains.is_some() || rule.max_contains.is_some() => {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'min_contains' and 'max_contains' require 'contains' {}",
+                    context
+                )));
+            }
+            None => {}
+        }
+
+        if let (Some(min), Some(max)) = (rule.min_contains, rule.max_contains) {
+            if min > max {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'min_contains' cannot be greater than 'max_contains' {}",
+                    context
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate `min`, `max`, `exclusive_min`, `exclusive_max` and `multiple_of` of a numeric rule
+    ///
+    /// Bounds must be numbers, and for fixed-width integer types they must be
+    /// integers that fit the width.
+    fn validate_numeric_bounds(&self, rule: &SchemaRule, context: &str) -> ConfigGuardResult<()> {
+        for (keyword, bound) in [
+            ("min", &rule.min),
+            ("max", &rule.max),
+            ("exclusive_min", &rule.exclusive_min),
+            ("exclusive_max", &rule.exclusive_max),
+            ("multiple_of", &rule.multiple_of),
+        ] {
+            let Some(bound) = bound else {
+                continue;
+            };
+            let Some(number) = Numeric::from_value(bound) else {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'{}' must be a number {}, found {:?}",
+                    keyword, context, bound
+                )));
+            };
+
+            if let Some((low, high)) = rule.data_type.integer_range() {
+                match number {
+                    Numeric::Int(n) if n < low || n > high => {
+                        return Err(ConfigGuardError::Schema(format!(
+                            "'{}' ({}) does not fit type '{}' (range {} to {}) {}",
+                            keyword, n, rule.data_type, low, high, context
+                        )));
+                    }
+                    Numeric::Float(_) => {
+                        return Err(ConfigGuardError::Schema(format!(
+                            "'{}' ({}) must be an integer for type '{}' {}",
+                            keyword, number, rule.data_type, context
+                        )));
+                    }
+                    Numeric::Int(_) => {}
+                }
+            }
+
+            if keyword == "multiple_of" && number.as_f64() <= 0.0 {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'multiple_of' must be greater than 0 {}",
+                    context
+                )));
+            }
+        }
+
+        let number = |bound: &Option<Value>| bound.as_ref().and_then(Numeric::from_value);
+        if let (Some(min), Some(max)) = (number(&rule.min), number(&rule.max)) {
+            if min.compare(max) == Some(Ordering::Greater) {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'min' ({}) cannot be greater than 'max' ({}) {}",
+                    min, max, context
+                )));
+            }
+        }
+        if let (Some(lower), Some(upper)) =
+            (number(&rule.exclusive_min), number(&rule.exclusive_max))
+        {
+            if lower.compare(upper) != Some(Ordering::Less) {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'exclusive_min' ({}) must be less than 'exclusive_max' ({}) {}",
+                    lower, upper, context
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that a `default` value is one the rule would accept as `data_type`
+    ///
+    /// Only the type (and width, unit or `enum` membership) is checked here;
+    /// the remaining constraints are enforced when the resolved config is validated.
+    fn validate_default(
+        &self,
+        rule: &SchemaRule,
+        data_type: &SchemaType,
+        default: &Value,
+        context: &str,
+    ) -> ConfigGuardResult<()> {
+        if rule.required {
+            return Err(ConfigGuardError::Schema(format!(
+                "'default' cannot be combined with 'required: true' {}",
+                context
+            )));
+        }
+
+        if !validate_type(default, data_type) {
+            return Err(ConfigGuardError::Schema(format!(
+                "'default' must be of type '{}' {}, found {}",
+                data_type,
+                context,
+                value_type_name(default)
+            )));
+        }
+
+        if let Some(quantity) = Quantity::of(data_type) {
+            if let Err(e) = quantity.parse_value(default) {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'default' is not a valid {} {}: {}",
+                    quantity.name(),
+                    context,
+                    e
+                )));
+            }
+        }
+
+        if let (Some((low, high)), Some(Numeric::Int(n))) =
+            (data_type.integer_range(), Numeric::from_value(default))
+        {
+            if n < low || n > high {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'default' ({}) does not fit type '{}' (range {} to {}) {}",
+                    n, data_type, low, high, context
+                )));
+            }
+        }
+
+        if let Some(allowed) = &rule.const_value {
+            if !values_equal(default, allowed, rule.case_insensitive) {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'default' ({}) does not match 'const' ({}) {}",
+                    display_value(default),
+                    display_value(allowed),
+                    context
+                )));
+            }
+        }
+
+        if let Some(allowed) = &rule.enum_values {
+            if !allowed
+                .iter()
+                .any(|v| values_equal(default, v, rule.case_insensitive))
+            {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'default' ({}) is not one of the allowed enum values {}",
+                    display_value(default),
+                    context
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate a discriminator and each of its variants
+    fn validate_discriminator(
+        &self,
+        rule: &SchemaRule,
+        discriminator: &Discriminator,
+        context: &str,
+    ) -> ConfigGuardResult<()> {
+        if rule.data_type != SchemaType::Object {
+            return Err(ConfigGuardError::Schema(format!(
+                "'discriminator' is only valid for type 'object', not '{}' {}",
+                rule.data_type, context
+            )));
+        }
+        if discriminator.mapping.is_empty() {
+            return Err(ConfigGuardError::Schema(format!(
+                "'discriminator' mapping for field '{}' must list at least one value {}",
+                discriminator.field, context
+            )));
+        }
+
+        let mut values: Vec<&String> = discriminator.mapping.keys().collect();
+        values.sort();
+        for value in values {
+            let variant = &discriminator.mapping[value];
+            self.validate_schema_rule(variant).map_err(|e| {
+                ConfigGuardError::Schema(format!(
+                    "Invalid schema rule for discriminator value '{}' {}: {}",
+                    value, context, e
+                ))
+            })?;
+            if variant.data_type != SchemaType::Object && variant.reference.is_none() {
+                return Err(ConfigGuardError::Schema(format!(
+                    "Discriminator value '{}' {} must map to an object rule or a '$ref'",
+                    value, context
+                )));
+            }
+
+            // Follow `$ref`s the way validation selects the variant; a cycle is
+            // reported by `check_reference_cycles`
+            let mut target = variant;
+            let mut seen = HashSet::new();
+            loop {
+                if let Some(keyword) = unsupported_variant_keyword(target) {
+                    return Err(ConfigGuardError::Schema(format!(
+                        "'{}' is not supported for discriminator value '{}' {}: a variant only adds \
+                         'keys', 'exclusive_keys', 'dependent_keys', 'one_required_of' and 'assert' to the object",
+                        keyword, value, context
+                    )));
+                }
+                let Some(reference) = target
+                    .reference
+                    .as_deref()
+                    .filter(|_| target.keys.is_none())
+                else {
+                    break;
+                };
+                match self.resolve_reference(reference) {
+                    Some(definition) if seen.insert(reference) => target = definition,
+                    _ => break,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate a rule whose type is a list of types
+    ///
+    /// Constraints apply to whichever type a value matches, so each
+    /// type-specific keyword must suit at least one of the non-null types, and
+    /// the `default` may be of any of them. The rule is then checked for each
+    /// member with only the keywords that suit it.
+    fn validate_union(
+        &self,
+        rule: &SchemaRule,
+        types: &[SchemaType],
+        context: &str,
+    ) -> ConfigGuardResult<()> {
+        if types.is_empty() {
+            return Err(ConfigGuardError::Schema(format!(
+                "'type' must list at least one type {}",
+                context
+            )));
+        }
+        for (i, data_type) in types.iter().enumerate() {
+            if types[..i].contains(data_type) {
+                return Err(ConfigGuardError::Schema(format!(
+                    "Type '{}' is listed more than once in 'type' {}",
+                    data_type, context
+                )));
+            }
+        }
+
+        let mut candidates: Vec<&SchemaType> =
+            types.iter().filter(|t| **t != SchemaType::Null).collect();
+        if candidates.is_empty() {
+            candidates.push(&SchemaType::Null);
+        }
+
+        if let Some(default) = &rule.default {
+            self.validate_union_default(rule, types, default, context)?;
+        }
+        self.validate_templates(rule, context)?;
+
+        // With a single member its checks apply as they are, with their own messages
+        if let [data_type] = candidates[..] {
+            return self.validate_schema_rule(&SchemaRule {
+                data_type: data_type.clone(),
+                default: None,
+                error_message: None,
+                hint: None,
+                ..rule.clone()
+            });
+        }
+
+        let fits: Vec<_> = candidates
+            .iter()
+            .map(|data_type| type_keywords(rule, data_type))
+            .collect();
+        for (i, (keyword, _)) in fits[0].iter().enumerate() {
+            if !fits.iter().any(|member| member[i].1) {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'{}' does not apply to any of the types '{}' {}",
+                    keyword, rule.data_type, context
+                )));
+            }
+        }
+
+        for (i, data_type) in candidates.iter().enumerate() {
+            let mut member = member_rule(rule, data_type);
+            // Rules applied whatever the type are checked with the first member only
+            if i > 0 {
+                member.one_of = None;
+                member.any_of = None;
+                member.all_of = None;
+                member.not = None;
+                member.if_rule = None;
+                member.then_rule = None;
+                member.else_rule = None;
+            }
+            self.validate_schema_rule(&member)?;
+        }
+        Ok(())
+    }
+
+    /// Check the `default` of a union rule against each member it could be
+    fn validate_union_default(
+        &self,
+        rule: &SchemaRule,
+        types: &[SchemaType],
+        default: &Value,
+        context: &str,
+    ) -> ConfigGuardResult<()> {
+        // The type, `enum` and `const` are checked for the union as a whole
+        self.validate_default(rule, &rule.data_type, default, context)?;
+
+        let mut first_error = None;
+        for data_type in types.iter().filter(|t| validate_type(default, t)) {
+            match self.validate_default(rule, data_type, default, context) {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Validate the schema itself for correctness
+    pub(crate) fn validate_schema_rule(&self, rule: &SchemaRule) -> ConfigGuardResult<()> {
+        let context = rule.description.as_ref().map_or_else(
+            || format!("for field of type {:?}", rule.data_type),
+            |desc| format!("for field '{}'", desc),
+        );
+
+        if rule.data_type == missing_type() {
+            return Err(ConfigGuardError::Schema(format!(
+                "Missing 'type' {}: every rule needs a 'type' unless it has a '$ref'",
+                rule.description.as_ref().map_or_else(
+                    || "for field".to_string(),
+                    |desc| format!("for field '{}'", desc)
+                )
+            )));
+        }
+
+        if let SchemaType::Union(types) = &rule.data_type {
+            return self.validate_union(rule, types, &context);
+        }
+
+        if rule.definitions.is_some() {
+            return Err(ConfigGuardError::Schema(format!(
+                "'definitions' is only allowed at the top level of a schema {}",
+                context
+            )));
+        }
+        if rule.check_severity.is_some() {
+            return Err(ConfigGuardError::Schema(format!(
+                "'check_severity' is only allowed at the top level of a schema {}",
+                context
+            )));
+        }
+        if rule.project.is_some() {
+            return Err(ConfigGuardError::Schema(format!(
+                "'project' is only allowed at the top level of a schema {}",
+                context
+            )));
+        }
+
+        // References must point at an existing definition
+        if let Some(reference) = &rule.reference {
+            match definition_key(reference) {
+                None => {
+                    return Err(ConfigGuardError::Schema(format!(
+                        "Unsupported reference '{}' {}: expected '{}<name>' or '<file>{}<name>'",
+                        reference, context, DEFINITIONS_PREFIX, DEFINITIONS_PREFIX
+                    )));
+                }
+                Some(name) if !self.definitions.contains_key(name) => {
+                    return Err(ConfigGuardError::Schema(format!(
+                        "Unresolved reference '{}' {}: no definition named '{}'",
+                        reference, context, name
+                    )));
+                }
+                Some(_) => {}
+            }
+        }
+
+        // Combinator branches are full rules applied to the same value
+        for (keyword, branches) in [
+            ("one_of", &rule.one_of),
+            ("any_of", &rule.any_of),
+            ("all_of", &rule.all_of),
+        ] {
+            if let Some(branches) = branches {
+                if branches.is_empty() {
+                    return Err(ConfigGuardError::Schema(format!(
+                        "'{}' must list at least one rule {}",
+                        keyword, context
+                    )));
+                }
+                for (i, branch) in branches.iter().enumerate() {
+                    self.validate_schema_rule(branch).map_err(|e| {
+                        ConfigGuardError::Schema(format!(
+                            "Invalid schema rule for {} branch #{} {}: {}",
+                            keyword,
+                            i + 1,
+                            context,
+                            e
+                        ))
+                    })?;
+                }
+            }
+        }
+        for (keyword, branch) in [
+            ("not", &rule.not),
+            ("if", &rule.if_rule),
+            ("then", &rule.then_rule),
+            ("else", &rule.else_rule),
+        ] {
+            if let Some(branch) = branch {
+                self.validate_schema_rule(branch).map_err(|e| {
+                    ConfigGuardError::Schema(format!(
+                        "Invalid schema rule for '{}' {}: {}",
+                        keyword, context, e
+                    ))
+                })?;
+            }
+        }
+        if rule.if_rule.is_none() && (rule.then_rule.is_some() || rule.else_rule.is_some()) {
+            return Err(ConfigGuardError::Schema(format!(
+                "'then' and 'else' require an 'if' condition {}",
+                context
+            )));
+        }
+
+        // Conditional presence only makes sense for keys that are not always required
+        for (keyword, conditions) in [
+            ("required_if", &rule.required_if),
+            ("forbidden_if", &rule.forbidden_if),
+        ] {
+            if let Some(conditions) = conditions {
+                if conditions.is_empty() {
+                    return Err(ConfigGuardError::Schema(format!(
+                        "'{}' must list at least one field {}",
+                        keyword, context
+                    )));
+                }
+                if rule.required {
+                    return Err(ConfigGuardError::Schema(format!(
+                        "'{}' cannot be combined with 'required: true' {}",
+                        keyword, context
+                    )));
+                }
+            }
+        }
+
+        if rule.enum_values.as_ref().is_some_and(Vec::is_empty) {
+            return Err(ConfigGuardError::Schema(format!(
+                "'enum' must list at least one value {}",
+                context
+            )));
+        }
+        if rule.case_insensitive && rule.enum_values.is_none() && rule.const_value.is_none() {
+            return Err(ConfigGuardError::Schema(format!(
+                "'case_insensitive' requires 'enum' or 'const' {}",
+                context
+            )));
+        }
+
+        if let Some(default) = &rule.default {
+            self.validate_default(rule, &rule.data_type, default, &context)?;
+        }
+
+        self.validate_templates(rule, &context)?;
+        if let Some(url) = &rule.docs_url {
+            StringFormat::Uri.check(url).map_err(|e| {
+                ConfigGuardError::Schema(format!("Invalid 'docs_url' {}: {}", context, e))
+            })?;
+        }
+
+        let is_deprecated = rule.deprecated.is_some() || rule.renamed_to.is_some();
+        if is_deprecated && rule.required {
+            return Err(ConfigGuardError::Schema(format!(
+                "Deprecated or renamed keys cannot be 'required: true' {}",
+                context
+            )));
+        }
+        if let Some(until) = &rule.deprecated_until {
+            if !is_deprecated {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'deprecated_until' requires 'deprecated' or 'renamed_to' {}",
+                    context
+                )));
+            }
+            check_date(until).map_err(|e| {
+                ConfigGuardError::Schema(format!(
+                    "'deprecated_until' must be a YYYY-MM-DD date {}: {}",
+                    context, e
+                ))
+            })?;
+        }
+
+        if let Some(discriminator) = &rule.discriminator {
+            self.validate_discriminator(rule, discriminator, &context)?;
+        }
+
+        self.validate_dynamic_keys(rule, &context)?;
+        self.validate_tuple(rule, &context)?;
+        self.validate_list_contents(rule, &context)?;
+
+        for (keyword, is_set) in [
+            ("exclusive_min", rule.exclusive_min.is_some()),
+            ("exclusive_max", rule.exclusive_max.is_some()),
+            ("multiple_of", rule.multiple_of.is_some()),
+        ] {
+            if is_set && !rule.data_type.is_numeric() {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'{}' is only valid for numeric types, not '{}' {}",
+                    keyword, rule.data_type, context
+                )));
+            }
+        }
+        if rule.max_decimal_places.is_some() && rule.data_type != SchemaType::Float {
+            return Err(ConfigGuardError::Schema(format!(
+                "'max_decimal_places' is only valid for type 'float', not '{}' {}",
+                rule.data_type, context
+            )));
+        }
+
+        if rule.format.is_some() && rule.data_type != SchemaType::String {
+            return Err(ConfigGuardError::Schema(format!(
+                "'format' is only valid for type 'string', not '{}' {}",
+                rule.data_type, context
+            )));
+        }
+        if let Some(path) = &rule.ref_to {
+            if rule.data_type != SchemaType::String {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'ref_to' is only valid for type 'string', not '{}' {}",
+                    rule.data_type, context
+                )));
+            }
+            DocumentPath::parse(path).map_err(|e| {
+                ConfigGuardError::Schema(format!(
+                    "Invalid 'ref_to' path '{}' {}: {}",
+                    path, context, e
+                ))
+            })?;
+        }
+        if rule.uri_schemes.is_some() && rule.format != Some(StringFormat::Uri) {
+            return Err(ConfigGuardError::Schema(format!(
+                "'uri_schemes' requires 'format: uri' {}",
+                context
+            )));
+        }
+
+        // Key groups relate keys of the same object
+        for (keyword, groups, min_size) in [
+            ("exclusive_keys", &rule.exclusive_keys, 2),
+            ("dependent_keys", &rule.dependent_keys, 2),
+            ("one_required_of", &rule.one_required_of, 1),
+        ] {
+            let Some(groups) = groups else {
+                continue;
+            };
+            if rule.data_type != SchemaType::Object {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'{}' is only valid for type 'object', not '{}' {}",
+                    keyword, rule.data_type, context
+                )));
+            }
+            for group in groups {
+                if group.len() < min_size {
+                    return Err(ConfigGuardError::Schema(format!(
+                        "Each '{}' group must list at least {} keys {}",
+                        keyword, min_size, context
+                    )));
+                }
+            }
+        }
+
+        if let Some(assertions) = &rule.assertions {
+            if rule.data_type != SchemaType::Object {
+                return Err(ConfigGuardError::Schema(format!(
+                    "'assert' is only valid for type 'object', not '{}' {}",
+                    rule.data_type, context
+                )));
+            }
+            for assertion in assertions {
+                expression::parse(&assertion.expr).map_err(|e| {
+                    ConfigGuardError::Schema(format!(
+                        "Invalid 'assert' expression '{}' {}: {}",
+                        assertion.expr, context, e
+                    ))
+                })?;
+            }
+        }
+
+        // Type-specific validation
+        match rule.data_type {
+            SchemaType::Object => {
+                // Object should have keys defined
+                if let Some(keys) = &rule.keys {
+                    // Recursively validate each key's rule
+                    for (key_name, key_rule) in keys {
+                        self.validate_schema_rule(key_rule).map_err(|e| {
+                            ConfigGuardError::Schema(format!(
+                                "Invalid schema rule for key '{}': {}",
+                                key_name, e
+                            ))
+                        })?;
+                    }
+                }
+
+                // Validate object-specific properties
+                if rule.items.is_some() {
+                    return Err(ConfigGuardError::Schema(format!(
+                        "'items' is only valid for type 'list', not 'object' {}",
+                        context
                     )));
                 }
                 if rule.min_length.is_some() || rule.max_length.is_some() {
@@ -181,7 +1951,7 @@ impl Schema {
 
                 // If 'items' is defined, recursively validate it
                 if let Some(items_rule) = &rule.items {
-                    Self::validate_schema_rule(items_rule).map_err(|e| {
+                    self.validate_schema_rule(items_rule).map_err(|e| {
                         ConfigGuardError::Schema(format!(
                             "Invalid schema rule for list items {}: {}",
                             context, e
@@ -240,7 +2010,18 @@ impl Schema {
                     })?;
                 }
             }
-            SchemaType::Integer | SchemaType::Float => {
+            SchemaType::Integer
+            | SchemaType::Float
+            | SchemaType::U8
+            | SchemaType::U16
+            | SchemaType::U32
+            | SchemaType::U64
+            | SchemaType::I8
+            | SchemaType::I16
+            | SchemaType::I32
+            | SchemaType::I64 => {
+                self.validate_numeric_bounds(rule, &context)?;
+
                 // Validate number-specific properties
                 if rule.keys.is_some() {
                     return Err(ConfigGuardError::Schema(format!(
@@ -265,24 +2046,57 @@ impl Schema {
                         context
                     )));
                 }
+            }
+            SchemaType::Duration | SchemaType::ByteSize | SchemaType::Percentage => {
+                if rule.keys.is_some() {
+                    return Err(ConfigGuardError::Schema(format!(
+                        "'keys' is only valid for type 'object', not '{}' {}",
+                        rule.data_type, context
+                    )));
+                }
+                if rule.items.is_some() {
+                    return Err(ConfigGuardError::Schema(format!(
+                        "'items' is only valid for type 'list', not '{}' {}",
+                        rule.data_type, context
+                    )));
+                }
+                if rule.min_length.is_some() || rule.max_length.is_some() {
+                    return Err(ConfigGuardError::Schema(format!(
+                        "'min_length' and 'max_length' are only valid for type 'string' or 'list', not '{}' {}",
+                        rule.data_type, context
+                    )));
+                }
+                if rule.pattern.is_some() {
+                    return Err(ConfigGuardError::Schema(format!(
+                        "'pattern' is only valid for type 'string', not '{}' {}",
+                        rule.data_type, context
+                    )));
+                }
 
-                // Validate min/max relationship if both are specified
-                if let (Some(min), Some(max)) = (&rule.min, &rule.max) {
-                    // Safely extract numeric values for comparison
-                    let min_val = min.as_f64();
-                    let max_val = max.as_f64();
-
-                    if let (Some(min_num), Some(max_num)) = (min_val, max_val) {
-                        if min_num > max_num {
-                            return Err(ConfigGuardError::Schema(format!(
-                                "'min' ({}) cannot be greater than 'max' ({}) {}",
-                                min_num, max_num, context
-                            )));
-                        }
-                    } else {
+                // Bounds are written in the same units as the values, e.g. `min: 1s`
+                let quantity = Quantity::of(&rule.data_type).expect("quantity type");
+                let parse_bound = |keyword: &str, bound: &Option<Value>| {
+                    bound
+                        .as_ref()
+                        .map(|bound| {
+                            quantity.parse_value(bound).map_err(|e| {
+                                ConfigGuardError::Schema(format!(
+                                    "Invalid '{}' for type '{}' {}: {}",
+                                    keyword, rule.data_type, context, e
+                                ))
+                            })
+                        })
+                        .transpose()
+                };
+                let min = parse_bound("min", &rule.min)?;
+                let max = parse_bound("max", &rule.max)?;
+                if let (Some(min_amount), Some(max_amount)) = (min, max) {
+                    if min_amount > max_amount {
                         return Err(ConfigGuardError::Schema(format!(
-                            "Non-numeric values used for 'min'/'max' in schema {} - min: {:?}, max: {:?}",
-                            context, min, max
+                            "'min' ({}) cannot be greater than 'max' ({}) {}",
+                            quantity.describe(min_amount),
+                            quantity.describe(max_amount),
+                            context
                         )));
                     }
                 }
@@ -319,6 +2133,8 @@ impl Schema {
                     )));
                 }
             }
+            // Unions are checked per member type by `validate_union`
+            SchemaType::Union(_) => {}
             SchemaType::Any => {
                 // Any type has fewer restrictions, but should still not have type-specific constraints
                 if rule.keys.is_some() {
@@ -357,6 +2173,68 @@ impl Schema {
     }
 }
 
+/// Rewrite the `$ref`s in a rule loaded from `file` to their resolved form
+///
+/// Local references in imported files are qualified with the file's path, and
+/// file references are resolved relative to the directory of `file`. Files that
+/// still need to be loaded are pushed onto `pending`.
+fn resolve_file_references(
+    rule: &mut SchemaRule,
+    file: &Path,
+    root_file: &Path,
+    pending: &mut Vec<PathBuf>,
+) -> ConfigGuardResult<()> {
+    if let Some(reference) = &rule.reference {
+        if let Some((target, name)) = reference.split_once(DEFINITIONS_PREFIX) {
+            let target_file = if target.is_empty() {
+                file.to_path_buf()
+            } else {
+                let relative = file.parent().unwrap_or(Path::new("")).join(target);
+                relative.canonicalize().map_err(|e| {
+                    ConfigGuardError::Schema(format!(
+                        "Cannot resolve '$ref: {}' in {}: {}: {}",
+                        reference,
+                        file.display(),
+                        relative.display(),
+                        e
+                    ))
+                })?
+            };
+
+            let resolved = if target_file == root_file {
+                format!("{}{}", DEFINITIONS_PREFIX, name)
+            } else {
+                pending.push(target_file.clone());
+                format!("{}{}{}", target_file.display(), DEFINITIONS_PREFIX, name)
+            };
+            rule.reference = Some(resolved);
+        }
+    }
+
+    for child in rule.children_mut() {
+        resolve_file_references(child, file, root_file, pending)?;
+    }
+
+    Ok(())
+}
+
+/// Definitions a rule refers to without descending into a child value
+fn non_consuming_references(rule: &SchemaRule) -> Vec<&str> {
+    let mut references: Vec<&str> = rule
+        .reference
+        .as_deref()
+        .and_then(definition_key)
+        .into_iter()
+        .collect();
+
+    // Combinator branches apply to the same value, so their references count too
+    for branch in rule.branches() {
+        references.extend(non_consuming_references(branch));
+    }
+
+    references
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -370,6 +2248,13 @@ mod tests {
         file
     }
 
+    // Helper to load a schema file and compile it, which is where the schema is checked
+    fn load_schema_file(path: impl AsRef<Path>) -> ConfigGuardResult<Schema> {
+        let schema = Schema::from_file(path)?;
+        schema.clone().compile()?;
+        Ok(schema)
+    }
+
     #[test]
     fn test_load_valid_schema() {
         let schema_content = r#"
@@ -384,7 +2269,7 @@ mod tests {
         "#;
 
         let schema_file = create_temp_schema_file(schema_content);
-        let schema = Schema::from_file(schema_file.path()).unwrap();
+        let schema = load_schema_file(schema_file.path()).unwrap();
 
         assert_eq!(schema.root.data_type, SchemaType::Object);
         assert!(schema.root.keys.is_some());
@@ -406,7 +2291,7 @@ mod tests {
     fn test_invalid_schema_syntax() {
         let invalid_schema = "type: object\n  invalid-yaml-indentation";
         let schema_file = create_temp_schema_file(invalid_schema);
-        let result = Schema::from_file(schema_file.path());
+        let result = load_schema_file(schema_file.path());
         assert!(result.is_err());
     }
 
@@ -414,8 +2299,19 @@ mod tests {
     fn test_schema_validation_invalid_type() {
         let schema_content = "type: invalidtype";
         let schema_file = create_temp_schema_file(schema_content);
-        let result = Schema::from_file(schema_file.path());
+        let result = load_schema_file(schema_file.path());
         assert!(result.is_err());
+
+        // The hint lists every type and the list syntax for unions
+        let schema_file = create_temp_schema_file("type: [string, { kind: null }]");
+        let message = load_schema_file(schema_file.path())
+            .unwrap_err()
+            .to_string();
+        assert!(
+            message.contains("(string, integer, float, boolean, object, list, any, null, duration, byte_size, percentage, u8, u16, u32, u64, i8, i16, i32, i64), or a list of them such as [string, null]"),
+            "{}",
+            message
+        );
     }
 
     #[test]
@@ -427,7 +2323,7 @@ mod tests {
             max: 10
         "#;
         let schema_file = create_temp_schema_file(schema_content);
-        let result = Schema::from_file(schema_file.path());
+        let result = load_schema_file(schema_file.path());
         assert!(result.is_err());
 
         // Object with list constraints
@@ -437,7 +2333,7 @@ mod tests {
               type: string
         "#;
         let schema_file = create_temp_schema_file(schema_content);
-        let result = Schema::from_file(schema_file.path());
+        let result = load_schema_file(schema_file.path());
         assert!(result.is_err());
     }
 
@@ -448,8 +2344,12 @@ mod tests {
             pattern: "*[invalid regex"
         "#;
         let schema_file = create_temp_schema_file(schema_content);
-        let result = Schema::from_file(schema_file.path());
+        let result = load_schema_file(schema_file.path());
         assert!(result.is_err());
+
+        // The schema is checked once, so the error is reported as it was raised
+        let err = crate::Validator::from_schema_file(schema_file.path()).unwrap_err();
+        assert!(matches!(err, ConfigGuardError::Pattern(_)), "{}", err);
     }
 
     #[test]
@@ -467,7 +2367,7 @@ mod tests {
                     allow_unknown_keys: true
         "#;
         let schema_file = create_temp_schema_file(schema_content);
-        let schema = Schema::from_file(schema_file.path()).unwrap();
+        let schema = load_schema_file(schema_file.path()).unwrap();
 
         let keys = schema.root.keys.as_ref().unwrap();
         let metadata = keys.get("metadata").unwrap();
@@ -481,4 +2381,981 @@ mod tests {
         assert_eq!(labels.data_type, SchemaType::Object);
         assert!(labels.allow_unknown_keys);
     }
+
+    #[test]
+    fn test_schema_definitions_and_references() {
+        let schema_content = r##"
+            type: object
+            definitions:
+              port:
+                type: integer
+                min: 1
+                max: 65535
+              node:
+                type: object
+                keys:
+                  name: { type: string, required: true }
+                  children:
+                    type: list
+                    items: { $ref: "#/definitions/node" }
+            keys:
+              port: { $ref: "#/definitions/port", required: true }
+              tree: { $ref: "#/definitions/node" }
+        "##;
+        let schema_file = create_temp_schema_file(schema_content);
+        let schema = load_schema_file(schema_file.path()).unwrap();
+
+        assert!(schema.root.definitions.is_none());
+        assert_eq!(schema.definitions.len(), 2);
+
+        let port = &schema.root.keys.as_ref().unwrap()["port"];
+        assert_eq!(port.data_type, SchemaType::Any);
+        assert!(port.required);
+        let target = schema
+            .resolve_reference(port.reference.as_ref().unwrap())
+            .unwrap();
+        assert_eq!(target.data_type, SchemaType::Integer);
+    }
+
+    #[test]
+    fn test_schema_dangling_reference() {
+        let schema_content = r##"
+            type: object
+            definitions:
+              port: { type: integer }
+            keys:
+              port: { $ref: "#/definitions/prot" }
+        "##;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err.to_string().contains("no definition named 'prot'"));
+
+        let schema_content = r##"
+            type: object
+            keys:
+              port: { $ref: "definitions/port" }
+        "##;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err.to_string().contains("Unsupported reference"));
+    }
+
+    #[test]
+    fn test_schema_missing_type() {
+        // A misspelled `type` must not turn the rule into `any`
+        for schema_content in [
+            "type: object\nkeys:\n  port:\n    typ: integer\n",
+            "type: object\nkeys:\n  port:\n    min: 1\n",
+            "keys:\n  port: { type: integer }\n",
+        ] {
+            let schema_file = create_temp_schema_file(schema_content);
+            let err = load_schema_file(schema_file.path()).unwrap_err();
+            assert!(err.to_string().contains("Missing 'type'"), "{}", err);
+        }
+
+        let schema_file =
+            create_temp_schema_file("type: object\nkeys:\n  port: { $ref: \"#/definitions/port\" }\ndefinitions:\n  port: { type: integer }\n");
+        let schema = load_schema_file(schema_file.path()).unwrap();
+        assert_eq!(
+            schema.root.keys.as_ref().unwrap()["port"].data_type,
+            SchemaType::Any
+        );
+    }
+
+    #[test]
+    fn test_schema_reference_cycle() {
+        let schema_content = r##"
+            type: object
+            definitions:
+              a: { $ref: "#/definitions/b" }
+              b: { $ref: "#/definitions/c" }
+              c: { $ref: "#/definitions/a" }
+            keys:
+              value: { $ref: "#/definitions/a" }
+        "##;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err.to_string().contains("a -> b -> c -> a"));
+    }
+
+    #[test]
+    fn test_schema_combinators() {
+        let schema_content = r##"
+            type: object
+            definitions:
+              port: { type: integer, min: 1, max: 65535 }
+            keys:
+              listen:
+                type: any
+                any_of:
+                  - { $ref: "#/definitions/port" }
+                  - { type: string }
+                not: { type: string, enum: [""] }
+        "##;
+        let schema_file = create_temp_schema_file(schema_content);
+        let schema = load_schema_file(schema_file.path()).unwrap();
+        let listen = &schema.root.keys.as_ref().unwrap()["listen"];
+        assert_eq!(listen.any_of.as_ref().unwrap().len(), 2);
+        assert!(listen.not.is_some());
+
+        let schema_content = r#"
+            type: object
+            keys:
+              listen: { type: any, one_of: [] }
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'one_of' must list at least one rule"));
+
+        let schema_content = r#"
+            type: object
+            keys:
+              listen:
+                type: string
+                all_of:
+                  - { type: string, min: 1 }
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err.to_string().contains("all_of branch #1"));
+    }
+
+    #[test]
+    fn test_schema_reference_cycle_through_combinators() {
+        let schema_content = r##"
+            type: object
+            definitions:
+              a:
+                type: any
+                any_of:
+                  - { type: string }
+                  - { $ref: "#/definitions/b" }
+              b: { type: any, not: { $ref: "#/definitions/a" } }
+            keys:
+              value: { $ref: "#/definitions/a" }
+        "##;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err.to_string().contains("a -> b -> a"));
+    }
+
+    #[test]
+    fn test_schema_discriminator_validation() {
+        let schema_content = r#"
+            type: object
+            discriminator:
+              field: type
+              mapping:
+                s3: { type: string }
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err.to_string().contains(
+            "Discriminator value 's3' for field of type Object must map to an object rule"
+        ));
+
+        let schema_content = r#"
+            type: list
+            discriminator: { field: type, mapping: { s3: { type: object } } }
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'discriminator' is only valid for type 'object', not 'list'"));
+
+        // Keywords that are not merged into the object rule are rejected
+        for (variant, keyword) in [
+            ("{ type: object, min_keys: 2 }", "min_keys"),
+            ("{ type: object, values: { type: string } }", "values"),
+            (
+                "{ type: object, allow_unknown_keys: false }",
+                "allow_unknown_keys",
+            ),
+            ("{ type: object, any_of: [{ type: object }] }", "any_of"),
+            (
+                "{ type: object, if: { type: object }, then: { type: object } }",
+                "if",
+            ),
+            ("{ $ref: \"#/definitions/s3\" }", "max_keys"),
+            (
+                "{ type: object, $ref: \"#/definitions/s3\", keys: { a: { type: string } } }",
+                "$ref",
+            ),
+        ] {
+            let schema_content = format!(
+                "type: object\ndefinitions:\n  s3: {{ type: object, max_keys: 3 }}\ndiscriminator: {{ field: type, mapping: {{ s3: {} }} }}\n",
+                variant
+            );
+            let schema_file = create_temp_schema_file(&schema_content);
+            let err = load_schema_file(schema_file.path()).unwrap_err();
+            assert!(
+                err.to_string().contains(&format!(
+                    "'{}' is not supported for discriminator value 's3'",
+                    keyword
+                )),
+                "{}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_schema_conditionals_validation() {
+        let schema_content = r#"
+            type: object
+            then: { type: object }
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'then' and 'else' require an 'if'"));
+
+        let schema_content = r#"
+            type: object
+            keys:
+              enabled: { type: boolean }
+              cert_file: { type: string, required: true, required_if: { enabled: true } }
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'required_if' cannot be combined with 'required: true'"));
+    }
+
+    #[test]
+    fn test_schema_key_groups_validation() {
+        let schema_content = r#"
+            type: list
+            exclusive_keys: [[a, b]]
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'exclusive_keys' is only valid for type 'object', not 'list'"));
+
+        let schema_content = r#"
+            type: object
+            dependent_keys: [[cert]]
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Each 'dependent_keys' group must list at least 2 keys"));
+    }
+
+    #[test]
+    fn test_schema_dynamic_keys_validation() {
+        let schema_content = r#"
+            type: string
+            values: { type: string }
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'values' is only valid for type 'object', not 'string'"));
+
+        let schema_content = r#"
+            type: object
+            pattern_keys:
+              "[invalid": { type: string }
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Invalid regex in pattern_keys '[invalid'"));
+
+        let schema_content = r#"
+            type: object
+            min_keys: 3
+            max_keys: 1
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'min_keys' cannot be greater than 'max_keys'"));
+    }
+
+    #[test]
+    fn test_schema_tuple_validation() {
+        let schema_content = r#"
+            type: list
+            prefix_items: [{ type: string }, { type: integer }]
+            additional_items: { type: boolean }
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let schema = load_schema_file(schema_file.path()).unwrap();
+        assert_eq!(schema.root.prefix_items.as_ref().unwrap().len(), 2);
+        assert!(matches!(
+            schema.root.additional_items,
+            Some(AdditionalItems::Rule(_))
+        ));
+
+        let schema_content = r#"
+            type: list
+            items: { type: string }
+            additional_items: false
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'additional_items' requires 'prefix_items'"));
+    }
+
+    #[test]
+    fn test_schema_list_contents_validation() {
+        let schema_content = r#"
+            type: object
+            keys:
+              names: { type: list, unique_by: name }
+              ports: { type: list, unique_by: [port, protocol] }
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let schema = load_schema_file(schema_file.path()).unwrap();
+        let keys = schema.root.keys.as_ref().unwrap();
+        assert_eq!(keys["names"].unique_by, Some(vec!["name".to_string()]));
+        assert_eq!(
+            keys["ports"].unique_by,
+            Some(vec!["port".to_string(), "protocol".to_string()])
+        );
+
+        let schema_content = r#"
+            type: list
+            max_contains: 1
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'min_contains' and 'max_contains' require 'contains'"));
+
+        let schema_content = r#"
+            type: object
+            unique_items: true
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'unique_items' is only valid for type 'list', not 'object'"));
+    }
+
+    #[test]
+    fn test_schema_format_validation() {
+        let schema_content = r#"
+            type: string
+            format: date-time
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let schema = load_schema_file(schema_file.path()).unwrap();
+        assert_eq!(schema.root.format, Some(StringFormat::DateTime));
+
+        let schema_content = r#"
+            type: string
+            format: phone-number
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        assert!(load_schema_file(schema_file.path()).is_err());
+
+        let schema_content = r#"
+            type: string
+            format: hostname
+            uri_schemes: [https]
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'uri_schemes' requires 'format: uri'"));
+    }
+
+    #[test]
+    fn test_schema_quantity_bounds() {
+        let schema_content = r#"
+            type: duration
+            min: 1s
+            max: 5m
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let schema = load_schema_file(schema_file.path()).unwrap();
+        assert_eq!(schema.root.data_type, SchemaType::Duration);
+
+        let schema_content = r#"
+            type: duration
+            min: 10m
+            max: 5m
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'min' (600 seconds) cannot be greater than 'max' (300 seconds)"));
+
+        let schema_content = r#"
+            type: byte_size
+            max: 10 parsecs
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Invalid 'max' for type 'byte_size'"));
+    }
+
+    #[test]
+    fn test_schema_numeric_bounds_fit_width() {
+        let schema_content = r#"
+            type: u16
+            max: 70000
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'max' (70000) does not fit type 'u16' (range 0 to 65535)"));
+
+        let schema_content = r#"
+            type: i32
+            exclusive_min: 0.5
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'exclusive_min' (0.5) must be an integer for type 'i32'"));
+
+        let schema_content = r#"
+            type: integer
+            multiple_of: 0
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'multiple_of' must be greater than 0"));
+
+        // Every bound is reported the same way, whether or not the other one is set
+        for (bounds, expected) in [
+            ("min: ten", "'min' must be a number"),
+            ("min: 1\nmax: ten", "'max' must be a number"),
+            ("exclusive_max: ten", "'exclusive_max' must be a number"),
+            (
+                "min: 10\nmax: 5",
+                "'min' (10) cannot be greater than 'max' (5)",
+            ),
+        ] {
+            let schema_file = create_temp_schema_file(&format!("type: integer\n{}\n", bounds));
+            let err = load_schema_file(schema_file.path()).unwrap_err();
+            assert!(err.to_string().contains(expected), "{}", err);
+        }
+
+        let schema_content = r#"
+            type: integer
+            max_decimal_places: 2
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let err = load_schema_file(schema_file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'max_decimal_places' is only valid for type 'float'"));
+    }
+
+    #[test]
+    fn test_schema_const_and_enum() {
+        let schema_file = create_temp_schema_file(
+            "type: object\nkeys:\n  kind:\n    type: string\n    enum: [Deployment, Service]\n    case_insensitive: true\n    default: service\n  replicas:\n    type: any\n    enum: [auto, 0, 1]\n",
+        );
+        assert!(load_schema_file(schema_file.path()).is_ok());
+
+        for (rule, expected) in [
+            (
+                "type: string\nenum: []",
+                "'enum' must list at least one value",
+            ),
+            (
+                "type: string\ncase_insensitive: true",
+                "'case_insensitive' requires 'enum' or 'const'",
+            ),
+            (
+                "type: integer\nconst: 1\ndefault: 2",
+                "'default' (2) does not match 'const' (1)",
+            ),
+        ] {
+            let schema_file = create_temp_schema_file(rule);
+            let err = load_schema_file(schema_file.path()).unwrap_err();
+            assert!(err.to_string().contains(expected), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_schema_assertions() {
+        let schema_file = create_temp_schema_file(
+            "type: object\nassert:\n  - min <= max\n  - { expr: \"all(items, it > 0)\", message: Items must be positive }\n",
+        );
+        let schema = load_schema_file(schema_file.path()).unwrap();
+        let assertions = schema.root.assertions.as_ref().unwrap();
+        assert_eq!(assertions[0].expr, "min <= max");
+        assert_eq!(
+            assertions[1].message.as_deref(),
+            Some("Items must be positive")
+        );
+
+        for (rule, expected) in [
+            (
+                "type: list\nassert: [\"len(self) > 0\"]",
+                "'assert' is only valid for type 'object', not 'list'",
+            ),
+            (
+                "type: object\nassert: [\"min <\"]",
+                "Invalid 'assert' expression 'min <' for field of type Object: expected a value at column 6",
+            ),
+            (
+                "type: object\nassert: [{ expr: a, mesage: typo }]",
+                "an expression or a map with 'expr' and 'message'",
+            ),
+        ] {
+            let schema_file = create_temp_schema_file(rule);
+            let err = load_schema_file(schema_file.path()).unwrap_err();
+            assert!(err.to_string().contains(expected), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_schema_ref_to() {
+        for (rule, expected) in [
+            (
+                "type: integer\nref_to: \"$.ids[*]\"",
+                "'ref_to' is only valid for type 'string', not 'integer'",
+            ),
+            (
+                "type: string\nref_to: backends[*].name",
+                "Invalid 'ref_to' path 'backends[*].name' for field of type String: the path must start with '$'",
+            ),
+        ] {
+            let schema_file = create_temp_schema_file(rule);
+            let err = load_schema_file(schema_file.path()).unwrap_err();
+            assert!(err.to_string().contains(expected), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_schema_project_constraints() {
+        let schema_file = create_temp_schema_file(
+            "type: object\nproject:\n  unique: [\"$.metadata.name\"]\n  exactly_one:\n    - { path: \"$.default\", equals: true }\n",
+        );
+        let schema = load_schema_file(schema_file.path()).unwrap();
+        assert_eq!(schema.project.unique, vec!["$.metadata.name"]);
+        assert_eq!(
+            schema.project.exactly_one[0].equals,
+            Some(Value::Bool(true))
+        );
+        assert!(schema.project.references.is_empty());
+        assert!(schema.root.project.is_none());
+
+        for (rule, expected) in [
+            (
+                "type: object\nkeys:\n  a:\n    type: object\n    project: { unique: [\"$.a\"] }",
+                "'project' is only allowed at the top level",
+            ),
+            (
+                "type: object\nproject: { unique: [metadata.name] }",
+                "Invalid 'project' path 'metadata.name'",
+            ),
+            (
+                "type: object\nproject: { uniq: [\"$.a\"] }",
+                "unknown field `uniq`",
+            ),
+        ] {
+            let schema_file = create_temp_schema_file(rule);
+            let err = load_schema_file(schema_file.path()).unwrap_err();
+            assert!(err.to_string().contains(expected), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_schema_default_validation() {
+        let valid = r#"
+        type: object
+        keys:
+          port:
+            type: u16
+            default: 8080
+          timeout:
+            type: duration
+            default: 30s
+          mode:
+            type: string
+            enum: [fast, safe]
+            default: safe
+        "#;
+        let schema_file = create_temp_schema_file(valid);
+        assert!(load_schema_file(schema_file.path()).is_ok());
+
+        for (rule, expected) in [
+            (
+                "type: integer\ndefault: \"80\"",
+                "'default' must be of type 'integer'",
+            ),
+            (
+                "type: u8\ndefault: 300",
+                "'default' (300) does not fit type 'u8'",
+            ),
+            (
+                "type: duration\ndefault: 30",
+                "'default' must be of type 'duration'",
+            ),
+            (
+                "type: duration\ndefault: 30x",
+                "'default' is not a valid duration",
+            ),
+            (
+                "type: string\nenum: [a, b]\ndefault: c",
+                "'default' (c) is not one of the allowed enum values",
+            ),
+            (
+                "type: string\nrequired: true\ndefault: a",
+                "'default' cannot be combined with 'required: true'",
+            ),
+        ] {
+            let schema_file = create_temp_schema_file(&format!(
+                "type: object\nkeys:\n  field:\n{}",
+                rule.lines()
+                    .map(|line| format!("    {}\n", line))
+                    .collect::<String>()
+            ));
+            let err = load_schema_file(schema_file.path()).unwrap_err();
+            assert!(err.to_string().contains(expected), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_schema_deprecation_validation() {
+        for (rule, expected) in [
+            (
+                "deprecated_until: 2030-01-01",
+                "'deprecated_until' requires 'deprecated' or 'renamed_to'",
+            ),
+            (
+                "renamed_to: server.port\n    deprecated_until: 2030-02-30",
+                "'deprecated_until' must be a YYYY-MM-DD date",
+            ),
+            (
+                "deprecated: use server.port\n    required: true",
+                "Deprecated or renamed keys cannot be 'required: true'",
+            ),
+        ] {
+            let schema_file = create_temp_schema_file(&format!(
+                "type: object\nkeys:\n  listen_port:\n    type: integer\n    {}\n",
+                rule
+            ));
+            let err = load_schema_file(schema_file.path()).unwrap_err();
+            assert!(err.to_string().contains(expected), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_schema_severity() {
+        let schema_file = create_temp_schema_file(
+            r#"
+        type: object
+        check_severity:
+          unknown_key: warning
+        keys:
+          name:
+            type: string
+            severity: info
+        "#,
+        );
+        let schema = load_schema_file(schema_file.path()).unwrap();
+        assert_eq!(schema.check_severity.unknown_key, Severity::Warning);
+        assert_eq!(schema.check_severity.deprecated, Severity::Warning);
+        assert_eq!(
+            schema.root.keys.as_ref().unwrap()["name"].severity,
+            Some(Severity::Info)
+        );
+
+        let nested = create_temp_schema_file(
+            "type: object\nkeys:\n  name:\n    type: object\n    check_severity: { unknown_key: info }\n",
+        );
+        let err = load_schema_file(nested.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'check_severity' is only allowed at the top level"));
+
+        let unknown = create_temp_schema_file("type: string\nseverity: fatal\n");
+        assert!(load_schema_file(unknown.path()).is_err());
+    }
+
+    #[test]
+    fn test_schema_custom_message_validation() {
+        for (rule, expected) in [
+            (
+                "type: string\nerror_message: \"bad {valeu}\"",
+                "Unknown placeholder '{valeu}' in 'error_message'",
+            ),
+            (
+                "type: string\nhint: \"at most {max_length} characters\"",
+                "'hint' uses '{max_length}' but the rule has no 'max_length'",
+            ),
+            ("type: string\ndocs_url: not a url", "Invalid 'docs_url'"),
+        ] {
+            let schema_file = create_temp_schema_file(rule);
+            let err = load_schema_file(schema_file.path()).unwrap_err();
+            assert!(err.to_string().contains(expected), "{}", err);
+        }
+
+        let schema_file = create_temp_schema_file(
+            "type: integer\nmin: 1\nerror_message: \"{path} must be at least {min}, not {value} {not a placeholder}\"\n",
+        );
+        assert!(load_schema_file(schema_file.path()).is_ok());
+    }
+
+    #[test]
+    fn test_schema_union_types() {
+        let schema_file = create_temp_schema_file(
+            "type: object\nkeys:\n  name:\n    type: [string, null]\n    max_length: 10\n  port:\n    type: [integer, string]\n    min: 1\n",
+        );
+        let schema = load_schema_file(schema_file.path()).unwrap();
+        let keys = schema.root.keys.as_ref().unwrap();
+        assert_eq!(
+            keys["name"].data_type,
+            SchemaType::Union(vec![SchemaType::String, SchemaType::Null])
+        );
+        assert!(keys["name"].is_nullable());
+        assert_eq!(keys["port"].data_type.to_string(), "integer or string");
+
+        let single = create_temp_schema_file("type: [string]\nnullable: true\n");
+        let schema = load_schema_file(single.path()).unwrap();
+        assert_eq!(schema.root.data_type, SchemaType::String);
+        assert!(schema.root.is_nullable());
+
+        for (rule, expected) in [
+            ("type: []", "'type' must list at least one type"),
+            (
+                "type: [string, string]",
+                "Type 'string' is listed more than once",
+            ),
+            (
+                "type: [string, null]\nmin: 1",
+                "'min' and 'max' are only valid for numeric types, not 'string'",
+            ),
+            (
+                "type: [boolean, list]\npattern: \"^a\"",
+                "'pattern' does not apply to any of the types 'boolean or list'",
+            ),
+            (
+                "type: [integer, string]\nmin_length: 2\nmin: 1s",
+                "'min' does not apply to any of the types 'integer or string'",
+            ),
+            (
+                "type: [u8, string]\nmax: 300",
+                "'max' (300) does not fit type 'u8'",
+            ),
+            (
+                "type: [integer, string]\ndefault: true",
+                "'default' must be of type 'integer or string'",
+            ),
+            (
+                "type: [u8, boolean]\ndefault: 300",
+                "'default' (300) does not fit type 'u8'",
+            ),
+            ("type: [string, strng]", "unknown variant `strng`"),
+        ] {
+            let schema_file = create_temp_schema_file(rule);
+            let err = load_schema_file(schema_file.path()).unwrap_err();
+            assert!(err.to_string().contains(expected), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_schema_union_member_constraints() {
+        // Each constraint applies to the members it suits, and the default may
+        // be of any member
+        for rule in [
+            "type: [string, integer]\nmin_length: 2\nmin: 0\n",
+            "type: [integer, string]\ndefault: auto\nmin: 0\n",
+            "type: [duration, integer]\nmin: 1s\nmultiple_of: 5\ndefault: 10\n",
+            "type: [object, list]\nkeys: { a: { type: string } }\nitems: { type: integer }\n",
+        ] {
+            let schema_file = create_temp_schema_file(rule);
+            let schema = load_schema_file(schema_file.path());
+            assert!(schema.is_ok(), "{}: {}", rule, schema.unwrap_err());
+        }
+    }
+
+    #[test]
+    fn test_schema_nested_unions() {
+        // Rules that apply whatever the type are checked with one member only,
+        // so deeply nested unions load quickly
+        let mut rule = "{ type: any }".to_string();
+        for _ in 0..20 {
+            rule = format!(
+                "{{ type: [integer, list], items: {{ type: any }}, all_of: [{}] }}",
+                rule
+            );
+        }
+        let nested = create_temp_schema_file(&rule);
+        assert!(load_schema_file(nested.path()).is_ok());
+    }
+
+    #[test]
+    fn test_schema_nested_definitions_rejected() {
+        let schema_content = r#"
+            type: object
+            keys:
+              nested:
+                type: object
+                definitions:
+                  port: { type: integer }
+        "#;
+        let schema_file = create_temp_schema_file(schema_content);
+        let result = load_schema_file(schema_file.path());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_schema_references_across_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("common")).unwrap();
+        fs::write(
+            dir.path().join("common/tls.yaml"),
+            r##"
+            definitions:
+              tls:
+                type: object
+                keys:
+                  cert_file: { $ref: "#/definitions/path" }
+                  limits: { $ref: "./limits.yaml#/definitions/limits" }
+              path:
+                type: string
+                min_length: 1
+            "##,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("common/limits.yaml"),
+            r##"
+            definitions:
+              limits:
+                type: object
+                keys:
+                  server: { $ref: "../schema.yaml#/definitions/server" }
+            "##,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("schema.yaml"),
+            r##"
+            type: object
+            definitions:
+              server:
+                type: object
+                keys:
+                  tls: { $ref: "./common/tls.yaml#/definitions/tls" }
+            keys:
+              server: { $ref: "#/definitions/server" }
+            "##,
+        )
+        .unwrap();
+
+        let schema = load_schema_file(dir.path().join("schema.yaml")).unwrap();
+        assert_eq!(schema.definitions.len(), 4);
+
+        let server = schema.resolve_reference("#/definitions/server").unwrap();
+        let tls_ref = server.keys.as_ref().unwrap()["tls"]
+            .reference
+            .as_ref()
+            .unwrap();
+        assert!(tls_ref.ends_with("tls.yaml#/definitions/tls"));
+
+        let tls = schema.resolve_reference(tls_ref).unwrap();
+        let limits_ref = tls.keys.as_ref().unwrap()["limits"]
+            .reference
+            .as_ref()
+            .unwrap();
+        let limits = schema.resolve_reference(limits_ref).unwrap();
+
+        // The import cycle back into the root file resolves to the local definition
+        assert_eq!(
+            limits.keys.as_ref().unwrap()["server"].reference.as_deref(),
+            Some("#/definitions/server")
+        );
+    }
+
+    #[test]
+    fn test_schema_reference_errors_name_the_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("shared.yaml"),
+            r##"
+            definitions:
+              port:
+                type: integer
+                pattern: "^[0-9]+$"
+            "##,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("schema.yaml"),
+            r##"
+            type: object
+            keys:
+              port: { $ref: "shared.yaml#/definitions/port" }
+            "##,
+        )
+        .unwrap();
+        let err = load_schema_file(dir.path().join("schema.yaml")).unwrap_err();
+        assert!(err.to_string().contains("shared.yaml#/definitions/port"));
+        assert!(err.to_string().contains("'pattern'"));
+
+        fs::write(
+            dir.path().join("schema.yaml"),
+            r##"
+            type: object
+            keys:
+              port: { $ref: "missing.yaml#/definitions/port" }
+            "##,
+        )
+        .unwrap();
+        let err = load_schema_file(dir.path().join("schema.yaml")).unwrap_err();
+        assert!(err.to_string().contains("missing.yaml"));
+        assert!(err.to_string().contains("schema.yaml"));
+    }
+
+    #[test]
+    fn test_schema_reference_cycle_across_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("other.yaml"),
+            r##"
+            definitions:
+              b: { $ref: "schema.yaml#/definitions/a" }
+            "##,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("schema.yaml"),
+            r##"
+            definitions:
+              a: { $ref: "other.yaml#/definitions/b" }
+            type: object
+            keys:
+              value: { $ref: "#/definitions/a" }
+            "##,
+        )
+        .unwrap();
+
+        let err = load_schema_file(dir.path().join("schema.yaml")).unwrap_err();
+        assert!(err.to_string().contains("Reference cycle"));
+    }
 }
@@ -0,0 +1,882 @@
+//! Source positions of the nodes of a configuration
+//!
+//! Configurations are loaded here: serde_yaml, serde_json and toml_edit give the
+//! value, and the span of every scalar, mapping and sequence is recorded
+//! alongside it. The spans are keyed by the same paths that validation errors use (`.a.b[0]`, with keys
+//! holding `.`, `[` or `]` written as `['a.b']`).
+
+use crate::config::ConfigFormat;
+use saphyr_parser::{Event, Marker, Parser, StrInput};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+/// Deepest nesting of collections scanned, the same limit as serde_yaml and serde_json
+const MAX_DEPTH: usize = 128;
+
+/// A position in a source file; lines and columns start at 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// The region of a source file holding a node
+///
+/// `end` is just past the last character of the node. For a key of a mapping
+/// the span starts at the key and ends with its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.start.line, self.start.column)
+    }
+}
+
+/// Spans of every node of a configuration, by path
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    spans: HashMap<String, Span>,
+}
+
+impl SourceMap {
+    /// Record the spans of a document
+    ///
+    /// Returns `None` for content that does not parse.
+    pub fn parse(content: &str, format: ConfigFormat) -> Option<Self> {
+        match format {
+            ConfigFormat::Yaml => yaml_spans(content),
+            ConfigFormat::Json => json_spans(content),
+            ConfigFormat::Toml => load_toml(content).ok().map(|(_, map)| map),
+        }
+    }
+
+    /// The span of the node at exactly this path
+    pub fn get(&self, path: &str) -> Option<Span> {
+        self.spans.get(path).copied()
+    }
+
+    /// The span of the node at this path, or of its closest enclosing node
+    ///
+    /// A required key that is missing is located at the object that lacks it.
+    pub fn locate(&self, path: &str) -> Option<Span> {
+        let mut path = path;
+        loop {
+            if let Some(span) = self.spans.get(path) {
+                return Some(*span);
+            }
+            if path.is_empty() {
+                return None;
+            }
+            path = parent_path(path);
+        }
+    }
+}
+
+/// Path of a key inside the object at `path`
+///
+/// Keys that would read as several steps (holding `.`, `[` or `]`) and empty
+/// keys are quoted in brackets, e.g. `.labels['app.kubernetes.io/name']`.
+pub(crate) fn key_path(path: &str, key: &str) -> String {
+    if !key.is_empty() && !key.contains(['.', '[', ']']) {
+        return format!("{}.{}", path, key);
+    }
+
+    let quote = if key.contains('\'') && !key.contains('"') {
+        '"'
+    } else {
+        '\''
+    };
+    let mut quoted = String::with_capacity(key.len() + 2);
+    quoted.push(quote);
+    for c in key.chars() {
+        if c == quote || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push(quote);
+    format!("{}[{}]", path, quoted)
+}
+
+/// The path of the node enclosing the one at `path`
+fn parent_path(path: &str) -> &str {
+    let mut last = 0;
+    let mut chars = path.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '.' => last = i,
+            '[' => {
+                last = i;
+                // A quoted key may hold dots, brackets and escaped quotes
+                if let Some(&(_, quote @ ('\'' | '"'))) = chars.peek() {
+                    chars.next();
+                    while let Some((_, c)) = chars.next() {
+                        if c == '\\' {
+                            chars.next();
+                        } else if c == quote {
+                            break;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    &path[..last]
+}
+
+/// Load the YAML document in `content` along with the span of each of its nodes
+///
+/// The value is the one serde_yaml gives, after skipping a byte order mark.
+/// The spans come from a second pass with saphyr, and are `None` for the few
+/// documents it does not read.
+pub(crate) fn load_yaml(content: &str) -> Result<(Value, Option<SourceMap>), String> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let value = serde_yaml::from_str(content).map_err(|e| e.to_string())?;
+    Ok((value, yaml_spans(content)))
+}
+
+fn yaml_spans(content: &str) -> Option<SourceMap> {
+    // Both serde_yaml and saphyr would read a byte order mark as part of the document
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut scanner = YamlSpans {
+        parser: Parser::new_from_str(content),
+        map: SourceMap::default(),
+    };
+
+    loop {
+        let (event, span) = scanner.next()?;
+        match event {
+            Event::StreamEnd => return Some(scanner.map),
+            Event::StreamStart | Event::DocumentStart(_) | Event::DocumentEnd | Event::Nothing => {}
+            event => {
+                scanner.node(event, span, Some(String::new()), 0)?;
+            }
+        }
+    }
+}
+
+/// Records the spans of the nodes of a YAML document from its parser events
+struct YamlSpans<'a> {
+    parser: Parser<'a, StrInput<'a>>,
+    map: SourceMap,
+}
+
+impl<'a> YamlSpans<'a> {
+    fn next(&mut self) -> Option<(Event<'a>, saphyr_parser::Span)> {
+        self.parser.next_event()?.ok()
+    }
+
+    /// Record the node starting with `event`, returning its end
+    ///
+    /// Nothing is recorded for a node without a path (a mapping key, or
+    /// something under one). Nodes reached through an alias are located at it.
+    fn node(
+        &mut self,
+        event: Event<'a>,
+        span: saphyr_parser::Span,
+        path: Option<String>,
+        depth: usize,
+    ) -> Option<Position> {
+        let start = position(span.start);
+        let end = match event {
+            Event::Alias(_) | Event::Scalar(..) => position(span.end),
+            Event::SequenceStart(..) if depth < MAX_DEPTH => {
+                let mut index = 0;
+                let mut last = start;
+                loop {
+                    let (item, item_span) = self.next()?;
+                    if let Event::SequenceEnd = item {
+                        break closing(item_span, last);
+                    }
+                    let item_path = path.as_ref().map(|p| format!("{}[{}]", p, index));
+                    last = self.node(item, item_span, item_path, depth + 1)?;
+                    index += 1;
+                }
+            }
+            Event::MappingStart(..) if depth < MAX_DEPTH => {
+                let mut last = start;
+                loop {
+                    let (key, key_span) = self.next()?;
+                    if let Event::MappingEnd = key {
+                        break closing(key_span, last);
+                    }
+                    // Only scalar keys have a path
+                    let entry_path = match (&path, &key) {
+                        (Some(path), Event::Scalar(name, ..)) => Some(key_path(path, name)),
+                        _ => None,
+                    };
+                    let key_end = self.node(key, key_span, None, depth + 1)?;
+                    let (value, value_span) = self.next()?;
+                    let value_end = self.node(value, value_span, entry_path.clone(), depth + 1)?;
+                    last = value_end.max(key_end);
+                    if let Some(entry_path) = entry_path {
+                        self.map.spans.insert(
+                            entry_path,
+                            Span {
+                                start: position(key_span.start),
+                                end: last,
+                            },
+                        );
+                    }
+                }
+            }
+            _ => return None,
+        };
+
+        if let Some(path) = path {
+            self.map.spans.insert(path, Span { start, end });
+        }
+        Some(end)
+    }
+}
+
+/// The end of a mapping or sequence from the event closing it
+///
+/// A flow collection ends with its closing bracket. A block collection has no
+/// closing token, so it ends with its last entry.
+fn closing(span: saphyr_parser::Span, last: Position) -> Position {
+    if span.start == span.end {
+        last
+    } else {
+        position(span.end)
+    }
+}
+
+fn position(mark: Marker) -> Position {
+    Position {
+        line: mark.line(),
+        column: mark.col() + 1,
+    }
+}
+
+/// Load the JSON document in `content` along with the span of each of its nodes
+///
+/// The value is the one serde_json gives; the spans come from a second pass.
+pub(crate) fn load_json(content: &str) -> Result<(Value, Option<SourceMap>), String> {
+    let value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    Ok((value, json_spans(content)))
+}
+
+fn json_spans(content: &str) -> Option<SourceMap> {
+    let mut scanner = JsonSpans {
+        input: content,
+        offset: 0,
+        line: 1,
+        column: 1,
+        map: SourceMap::default(),
+    };
+
+    scanner.whitespace();
+    scanner.value(String::new(), 0)?;
+    scanner.whitespace();
+    (scanner.offset == content.len()).then_some(scanner.map)
+}
+
+/// Scans a JSON document, recording where each value is
+struct JsonSpans<'a> {
+    input: &'a str,
+    offset: usize,
+    line: usize,
+    column: usize,
+    map: SourceMap,
+}
+
+impl JsonSpans<'_> {
+    fn position(&self) -> Position {
+        Position {
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.offset..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.bump();
+        }
+    }
+
+    /// Scan the value at the current position and record its span at `path`
+    fn value(&mut self, path: String, depth: usize) -> Option<Position> {
+        let start = self.position();
+        match self.peek()? {
+            '{' if depth < MAX_DEPTH => self.object(&path, depth)?,
+            '[' if depth < MAX_DEPTH => self.array(&path, depth)?,
+            '"' => {
+                self.string()?;
+            }
+            // Numbers and literals, whose text serde_json has already checked
+            '-' | '+' | '.' | '0'..='9' | 'a'..='z' | 'A'..='Z' => {
+                while matches!(
+                    self.peek(),
+                    Some('-' | '+' | '.' | '0'..='9' | 'a'..='z' | 'A'..='Z')
+                ) {
+                    self.bump();
+                }
+            }
+            _ => return None,
+        }
+
+        let end = self.position();
+        self.map.spans.insert(path, Span { start, end });
+        Some(end)
+    }
+
+    fn object(&mut self, path: &str, depth: usize) -> Option<()> {
+        self.bump();
+        self.whitespace();
+        if self.peek() == Some('}') {
+            self.bump();
+            return Some(());
+        }
+
+        loop {
+            self.whitespace();
+            let key_start = self.position();
+            if self.peek() != Some('"') {
+                return None;
+            }
+            let key = self.string()?;
+            self.whitespace();
+            if self.bump() != Some(':') {
+                return None;
+            }
+            self.whitespace();
+
+            let entry_path = key_path(path, &key);
+            let end = self.value(entry_path.clone(), depth + 1)?;
+            self.map.spans.insert(
+                entry_path,
+                Span {
+                    start: key_start,
+                    end,
+                },
+            );
+
+            self.whitespace();
+            match self.bump()? {
+                ',' => {}
+                '}' => return Some(()),
+                _ => return None,
+            }
+        }
+    }
+
+    fn array(&mut self, path: &str, depth: usize) -> Option<()> {
+        self.bump();
+        self.whitespace();
+        if self.peek() == Some(']') {
+            self.bump();
+            return Some(());
+        }
+
+        let mut index = 0;
+        loop {
+            self.whitespace();
+            self.value(format!("{}[{}]", path, index), depth + 1)?;
+            index += 1;
+            self.whitespace();
+            match self.bump()? {
+                ',' => {}
+                ']' => return Some(()),
+                _ => return None,
+            }
+        }
+    }
+
+    /// Scan a string, returning its text with escapes decoded
+    fn string(&mut self) -> Option<String> {
+        self.bump();
+        let mut string = String::new();
+        loop {
+            match self.bump()? {
+                '"' => return Some(string),
+                '\\' => {
+                    let c = match self.bump()? {
+                        c @ ('"' | '\\' | '/') => c,
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => self.unicode_escape()?,
+                        _ => return None,
+                    };
+                    string.push(c);
+                }
+                c => string.push(c),
+            }
+        }
+    }
+
+    /// The character of a `\uXXXX` escape, or of a pair of them for a surrogate pair
+    fn unicode_escape(&mut self) -> Option<char> {
+        let first = self.hex_digits()?;
+        let code = match first {
+            0xD800..=0xDBFF => {
+                if !self.input[self.offset..].starts_with("\\u") {
+                    return None;
+                }
+                self.bump();
+                self.bump();
+                let second = self.hex_digits()?;
+                if !(0xDC00..=0xDFFF).contains(&second) {
+                    return None;
+                }
+                0x10000 + ((first - 0xD800) << 10) + (second - 0xDC00)
+            }
+            code => code,
+        };
+        char::from_u32(code)
+    }
+
+    fn hex_digits(&mut self) -> Option<u32> {
+        let code = self
+            .input
+            .get(self.offset..self.offset + 4)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())?;
+        for _ in 0..4 {
+            self.bump();
+        }
+        Some(code)
+    }
+}
+
+/// Load the TOML document in `content` along with the span of each of its nodes
+///
+/// Integers and floats keep their TOML types, and datetimes become strings in
+/// their RFC 3339 form so they can be checked with `type: string`.
+pub(crate) fn load_toml(content: &str) -> Result<(Value, SourceMap), String> {
+    let document = toml_edit::ImDocument::parse(content).map_err(|e| e.to_string())?;
+    let mut loader = TomlLoader {
+        input: content,
+        line_starts: std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect(),
+        map: SourceMap::default(),
+    };
+
+    let (value, _) = loader.table(document.as_table(), String::new());
+    Ok((value, loader.map))
+}
+
+/// Converts a parsed TOML document, recording the byte ranges toml_edit keeps
+struct TomlLoader<'a> {
+    input: &'a str,
+    /// Byte offset of the start of each line
+    line_starts: Vec<usize>,
+    map: SourceMap,
+}
+
+impl TomlLoader<'_> {
+    fn position(&self, offset: usize) -> Position {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self.line_starts[line - 1];
+        Position {
+            line,
+            column: self.input[line_start..offset].chars().count() + 1,
+        }
+    }
+
+    fn record(&mut self, path: String, range: Option<Range<usize>>) {
+        if let Some(range) = range {
+            let span = Span {
+                start: self.position(range.start),
+                end: self.position(range.end),
+            };
+            self.map.spans.insert(path, span);
+        }
+    }
+
+    fn item(&mut self, item: &toml_edit::Item, path: String) -> (Value, Option<Range<usize>>) {
+        match item {
+            toml_edit::Item::None => (Value::Null, None),
+            toml_edit::Item::Value(value) => self.value(value, path),
+            toml_edit::Item::Table(table) => self.table(table, path),
+            toml_edit::Item::ArrayOfTables(tables) => {
+                let mut range = tables.span();
+                let mut items = Vec::new();
+                for table in tables.iter() {
+                    let item_path = format!("{}[{}]", path, items.len());
+                    let (value, table_range) = self.table(table, item_path);
+                    range = join(range, table_range);
+                    items.push(value);
+                }
+                self.record(path, range.clone());
+                (Value::Sequence(items), range)
+            }
+        }
+    }
+
+    /// Convert a table, whose span runs from its header to its last entry
+    ///
+    /// Tables only implied by a dotted key or a nested header have no range of
+    /// their own and cover their entries instead.
+    fn table(&mut self, table: &toml_edit::Table, path: String) -> (Value, Option<Range<usize>>) {
+        let mut mapping = Mapping::new();
+        let mut range = table.span();
+        for (key, item) in table.iter() {
+            let entry_path = key_path(&path, key);
+            let (value, value_range) = self.item(item, entry_path.clone());
+            let entry_range = join(table.key(key).and_then(|key| key.span()), value_range);
+            self.record(entry_path, entry_range.clone());
+            range = join(range, entry_range);
+            mapping.insert(Value::String(key.to_string()), value);
+        }
+        self.record(path, range.clone());
+        (Value::Mapping(mapping), range)
+    }
+
+    fn value(&mut self, value: &toml_edit::Value, path: String) -> (Value, Option<Range<usize>>) {
+        let converted = match value {
+            toml_edit::Value::String(s) => Value::String(s.value().clone()),
+            toml_edit::Value::Integer(i) => Value::from(*i.value()),
+            toml_edit::Value::Float(f) => Value::from(*f.value()),
+            toml_edit::Value::Boolean(b) => Value::Bool(*b.value()),
+            toml_edit::Value::Datetime(dt) => Value::String(dt.value().to_string()),
+            toml_edit::Value::Array(array) => {
+                let mut items = Vec::new();
+                for item in array.iter() {
+                    let item_path = format!("{}[{}]", path, items.len());
+                    items.push(self.value(item, item_path).0);
+                }
+                Value::Sequence(items)
+            }
+            toml_edit::Value::InlineTable(table) => {
+                let mut mapping = Mapping::new();
+                for (key, item) in table.iter() {
+                    let entry_path = key_path(&path, key);
+                    let (value, value_range) = self.value(item, entry_path.clone());
+                    let key_range = table.key(key).and_then(|key| key.span());
+                    self.record(entry_path, join(key_range, value_range));
+                    mapping.insert(Value::String(key.to_string()), value);
+                }
+                Value::Mapping(mapping)
+            }
+        };
+        let range = value.span();
+        self.record(path, range.clone());
+        (converted, range)
+    }
+}
+
+/// The smallest range covering both, when either is known
+fn join(a: Option<Range<usize>>, b: Option<Range<usize>>) -> Option<Range<usize>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.start.min(b.start)..a.end.max(b.end)),
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(start: (usize, usize), end: (usize, usize)) -> Span {
+        Span {
+            start: Position {
+                line: start.0,
+                column: start.1,
+            },
+            end: Position {
+                line: end.0,
+                column: end.1,
+            },
+        }
+    }
+
+    #[test]
+    fn test_yaml_spans() {
+        let yaml = "\
+name: web
+spec:
+  containers:
+    - name: app
+      ports: [80, 443]
+    - name: sidecar
+  labels: { tier: front }
+";
+        let map = SourceMap::parse(yaml, ConfigFormat::Yaml).unwrap();
+
+        assert_eq!(map.get(".name"), Some(span((1, 1), (1, 10))));
+        assert_eq!(
+            map.get(".spec.containers[0].name"),
+            Some(span((4, 7), (4, 16)))
+        );
+        assert_eq!(
+            map.get(".spec.containers[1].name"),
+            Some(span((6, 7), (6, 20)))
+        );
+        assert_eq!(
+            map.get(".spec.containers[0].ports[1]"),
+            Some(span((5, 19), (5, 22)))
+        );
+        assert_eq!(map.get(".spec.containers[0]"), Some(span((4, 7), (5, 23))));
+        assert_eq!(map.get(".spec.labels"), Some(span((7, 3), (7, 26))));
+        assert_eq!(map.get(".spec.labels.tier"), Some(span((7, 13), (7, 24))));
+
+        // Missing keys and nodes under them fall back to the enclosing node
+        assert_eq!(
+            map.locate(".spec.containers[1].image"),
+            map.get(".spec.containers[1]")
+        );
+        assert_eq!(map.locate(".missing.deeper"), map.get(""));
+        assert_eq!(
+            map.get("").map(|s| s.start),
+            Some(span((1, 1), (1, 1)).start)
+        );
+    }
+
+    #[test]
+    fn test_json_spans() {
+        let json =
+            "{\n  \"server\": {\n    \"port\": 8080,\n    \"hosts\": [\"a\", \"b\"]\n  }\n}\n";
+        let map = SourceMap::parse(json, ConfigFormat::Json).unwrap();
+
+        assert_eq!(map.get(".server.port"), Some(span((3, 5), (3, 17))));
+        assert_eq!(map.get(".server.hosts[1]"), Some(span((4, 20), (4, 23))));
+        assert_eq!(map.get(".server"), Some(span((2, 3), (5, 4))));
+        assert_eq!(map.get(""), Some(span((1, 1), (6, 2))));
+    }
+
+    #[test]
+    fn test_toml_spans() {
+        let toml = "\
+title = \"app\"
+owner.name = \"ann\"
+
+[server]
+port = 8080
+hosts = [\"a\", \"b\"]
+limits = { cpu = 2 }
+
+[[backends]]
+name = \"x\"
+
+[[backends]]
+name = \"y\"
+
+[db.primary]
+port = 5432
+";
+        let map = SourceMap::parse(toml, ConfigFormat::Toml).unwrap();
+
+        assert_eq!(map.get(".title"), Some(span((1, 1), (1, 14))));
+        assert_eq!(map.get(".owner.name"), Some(span((2, 7), (2, 19))));
+        // A table runs from its header to its last entry
+        assert_eq!(map.get(".server"), Some(span((4, 1), (7, 21))));
+        assert_eq!(map.get(".server.hosts[1]"), Some(span((6, 15), (6, 18))));
+        assert_eq!(map.get(".server.limits.cpu"), Some(span((7, 12), (7, 19))));
+        assert_eq!(map.get(".backends[1]"), Some(span((12, 1), (13, 11))));
+        assert_eq!(map.get(".backends[1].name"), Some(span((13, 1), (13, 11))));
+        // Keys with the same name in different tables are told apart
+        assert_eq!(map.get(".server.port"), Some(span((5, 1), (5, 12))));
+        assert_eq!(map.get(".db.primary.port"), Some(span((16, 1), (16, 12))));
+        // A table only implied by a header covers the tables under it
+        assert_eq!(map.get(".db"), Some(span((15, 1), (16, 12))));
+
+        let map = SourceMap::parse("\"a.b\" = 1\n[a]\nb = 2\n", ConfigFormat::Toml).unwrap();
+        assert_eq!(map.get("['a.b']"), Some(span((1, 1), (1, 10))));
+        assert_eq!(map.get(".a.b"), Some(span((3, 1), (3, 6))));
+    }
+
+    #[test]
+    fn test_unsupported_content() {
+        assert_eq!(SourceMap::parse("a = ", ConfigFormat::Toml), None);
+        assert_eq!(SourceMap::parse("a = 1\na = 2", ConfigFormat::Toml), None);
+        assert_eq!(SourceMap::parse("a: [1, 2", ConfigFormat::Yaml), None);
+
+        // An empty document has no nodes
+        let map = SourceMap::parse("", ConfigFormat::Yaml).unwrap();
+        assert_eq!(map.locate(".a"), None);
+    }
+
+    #[test]
+    fn test_yaml_values() {
+        // The loaded value is the one serde_yaml gives for the same document
+        let documents = [
+            r#"
+plain: [~, null, "", true, False, 42, +7, -3, 0x1F, 0o17, 0b101, -0x10, 0755, 1.5, -2e3, .inf, -.INF]
+quoted: ["42", 'true', "null"]
+tagged: [!!str 42, !!int "7", !!float "1", !custom value, !!bool "true"]
+text: |
+  two
+  lines
+base: &base { host: localhost, ports: [80, 443] }
+copy: *base
+"quoted key": 1
+? [complex, key]
+: value
+"#,
+            "a:\tb\n",
+            "verbatim: !<tag:yaml.org,2002:str> 1\n",
+            "%TAG !e! tag:yaml.org,2002:\n---\ndeclared: !e!str 1\n",
+        ];
+        for yaml in documents {
+            let (value, _) = load_yaml(yaml).unwrap();
+            let expected: Value = serde_yaml::from_str(yaml).unwrap();
+            assert_eq!(value, expected, "{:?}", yaml);
+        }
+
+        let (value, _) = load_yaml("nan: .nan").unwrap();
+        assert!(value["nan"].as_f64().unwrap().is_nan());
+        assert!(load_yaml("big: 18446744073709551616").is_err());
+
+        // A byte order mark is skipped, and does not shift the spans of the first line
+        let (value, map) = load_yaml("\u{feff}name: web\n").unwrap();
+        assert_eq!(value, load_yaml("name: web\n").unwrap().0);
+        assert_eq!(map.unwrap().get(".name"), Some(span((1, 1), (1, 10))));
+
+        // Nodes reached through an alias are located at the alias
+        let (_, map) = load_yaml("base: &b { port: 1 }\ncopy: *b\n").unwrap();
+        assert_eq!(
+            map.unwrap().locate(".copy.port"),
+            Some(span((2, 1), (2, 9)))
+        );
+    }
+
+    #[test]
+    fn test_json_values() {
+        // The loaded value is the one serde_json gives for the same document
+        let json = r#"{
+            "strings": ["plain", "esc\"aped\\\/", "\b\f\n\r\t", "\u00e9\ud83d\ude00", "日本"],
+            "numbers": [0, -0, 42, -17, 1.5, -2.5e-3, 1E3, 18446744073709551615, -9223372036854775808, 99999999999999999999, -123456789012345678901],
+            "literals": [true, false, null],
+            "nested": { "empty": {}, "list": [[], [{}]] }
+        }"#;
+        let (value, map) = load_json(json).unwrap();
+        let expected: Value = serde_json::from_str(json).unwrap();
+        assert_eq!(value, expected);
+        assert_eq!(
+            map.unwrap().get(".numbers[10]"),
+            Some(span((3, 126), (3, 148)))
+        );
+    }
+
+    #[test]
+    fn test_escaped_keys() {
+        assert_eq!(key_path(".a", "b"), ".a.b");
+        assert_eq!(key_path(".a", "b.c"), ".a['b.c']");
+        assert_eq!(key_path("", "x[0]"), "['x[0]']");
+        assert_eq!(key_path("", ""), "['']");
+        assert_eq!(key_path("", "it's.here"), "[\"it's.here\"]");
+        assert_eq!(key_path("", "both'\".x"), "['both\\'\".x']");
+
+        assert_eq!(parent_path(".a['b.c']"), ".a");
+        assert_eq!(parent_path(".a['b.c'].d"), ".a['b.c']");
+        assert_eq!(parent_path(".a['x]['].d[3]"), ".a['x]['].d");
+        assert_eq!(parent_path(".a['x]['].d"), ".a['x][']");
+        assert_eq!(parent_path("['both\\'\".x']"), "");
+        assert_eq!(parent_path(".a"), "");
+
+        // A key with dots gets its own path, apart from the nested keys it reads like
+        let yaml = "a.b: 1\na:\n  b: 2\n\"c[0]\": 3\n";
+        let map = load_yaml(yaml).unwrap().1.unwrap();
+        assert_eq!(map.get("['a.b']"), Some(span((1, 1), (1, 7))));
+        assert_eq!(map.get(".a.b"), Some(span((3, 3), (3, 7))));
+        assert_eq!(map.get("['c[0]']"), Some(span((4, 1), (4, 10))));
+        assert_eq!(map.locate("['a.b'].missing"), map.get("['a.b']"));
+
+        let map = load_json(r#"{"a.b": {"c": 1}}"#).unwrap().1.unwrap();
+        assert_eq!(map.get("['a.b'].c"), Some(span((1, 10), (1, 16))));
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        let yaml = [
+            "a: [1, 2",
+            "a: {b: 1",
+            "a: 'open",
+            "a: 1\n b: 2\n- c",
+            "a: *missing",
+            "a: 1\na: 2",
+            "{a: 1, a: 2}",
+            "a: 1\n---\nb: 2",
+            "a: !!int x",
+            "a: !!bool yes",
+            "key: @reserved",
+        ];
+        for content in yaml {
+            assert!(load_yaml(content).is_err(), "{:?} should not load", content);
+        }
+
+        let json = [
+            "",
+            "{",
+            "{\"a\": 1,}",
+            "[1, 2,]",
+            "{a: 1}",
+            "{\"a\" 1}",
+            "\"open",
+            "\"bad \\x escape\"",
+            "\"\\u12\"",
+            "\"\\ud800\"",
+            "\"\\udc00\"",
+            "\"tab\there\"",
+            "01",
+            "1.",
+            "-",
+            "1e",
+            "1e400",
+            "tru",
+            "nul",
+            "{\"a\": 1, \"a\": 2}",
+            "[1] [2]",
+            "# comment",
+        ];
+        for content in json {
+            assert!(load_json(content).is_err(), "{:?} should not load", content);
+        }
+
+        // Deep nesting and exponential aliases are refused rather than exhausting
+        // the stack or memory
+        assert!(load_yaml(&"[".repeat(10_000)).is_err());
+        assert!(load_yaml(&format!("{}1{}", "[".repeat(200), "]".repeat(200))).is_err());
+        assert!(load_json(&"[".repeat(10_000)).is_err());
+        assert!(load_json(&format!("{}1{}", "[".repeat(200), "]".repeat(200))).is_err());
+        let mut laughs = String::from("a0: &a0 [lol, lol, lol, lol, lol, lol, lol, lol, lol]\n");
+        for i in 1..10 {
+            let items = vec![format!("*a{}", i - 1); 9].join(", ");
+            laughs.push_str(&format!("a{}: &a{} [{}]\n", i, i, items));
+        }
+        let error = load_yaml(&laughs).unwrap_err();
+        assert!(error.starts_with("repetition limit exceeded"), "{}", error);
+    }
+
+    #[test]
+    fn test_truncated_input() {
+        // Every prefix of a document either loads or fails, never panics
+        let yaml = "a: &x { b: [1, 'two', \"th\\u00e9\"] }\nc: *x\nd: |\n  é\n? [k]\n: !t v\n";
+        let json = "{\"a\": [1, -2.5e3, \"\\ud83d\\ude00é\", {\"b\": null}], \"c\": true}";
+        for content in [yaml, json] {
+            for (end, _) in content.char_indices() {
+                let _ = load_yaml(&content[..end]);
+                let _ = load_json(&content[..end]);
+            }
+        }
+    }
+}
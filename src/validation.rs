@@ -1,8 +1,19 @@
+use crate::compiled::{CompiledRule, CompiledSchema, DocumentPath, Numeric};
 use crate::config::{Config, ConfigFormat};
 use crate::error::{ConfigGuardError, ConfigGuardResult};
-use crate::schema::{Schema, SchemaRule, SchemaType};
-use regex::Regex;
+use crate::formats::{today, uri_scheme, StringFormat};
+use crate::quantity::Quantity;
+use crate::schema::{AdditionalItems, Discriminator, Schema, SchemaRule, SchemaType};
+use crate::source::{key_path, SourceMap, Span};
+use serde::{Deserialize, Serialize};
 use serde_yaml::Value;
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
 
 /// Represents a validation error
 #[derive(Debug, Clone)]
@@ -24,6 +35,102 @@ pub struct ValidationError {
 
     /// Line number in the source file (if available)
     pub line: Option<usize>,
+
+    /// Start and end of the offending node in the source file (if available)
+    ///
+    /// Errors about a missing key point at the object that lacks it.
+    pub span: Option<Span>,
+
+    /// How serious the diagnostic is; only errors make a configuration invalid
+    pub severity: Severity,
+
+    /// Remediation hint from the schema (if available)
+    pub hint: Option<String>,
+
+    /// Link to documentation from the schema (if available)
+    pub docs_url: Option<String>,
+
+    /// File the diagnostic refers to, for checks across the files of a project
+    pub file: Option<String>,
+}
+
+/// How serious a validation diagnostic is, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Informational only
+    Info,
+
+    /// The configuration is accepted, but should be changed (e.g. a deprecated key)
+    Warning,
+
+    /// The configuration is invalid
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl ValidationError {
+    /// Create a validation error for the given path
+    pub fn new(
+        path: impl Into<String>,
+        message: impl Into<String>,
+        expected: impl Into<String>,
+        actual: impl Into<String>,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+            expected: expected.into(),
+            actual: actual.into(),
+            description: None,
+            line: None,
+            span: None,
+            severity: Severity::Error,
+            hint: None,
+            docs_url: None,
+            file: None,
+        }
+    }
+
+    /// Attach the schema description of the offending field
+    pub fn with_description(mut self, description: Option<String>) -> Self {
+        self.description = description;
+        self
+    }
+
+    /// Set the severity of the diagnostic
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Where the error is in the source file, e.g. `line 3, column 5`
+    pub fn location(&self) -> Option<String> {
+        match (&self.span, self.line) {
+            (Some(span), _) => Some(span.to_string()),
+            (None, Some(line)) => Some(format!("line {}", line)),
+            (None, None) => None,
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} (expected: {}, found: {})",
+            self.path, self.message, self.expected, self.actual
+        )
+    }
 }
 
 /// Result of a validation operation
@@ -33,24 +140,291 @@ pub enum ValidationResult {
     Valid,
 
     /// Configuration has validation errors
-    #[allow(dead_code)]
     Invalid(Vec<ValidationError>),
 }
 
+/// Outcome of validating a single configuration
+///
+/// Unlike [`validate`], an invalid configuration is not an `Err`: the report
+/// carries every validation error so callers can decide how to react.
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    /// Path of the validated file (if it was loaded from disk)
+    pub path: Option<PathBuf>,
+
+    /// All validation errors, in the order they were found
+    pub errors: Vec<ValidationError>,
+
+    /// Warnings that do not make the configuration invalid (e.g. deprecated keys)
+    pub warnings: Vec<ValidationError>,
+
+    /// Informational diagnostics
+    pub info: Vec<ValidationError>,
+}
+
+impl ValidationReport {
+    /// Whether the configuration passed validation
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Whether the report has a diagnostic of at least the given severity
+    ///
+    /// `fails_on(Severity::Warning)` treats warnings as failures, e.g. to
+    /// enforce a rule that was rolled out as a warning first.
+    pub fn fails_on(&self, severity: Severity) -> bool {
+        self.diagnostics().any(|e| e.severity >= severity)
+    }
+
+    /// All diagnostics, most severe first
+    pub fn diagnostics(&self) -> impl Iterator<Item = &ValidationError> {
+        self.errors.iter().chain(&self.warnings).chain(&self.info)
+    }
+
+    /// Convert the report into the `ValidationResult` used for reporting
+    pub fn into_result(self) -> ValidationResult {
+        self.into()
+    }
+}
+
+impl From<ValidationReport> for ValidationResult {
+    fn from(report: ValidationReport) -> Self {
+        if report.errors.is_empty() {
+            ValidationResult::Valid
+        } else {
+            ValidationResult::Invalid(report.errors)
+        }
+    }
+}
+
+/// Validates configurations against a schema
+///
+/// A `Validator` is cheap to clone and share between threads: build it once
+/// at startup and call [`Validator::validate`] for every configuration.
+#[derive(Debug, Clone)]
+pub struct Validator {
+    schema: Arc<CompiledSchema>,
+    strict: bool,
+}
+
+impl Validator {
+    /// Create a validator for the given compiled schema
+    pub fn new(schema: CompiledSchema) -> Self {
+        Self {
+            schema: Arc::new(schema),
+            strict: false,
+        }
+    }
+
+    /// Load and compile a schema from a file and create a validator for it
+    pub fn from_schema_file<P: AsRef<Path>>(path: P) -> ConfigGuardResult<Self> {
+        Ok(Self::new(Schema::from_file(path)?.compile()?))
+    }
+
+    /// Enable or disable strict mode (reject keys not defined in the schema)
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Whether strict mode is enabled
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// The schema this validator checks against
+    pub fn schema(&self) -> &Schema {
+        self.schema.schema()
+    }
+
+    /// Validate a parsed configuration
+    pub fn validate(&self, config: &Config) -> ConfigGuardResult<ValidationReport> {
+        let mut report = ValidationReport {
+            path: config.path.clone(),
+            ..Default::default()
+        };
+        for diagnostic in collect_errors(config, &self.schema, self.strict)? {
+            match diagnostic.severity {
+                Severity::Error => report.errors.push(diagnostic),
+                Severity::Warning => report.warnings.push(diagnostic),
+                Severity::Info => report.info.push(diagnostic),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Parse and validate configuration content
+    pub fn validate_str(
+        &self,
+        content: &str,
+        format: ConfigFormat,
+    ) -> ConfigGuardResult<ValidationReport> {
+        self.validate(&Config::from_str(content, format)?)
+    }
+
+    /// Load and validate a configuration file
+    pub fn validate_file<P: AsRef<Path>>(&self, path: P) -> ConfigGuardResult<ValidationReport> {
+        self.validate(&Config::from_file(path)?)
+    }
+
+    /// Check the schema's `project` constraints across the files of a project
+    ///
+    /// Each configuration is expected to be validated on its own as well; this
+    /// only checks what spans files, such as names that must be unique across
+    /// all of them. Every diagnostic names the file it refers to (the
+    /// configuration's path), and mentions the other file involved, if any.
+    pub fn validate_project(&self, configs: &[Config]) -> ConfigGuardResult<ValidationReport> {
+        let project = &self.schema().project;
+        let parse = |path: &str| {
+            DocumentPath::parse(path).map_err(|e| {
+                ConfigGuardError::Schema(format!("Invalid 'project' path '{}': {}", path, e))
+            })
+        };
+        let file_name = |i: usize| {
+            configs[i].path.as_ref().map_or_else(
+                || format!("<configuration {}>", i + 1),
+                |path| path.display().to_string(),
+            )
+        };
+        // Each problem with the index of the configuration it was found in
+        let mut problems: Vec<(usize, ValidationError)> = Vec::new();
+
+        for unique in &project.unique {
+            let target = parse(unique)?;
+            let mut seen: Vec<(&Value, usize, String)> = Vec::new();
+            for (i, config) in configs.iter().enumerate() {
+                for (path, value) in target.select_with_paths(&config.data) {
+                    let first = seen
+                        .iter()
+                        .find(|(other, _, _)| values_equal(value, other, false));
+                    match first {
+                        Some((_, first_file, first_path)) => problems.push((
+                            i,
+                            ValidationError::new(
+                                &path,
+                                format!(
+                                    "Duplicate value '{}' for {}",
+                                    display_value(value),
+                                    unique
+                                ),
+                                "Unique values across files",
+                                format!("Also in '{}' at {}", file_name(*first_file), first_path),
+                            ),
+                        )),
+                        None => seen.push((value, i, path)),
+                    }
+                }
+            }
+        }
+
+        for reference in &project.references {
+            let (source, target) = (parse(&reference.path)?, parse(&reference.ref_to)?);
+            let names = reference_names(configs.iter().flat_map(|c| target.select(&c.data)));
+            for (i, config) in configs.iter().enumerate() {
+                for (path, value) in source.select_with_paths(&config.data) {
+                    let name = reference_names([value]);
+                    if let [name] = name.as_slice() {
+                        if !names.contains(name) {
+                            problems.push((
+                                i,
+                                unknown_reference(&path, name, &names, &reference.ref_to),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        for count in &project.exactly_one {
+            let target = parse(&count.path)?;
+            let wanted = match &count.equals {
+                Some(value) => format!("{} = {}", count.path, display_value(value)),
+                None => format!("{} set", count.path),
+            };
+            let mut matches: Vec<(usize, String)> = Vec::new();
+            for (i, config) in configs.iter().enumerate() {
+                let matched =
+                    target
+                        .select_with_paths(&config.data)
+                        .into_iter()
+                        .find(|(_, value)| match &count.equals {
+                            Some(equals) => values_equal(value, equals, false),
+                            None => !value.is_null(),
+                        });
+                if let Some((path, _)) = matched {
+                    matches.push((i, path));
+                }
+            }
+
+            let expected = format!("Exactly one file with {}", wanted);
+            match matches.split_first() {
+                None => problems.push((
+                    usize::MAX,
+                    ValidationError::new(
+                        "",
+                        format!("No file has {}", wanted),
+                        expected,
+                        format!("None of the {} files", configs.len()),
+                    ),
+                )),
+                Some(((first_file, first_path), others)) => {
+                    for (i, path) in others {
+                        problems.push((
+                            *i,
+                            ValidationError::new(
+                                path,
+                                format!("More than one file has {}", wanted),
+                                expected.clone(),
+                                format!("Also in '{}' at {}", file_name(*first_file), first_path),
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+
+        let mut report = ValidationReport::default();
+        for (i, mut error) in problems {
+            if let Some(config) = configs.get(i) {
+                error.file = Some(file_name(i));
+                locate_errors(config, std::slice::from_mut(&mut error));
+            }
+            report.errors.push(error);
+        }
+
+        Ok(report)
+    }
+
+    /// Fill in the `default` of every absent key that has one
+    ///
+    /// Defaults are applied through `$ref`s, `all_of` branches, discriminator
+    /// variants, list items and map values, and inside other defaults. The
+    /// returned configuration keeps the input's format but is not validated;
+    /// pass it to [`Validator::validate`] to check it.
+    pub fn resolve(&self, config: &Config) -> ConfigGuardResult<Config> {
+        let ctx = ValidationContext::new(&self.schema, &config.data);
+        let mut resolved = config.clone();
+        fill_defaults(&ctx, &mut resolved.data, self.schema.root())?;
+
+        Ok(resolved)
+    }
+}
+
 /// Validate a configuration against a schema
+///
+/// Validation failures are returned as `ConfigGuardError::AllValidationErrors`.
+/// The schema is compiled on every call; compile it once into a [`Validator`]
+/// instead, which also reports warnings through its [`ValidationReport`].
+#[deprecated(note = "compiles the schema on every call; use `Validator::validate` instead")]
 pub fn validate(
     config: &Config,
     schema: &Schema,
     strict: bool,
 ) -> ConfigGuardResult<ValidationResult> {
-    let mut errors = Vec::new();
-
-    validate_node(&config.data, &schema.root, "", &mut errors, strict)?;
-
-    // If we have the original content, try to find line numbers for each error
-    if let Some(content) = &config.content {
-        find_line_numbers(content, &mut errors, config.format);
-    }
+    let schema = schema.clone().compile()?;
+    let mut errors = collect_errors(config, &schema, strict)?;
+    errors.retain(|e| e.severity == Severity::Error);
 
     if errors.is_empty() {
         Ok(ValidationResult::Valid)
@@ -60,71 +434,298 @@ pub fn validate(
     }
 }
 
-/// Find line numbers for validation errors based on the path
-fn find_line_numbers(content: &str, errors: &mut [ValidationError], format: ConfigFormat) {
-    // Create a map of paths to line numbers
-    let mut path_to_line = std::collections::HashMap::new();
+/// Run validation and collect every diagnostic (errors and warnings) for the configuration
+fn collect_errors(
+    config: &Config,
+    schema: &CompiledSchema,
+    strict: bool,
+) -> ConfigGuardResult<Vec<ValidationError>> {
+    let mut errors = Vec::new();
+    let ctx = ValidationContext::new(schema, &config.data);
 
-    let lines: Vec<&str> = content.lines().collect();
+    validate_node(&ctx, &config.data, schema.root(), "", &mut errors, strict)?;
 
-    match format {
-        ConfigFormat::Yaml => {
-            // For YAML, we look for keys that match our paths
-            for (i, line) in lines.iter().enumerate() {
-                let trimmed = line.trim();
-                if let Some(key) = trimmed.split(':').next() {
-                    let key = key.trim();
-                    if !key.is_empty() && !key.starts_with('#') {
-                        // Store both the simple key and potential path components
-                        path_to_line.insert(key.to_string(), i + 1);
+    locate_errors(config, &mut errors);
 
-                        // Also try to match array indices like [0]
-                        if key.ends_with(']') {
-                            if let Some(base_key) = key.split('[').next() {
-                                path_to_line.insert(base_key.to_string(), i + 1);
-                            }
-                        }
+    Ok(errors)
+}
+
+/// State shared by every step of a single validation run
+struct ValidationContext<'a> {
+    /// The compiled schema, used to resolve `$ref`s
+    schema: &'a CompiledSchema,
+
+    /// Today's date (`YYYY-MM-DD`), compared against `deprecated_until`
+    today: String,
+
+    /// The whole configuration, which `ref_to` paths select from
+    document: &'a Value,
+
+    /// Names selected by each `ref_to` path, collected on first use
+    ref_targets: RefCell<HashMap<String, Rc<Vec<String>>>>,
+
+    /// Set while checking an `if` rule, which only looks at the keys it names
+    /// and so accepts any other key, whatever the schema or strict mode say
+    ignore_unknown_keys: Cell<bool>,
+}
+
+impl<'a> ValidationContext<'a> {
+    fn new(schema: &'a CompiledSchema, document: &'a Value) -> Self {
+        Self {
+            schema,
+            today: today(),
+            document,
+            ref_targets: RefCell::new(HashMap::new()),
+            ignore_unknown_keys: Cell::new(false),
+        }
+    }
+
+    /// The compiled state of a rule
+    fn compiled(&self, rule: &SchemaRule) -> ConfigGuardResult<&'a CompiledRule> {
+        self.schema.rule(rule)
+    }
+}
+
+/// Whether any of the diagnostics is an error rather than a warning
+fn has_errors(diagnostics: &[ValidationError]) -> bool {
+    diagnostics.iter().any(|e| e.severity == Severity::Error)
+}
+
+/// Insert the defaults of absent keys into a configuration node
+fn fill_defaults(
+    ctx: &ValidationContext,
+    value: &mut Value,
+    rule: &SchemaRule,
+) -> ConfigGuardResult<()> {
+    let compiled = ctx.compiled(rule)?;
+    if let Some(name) = &compiled.reference {
+        let definition = ctx.schema.definition(name).ok_or_else(|| {
+            ConfigGuardError::Internal(format!("Unresolved reference to definition '{}'", name))
+        })?;
+        fill_defaults(ctx, value, definition)?;
+    }
+    for branch in rule.all_of.iter().flatten() {
+        fill_defaults(ctx, value, branch)?;
+    }
+
+    match value {
+        Value::Mapping(map) => {
+            let variant = match &rule.discriminator {
+                Some(discriminator) => {
+                    select_variant(ctx, map, rule, discriminator, "", &mut Vec::new())?
+                }
+                None => None,
+            };
+            let key_sets: Vec<&HashMap<String, SchemaRule>> = std::iter::once(rule)
+                .chain(variant)
+                .filter_map(|r| r.keys.as_ref())
+                .collect();
+
+            // Insert in sorted order so the output does not depend on hash order
+            let mut missing: Vec<(&String, &Value)> = key_sets
+                .iter()
+                .flat_map(|keys| keys.iter())
+                .filter_map(|(key_name, key_rule)| Some((key_name, key_rule.default.as_ref()?)))
+                .filter(|(key_name, _)| !map.contains_key(key_name.as_str()))
+                .collect();
+            missing.sort_by(|a, b| a.0.cmp(b.0));
+            for (key_name, default) in missing {
+                map.insert(Value::String(key_name.clone()), default.clone());
+            }
+
+            for (key, val) in map.iter_mut() {
+                if let Value::String(key_name) = key {
+                    for key_rule in rules_for_key(rule, compiled, &key_sets, key_name) {
+                        fill_defaults(ctx, val, key_rule)?;
                     }
                 }
             }
         }
-        ConfigFormat::Json => {
-            // For JSON, we need a more sophisticated approach
-            // This is a simplified version that looks for key patterns
-            for (i, line) in lines.iter().enumerate() {
-                let trimmed = line.trim();
-                if trimmed.contains('"') && trimmed.contains(':') {
-                    if let Some(key) = trimmed.split(':').next() {
-                        let key = key.trim().trim_matches('"').trim_matches('"');
-                        if !key.is_empty() {
-                            path_to_line.insert(key.to_string(), i + 1);
-                        }
-                    }
+        Value::Sequence(items) => {
+            let prefix_items = rule.prefix_items.as_deref().unwrap_or_default();
+            let additional_rule = match &rule.additional_items {
+                Some(AdditionalItems::Rule(additional)) => Some(additional.as_ref()),
+                Some(AdditionalItems::Allowed(false)) => None,
+                Some(AdditionalItems::Allowed(true)) | None => rule.items.as_deref(),
+            };
+            for (i, item) in items.iter_mut().enumerate() {
+                if let Some(item_rule) = prefix_items.get(i).or(additional_rule) {
+                    fill_defaults(ctx, item, item_rule)?;
                 }
             }
         }
+        _ => {}
     }
 
-    // Update each error with its line number if we can find it
-    for error in errors {
-        // Extract the last component of the path
-        if let Some(last_component) = error.path.split('.').last() {
-            // Remove array indices for matching
-            let clean_component = if last_component.contains('[') {
-                last_component.split('[').next().unwrap_or(last_component)
-            } else {
-                last_component
-            };
+    Ok(())
+}
+
+/// Find where each error is in the configuration's source
+///
+/// Every error gets the exact span of its node, or of the closest enclosing
+/// node that exists when it is about a missing key.
+fn locate_errors(config: &Config, errors: &mut [ValidationError]) {
+    let parsed;
+    let source_map = match &config.source_map {
+        Some(source_map) => Some(source_map),
+        None => {
+            parsed = config
+                .content
+                .as_deref()
+                .and_then(|content| SourceMap::parse(content, config.format));
+            parsed.as_ref()
+        }
+    };
 
-            if let Some(&line) = path_to_line.get(clean_component) {
-                error.line = Some(line);
+    if let Some(source_map) = source_map {
+        for error in errors {
+            if let Some(span) = source_map.locate(&error.path) {
+                error.line = Some(span.start.line);
+                error.span = Some(span);
             }
         }
     }
 }
 
 /// Validate a single node in the configuration
+///
+/// A rule's `severity` caps the severity of everything found while checking it,
+/// so `severity: warning` turns its errors into warnings. Its `error_message`,
+/// `hint` and `docs_url` apply to the problems found at the node itself.
 fn validate_node(
+    ctx: &ValidationContext,
+    value: &Value,
+    rule: &SchemaRule,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+    strict: bool,
+) -> ConfigGuardResult<()> {
+    let first = errors.len();
+    check_node(ctx, value, rule, path, errors, strict)?;
+
+    for error in &mut errors[first..] {
+        if let Some(severity) = rule.severity {
+            error.severity = error.severity.min(severity);
+        }
+        if error.path == path {
+            apply_custom_text(error, rule, &display_value(value));
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace the built-in text of a problem with the rule's `error_message`, `hint` and `docs_url`
+fn apply_custom_text(error: &mut ValidationError, rule: &SchemaRule, value: &str) {
+    if let Some(template) = &rule.error_message {
+        error.message = render_template(template, rule, &error.path, value);
+    }
+    if let Some(template) = &rule.hint {
+        error.hint = Some(render_template(template, rule, &error.path, value));
+    }
+    if rule.docs_url.is_some() {
+        error.docs_url = rule.docs_url.clone();
+    }
+}
+
+/// Constraint placeholders usable in `error_message` and `hint`
+pub(crate) const RULE_PLACEHOLDERS: &[&str] = &[
+    "type",
+    "min",
+    "max",
+    "exclusive_min",
+    "exclusive_max",
+    "multiple_of",
+    "min_length",
+    "max_length",
+    "pattern",
+    "format",
+    "enum",
+    "const",
+];
+
+/// The value of a constraint placeholder, or `None` if the rule does not set it
+pub(crate) fn rule_placeholder(rule: &SchemaRule, name: &str) -> Option<String> {
+    let number = |bound: &Option<Value>| bound.as_ref().map(display_value);
+    match name {
+        "type" => Some(rule.data_type.to_string()),
+        "min" => number(&rule.min),
+        "max" => number(&rule.max),
+        "exclusive_min" => number(&rule.exclusive_min),
+        "exclusive_max" => number(&rule.exclusive_max),
+        "multiple_of" => number(&rule.multiple_of),
+        "min_length" => rule.min_length.map(|n| n.to_string()),
+        "max_length" => rule.max_length.map(|n| n.to_string()),
+        "pattern" => rule.pattern.clone(),
+        "format" => rule.format.map(|format| format.name().to_string()),
+        "const" => rule.const_value.as_ref().map(display_value),
+        "enum" => rule.enum_values.as_ref().map(|values| {
+            values
+                .iter()
+                .map(display_value)
+                .collect::<Vec<_>>()
+                .join(", ")
+        }),
+        _ => None,
+    }
+}
+
+/// Names of the `{placeholder}`s in a template
+///
+/// Braces around anything other than a lowercase name are left as they are.
+pub(crate) fn placeholders(template: &str) -> Vec<&str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}'))
+        .map(|(name, _)| name)
+        .filter(|name| is_placeholder_name(name))
+        .collect()
+}
+
+fn is_placeholder_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c == '_')
+}
+
+/// Fill in the placeholders of an `error_message` or `hint`
+fn render_template(template: &str, rule: &SchemaRule, path: &str, value: &str) -> String {
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.split_once('}') {
+            Some((name, tail)) if is_placeholder_name(name) => {
+                match name {
+                    "value" => rendered.push_str(value),
+                    "path" => rendered.push_str(path),
+                    _ => rendered.push_str(&rule_placeholder(rule, name).unwrap_or_default()),
+                }
+                rest = tail;
+            }
+            _ => {
+                rendered.push('{');
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// The type a rule expects, for type mismatch messages, e.g. "string or null"
+fn expected_type_name(rule: &SchemaRule) -> String {
+    let data_type = &rule.data_type;
+    if rule.nullable && !data_type.members().contains(&SchemaType::Null) {
+        format!("{} or null", data_type)
+    } else {
+        data_type.to_string()
+    }
+}
+
+/// Check a node against a single rule, before its `severity` is applied
+fn check_node(
+    ctx: &ValidationContext,
     value: &Value,
     rule: &SchemaRule,
     path: &str,
@@ -133,39 +734,62 @@ fn validate_node(
 ) -> ConfigGuardResult<()> {
     // Adjust allow_unknown_keys based on strict mode
     // In strict mode, we don't allow unknown keys regardless of the schema setting
-    let allow_unknown_keys = if strict {
+    let allow_unknown_keys = if ctx.ignore_unknown_keys.get() {
+        true
+    } else if strict {
         false
     } else {
         rule.allow_unknown_keys
     };
 
-    // Type validation first
-    if !validate_type(value, &rule.data_type) {
-        errors.push(ValidationError {
-            path: path.to_string(),
-            message: "Type mismatch".to_string(),
-            expected: format!("{:?}", rule.data_type),
-            actual: value_type_name(value),
-            description: rule.description.clone(),
-            line: None,
-        });
-        // Don't proceed with further checks if type doesn't match
+    // Nullable rules accept null without applying any other constraint
+    if value.is_null() && rule.is_nullable() {
         return Ok(());
     }
+    let compiled = ctx.compiled(rule)?;
+
+    // Type validation first; constraints then apply to the type the value matched
+    let Some(value_type) = rule
+        .data_type
+        .members()
+        .iter()
+        .find(|data_type| validate_type(value, data_type))
+    else {
+        errors.push(
+            ValidationError::new(
+                path,
+                "Type mismatch",
+                expected_type_name(rule),
+                value_type_name(value),
+            )
+            .with_description(rule.description.clone()),
+        );
+        // Don't proceed with further checks if type doesn't match
+        return Ok(());
+    };
 
     // Type-specific validation
-    match rule.data_type {
+    match value_type {
         SchemaType::Object => {
-            validate_object(value, rule, path, errors, allow_unknown_keys)?;
+            validate_object(ctx, value, rule, path, errors, allow_unknown_keys)?;
         }
         SchemaType::List => {
-            validate_list(value, rule, path, errors)?;
+            validate_list(ctx, value, rule, path, errors)?;
         }
         SchemaType::String => {
-            validate_string(value, rule, path, errors)?;
+            validate_string(value, rule, compiled, path, errors)?;
         }
-        SchemaType::Integer | SchemaType::Float => {
-            validate_number(value, rule, path, errors)?;
+        SchemaType::Integer
+        | SchemaType::Float
+        | SchemaType::U8
+        | SchemaType::U16
+        | SchemaType::U32
+        | SchemaType::U64
+        | SchemaType::I8
+        | SchemaType::I16
+        | SchemaType::I32
+        | SchemaType::I64 => {
+            validate_number(value, value_type, rule, compiled, path, errors)?;
         }
         SchemaType::Boolean => {
             // Already validated by type check
@@ -176,241 +800,325 @@ fn validate_node(
         SchemaType::Any => {
             // Any type is always valid
         }
+        SchemaType::Duration | SchemaType::ByteSize | SchemaType::Percentage => {
+            if let Some(quantity) = Quantity::of(value_type) {
+                validate_quantity(value, quantity, rule, compiled, path, errors);
+            }
+        }
+        SchemaType::Union(_) => {
+            // Union members are never unions themselves
+        }
+    }
+
+    validate_allowed_values(value, rule, path, errors);
+    if let (Some(target), Value::String(name)) = (&compiled.ref_to, value) {
+        check_reference(ctx, name, target, rule, path, errors);
+    }
+
+    // The value must also satisfy the referenced definition
+    if let Some(name) = &compiled.reference {
+        let target = ctx.schema.definition(name).ok_or_else(|| {
+            ConfigGuardError::Internal(format!("Unresolved reference to definition '{}'", name))
+        })?;
+        validate_node(ctx, value, target, path, errors, strict)?;
     }
 
+    validate_combinators(ctx, value, rule, path, errors, strict)?;
+    validate_conditional(ctx, value, rule, path, errors, strict)?;
+
     Ok(())
 }
 
-/// Validate an object node against a schema rule
-fn validate_object(
+/// Validate a node against the `all_of`, `any_of`, `one_of` and `not` combinators
+fn validate_combinators(
+    ctx: &ValidationContext,
     value: &Value,
     rule: &SchemaRule,
     path: &str,
     errors: &mut Vec<ValidationError>,
-    allow_unknown_keys: bool,
+    strict: bool,
 ) -> ConfigGuardResult<()> {
-    if let Value::Mapping(map) = value {
-        // Check if all required keys are present
-        if let Some(keys) = &rule.keys {
-            for (key_name, key_rule) in keys {
-                if key_rule.required && !map.contains_key(Value::String(key_name.clone())) {
-                    let field_desc = key_rule.description.clone();
-                    errors.push(ValidationError {
-                        path: if path.is_empty() {
-                            format!(".{}", key_name)
-                        } else {
-                            format!("{}.{}", path, key_name)
-                        },
-                        message: "Required key missing".to_string(),
-                        expected: "Key to be present".to_string(),
-                        actual: "Key is absent".to_string(),
-                        description: field_desc,
-                        line: None,
-                    });
-                } else if key_rule.required && key_rule.data_type == SchemaType::Object {
-                    // Check if the required object is empty when it shouldn't be
-                    if let Some(Value::Mapping(inner_map)) =
-                        map.get(&Value::String(key_name.clone()))
-                    {
-                        if inner_map.is_empty()
-                            && key_rule.keys.is_some()
-                            && !key_rule.keys.as_ref().unwrap().is_empty()
-                        {
-                            let field_desc = key_rule.description.clone();
-                            errors.push(ValidationError {
-                                path: if path.is_empty() {
-                                    format!(".{}", key_name)
-                                } else {
-                                    format!("{}.{}", path, key_name)
-                                },
-                                message: "Required object is empty".to_string(),
-                                expected: "Object with required fields".to_string(),
-                                actual: "Empty object".to_string(),
-                                description: field_desc,
-                                line: None,
-                            });
-                        }
-                    }
-                }
-            }
+    if let Some(branches) = &rule.all_of {
+        for branch in branches {
+            validate_node(ctx, value, branch, path, errors, strict)?;
+        }
+    }
 
-            // Check each key in the configuration
-            for (key, val) in map {
-                if let Value::String(key_name) = key {
-                    // Check if the key is defined in the schema
-                    if let Some(key_rule) = keys.get(key_name) {
-                        // Validate the value against the key's rule
-                        let new_path = if path.is_empty() {
-                            format!(".{}", key_name)
-                        } else {
-                            format!("{}.{}", path, key_name)
-                        };
+    if let Some(branches) = &rule.any_of {
+        let outcomes = validate_branches(ctx, value, branches, path, strict)?;
+        // Warnings (e.g. deprecated keys) of the first matching branch are kept
+        match outcomes
+            .iter()
+            .position(|branch_errors| !has_errors(branch_errors))
+        {
+            Some(i) => errors.extend(outcomes.into_iter().nth(i).unwrap_or_default()),
+            None => push_no_match(
+                rule,
+                branches,
+                outcomes,
+                path,
+                value,
+                "Value does not match any of the allowed alternatives",
+                errors,
+            ),
+        }
+    }
 
-                        // Pass down the strict mode setting to nested validations
-                        // If we're in strict mode (allow_unknown_keys is false), pass that down
-                        // Otherwise use the key rule's setting
-                        let nested_allow_unknown = if !allow_unknown_keys {
-                            false // Strict mode
-                        } else {
-                            key_rule.allow_unknown_keys
-                        };
-                        validate_node(val, key_rule, &new_path, errors, nested_allow_unknown)?;
-                    } else if !allow_unknown_keys {
-                        // Report unknown key error if in strict mode
-                        errors.push(ValidationError {
-                            path: if path.is_empty() {
-                                format!(".{}", key_name)
-                            } else {
-                                format!("{}.{}", path, key_name)
-                            },
-                            message: "Unknown key".to_string(),
-                            expected: "Key defined in schema".to_string(),
-                            actual: "Undefined key".to_string(),
-                            description: None,
-                            line: None,
-                        });
-                    }
-                }
-            }
+    if let Some(branches) = &rule.one_of {
+        let outcomes = validate_branches(ctx, value, branches, path, strict)?;
+        let matched: Vec<usize> = outcomes
+            .iter()
+            .enumerate()
+            .filter(|(_, branch_errors)| !has_errors(branch_errors))
+            .map(|(i, _)| i)
+            .collect();
+
+        match matched.len() {
+            0 => push_no_match(
+                rule,
+                branches,
+                outcomes,
+                path,
+                value,
+                "Value does not match exactly one of the alternatives",
+                errors,
+            ),
+            1 => errors.extend(outcomes.into_iter().nth(matched[0]).unwrap_or_default()),
+            _ => errors.push(
+                ValidationError::new(
+                    path,
+                    "Value matches more than one alternative",
+                    format!("Exactly one of: {}", branch_list(branches)),
+                    format!(
+                        "Matched {}",
+                        matched
+                            .iter()
+                            .map(|&i| branch_label(branches, i))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                )
+                .with_description(rule.description.clone()),
+            ),
+        }
+    }
+
+    if let Some(not) = &rule.not {
+        let mut branch_errors = Vec::new();
+        validate_node(ctx, value, not, path, &mut branch_errors, strict)?;
+        if !has_errors(&branch_errors) {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    "Value matches a disallowed rule",
+                    format!("Anything except {}", not.label()),
+                    display_value(value),
+                )
+                .with_description(rule.description.clone()),
+            );
         }
     }
 
     Ok(())
 }
 
-/// Validate a list node against a schema rule
-fn validate_list(
+/// Validate a value against each branch separately, collecting each branch's errors
+fn validate_branches(
+    ctx: &ValidationContext,
     value: &Value,
+    branches: &[SchemaRule],
+    path: &str,
+    strict: bool,
+) -> ConfigGuardResult<Vec<Vec<ValidationError>>> {
+    branches
+        .iter()
+        .map(|branch| {
+            let mut branch_errors = Vec::new();
+            validate_node(ctx, value, branch, path, &mut branch_errors, strict)?;
+            Ok(branch_errors)
+        })
+        .collect()
+}
+
+/// Report that no branch matched, followed by the errors of the closest branch
+///
+/// The closest branch is the one whose type matched with the fewest errors, which
+/// is usually the alternative the author intended.
+fn push_no_match(
     rule: &SchemaRule,
+    branches: &[SchemaRule],
+    outcomes: Vec<Vec<ValidationError>>,
     path: &str,
+    value: &Value,
+    message: &str,
     errors: &mut Vec<ValidationError>,
-) -> ConfigGuardResult<()> {
-    if let Value::Sequence(items) = value {
-        // Check list length constraints
-        if let Some(min_length) = rule.min_length {
-            if items.len() < min_length {
-                errors.push(ValidationError {
-                    path: path.to_string(),
-                    message: "List too short".to_string(),
-                    expected: format!("At least {} items", min_length),
-                    actual: format!("{} items", items.len()),
-                    description: rule.description.clone(),
-                    line: None,
-                });
+) {
+    let closest = outcomes
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, branch_errors)| {
+            let type_mismatch = branch_errors
+                .iter()
+                .any(|e| e.path == path && e.message == "Type mismatch");
+            (type_mismatch, branch_errors.len())
+        })
+        .map(|(i, _)| i);
 
-                // If the list is empty and items are required, don't try to validate items
-                if items.is_empty() && min_length > 0 {
-                    return Ok(());
-                }
-            }
-        }
+    errors.push(
+        ValidationError::new(
+            path,
+            message,
+            format!("One of: {}", branch_list(branches)),
+            match closest {
+                Some(i) => format!(
+                    "{} (closest match: {})",
+                    value_type_name(value),
+                    branch_label(branches, i)
+                ),
+                None => value_type_name(value),
+            },
+        )
+        .with_description(rule.description.clone()),
+    );
 
-        if let Some(max_length) = rule.max_length {
-            if items.len() > max_length {
-                errors.push(ValidationError {
-                    path: path.to_string(),
-                    message: "List too long".to_string(),
-                    expected: format!("At most {} items", max_length),
-                    actual: format!("{} items", items.len()),
-                    description: rule.description.clone(),
-                    line: None,
-                });
-            }
-        }
+    if let Some(i) = closest {
+        errors.extend(outcomes.into_iter().nth(i).unwrap_or_default());
+    }
+}
 
-        // Validate each item if a rule is defined
-        if let Some(item_rule) = &rule.items {
-            for (i, item) in items.iter().enumerate() {
-                let item_path = format!("{}[{}]", path, i);
-                validate_node(item, item_rule, &item_path, errors, false)?;
-            }
+/// Label a combinator branch as `#N (description, $ref or type)`
+fn branch_label(branches: &[SchemaRule], index: usize) -> String {
+    format!("#{} ({})", index + 1, branches[index].label())
+}
+
+/// List every combinator branch for an `Expected:` line
+fn branch_list(branches: &[SchemaRule]) -> String {
+    (0..branches.len())
+        .map(|i| branch_label(branches, i))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Render a scalar value for error output, falling back to its type name
+pub(crate) fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        // Lists and objects are shown inline as JSON, e.g. `{"name":"web"}`
+        Value::Sequence(_) | Value::Mapping(_) => {
+            serde_json::to_string(value).unwrap_or_else(|_| value_type_name(value))
         }
+        _ => value_type_name(value),
     }
-
-    Ok(())
 }
 
-/// Validate a string node against a schema rule
-fn validate_string(
+/// Validate an object node against a schema rule
+fn validate_object(
+    ctx: &ValidationContext,
     value: &Value,
     rule: &SchemaRule,
     path: &str,
     errors: &mut Vec<ValidationError>,
+    allow_unknown_keys: bool,
 ) -> ConfigGuardResult<()> {
-    if let Value::String(s) = value {
-        // Check string length constraints
-        if let Some(min_length) = rule.min_length {
-            if s.len() < min_length {
-                errors.push(ValidationError {
-                    path: path.to_string(),
-                    message: "String too short".to_string(),
-                    expected: format!("At least {} characters", min_length),
-                    actual: format!("{} characters", s.len()),
-                    description: rule.description.clone(),
-                    line: None,
-                });
-            }
+    if let Value::Mapping(map) = value {
+        let first_error = errors.len();
+        let compiled = ctx.compiled(rule)?;
+
+        // Key rules come from the object itself and from the selected discriminator variant
+        let variant = match &rule.discriminator {
+            Some(discriminator) => select_variant(ctx, map, rule, discriminator, path, errors)?,
+            None => None,
+        };
+        let key_sets: Vec<&HashMap<String, SchemaRule>> = std::iter::once(rule)
+            .chain(variant)
+            .filter_map(|r| r.keys.as_ref())
+            .collect();
+
+        check_key_count(map, rule, path, errors);
+        check_key_groups(map, rule, path, errors);
+        if let Some(variant) = variant {
+            check_key_groups(map, variant, path, errors);
         }
 
-        if let Some(max_length) = rule.max_length {
-            if s.len() > max_length {
-                errors.push(ValidationError {
-                    path: path.to_string(),
-                    message: "String too long".to_string(),
-                    expected: format!("At most {} characters", max_length),
-                    actual: format!("{} characters", s.len()),
-                    description: rule.description.clone(),
-                    line: None,
-                });
-            }
+        // Check if all required keys are present
+        check_required_keys(map, rule, compiled, path, errors);
+        check_conditional_keys(map, rule, compiled, path, errors);
+        if let Some(variant) = variant {
+            let variant_compiled = ctx.compiled(variant)?;
+            check_required_keys(map, variant, variant_compiled, path, errors);
+            check_conditional_keys(map, variant, variant_compiled, path, errors);
         }
 
-        // Check pattern constraint
-        if let Some(pattern) = &rule.pattern {
-            let regex = Regex::new(pattern).map_err(|e| {
-                ConfigGuardError::Pattern(format!("Invalid pattern in schema: {}", e))
-            })?;
+        // Without any key rules every key is accepted, only its name is checked
+        let has_key_rules =
+            !key_sets.is_empty() || rule.values.is_some() || !compiled.pattern_keys.is_empty();
 
-            if !regex.is_match(s) {
-                errors.push(ValidationError {
-                    path: path.to_string(),
-                    message: "String doesn't match pattern".to_string(),
-                    expected: format!("Pattern: {}", pattern),
-                    actual: s.clone(),
-                    description: rule.description.clone(),
-                    line: None,
-                });
-            }
-        }
+        // Check each key in the configuration
+        for (key, val) in map {
+            if let Value::String(key_name) = key {
+                let new_path = key_path(path, key_name);
+
+                if let Some(key_pattern) = &compiled.key_pattern {
+                    if !key_pattern.is_match(key_name) {
+                        errors.push(
+                            ValidationError::new(
+                                new_path.clone(),
+                                "Key name doesn't match pattern",
+                                format!("Pattern: {}", key_pattern.as_str()),
+                                key_name.as_str(),
+                            )
+                            .with_description(rule.description.clone()),
+                        );
+                    }
+                }
+                if !has_key_rules {
+                    continue;
+                }
+
+                let key_rules = rules_for_key(rule, compiled, &key_sets, key_name);
 
-        // Check enum constraint
-        if let Some(enum_values) = &rule.enum_values {
-            let mut found = false;
+                if !key_rules.is_empty() {
+                    // Validate the value against the key's rule(s)
+                    for key_rule in key_rules {
+                        check_deprecation(ctx, key_name, key_rule, &new_path, errors);
 
-            for enum_val in enum_values {
-                if let Value::String(enum_str) = enum_val {
-                    if s == enum_str {
-                        found = true;
-                        break;
+                        // Pass down the strict mode setting to nested validations
+                        // If we're in strict mode (allow_unknown_keys is false), pass that down
+                        // Otherwise use the key rule's setting
+                        let nested_allow_unknown = if !allow_unknown_keys {
+                            false // Strict mode
+                        } else {
+                            key_rule.allow_unknown_keys
+                        };
+                        validate_node(ctx, val, key_rule, &new_path, errors, nested_allow_unknown)?;
                     }
+                } else if rule
+                    .discriminator
+                    .as_ref()
+                    .is_some_and(|d| &d.field == key_name)
+                {
+                    // The tag field itself was checked when selecting the variant
+                } else if !allow_unknown_keys {
+                    // Report unknown key error if in strict mode
+                    errors.push(
+                        ValidationError::new(
+                            new_path,
+                            "Unknown key",
+                            "Key defined in schema",
+                            "Undefined key",
+                        )
+                        .with_severity(ctx.schema.schema().check_severity.unknown_key),
+                    );
                 }
             }
+        }
 
-            if !found {
-                let allowed_values = enum_values
-                    .iter()
-                    .map(|v| format!("{:?}", v))
-                    .collect::<Vec<_>>()
-                    .join(", ");
-
-                errors.push(ValidationError {
-                    path: path.to_string(),
-                    message: "Value not in allowed set".to_string(),
-                    expected: format!("One of: {}", allowed_values),
-                    actual: s.clone(),
-                    description: rule.description.clone(),
-                    line: None,
-                });
+        // Assertions relate fields to each other, so they only run once every field is valid
+        if !has_errors(&errors[first_error..]) {
+            for assert_rule in std::iter::once(rule).chain(variant) {
+                check_assertions(value, assert_rule, ctx.compiled(assert_rule)?, path, errors);
             }
         }
     }
@@ -418,635 +1126,3558 @@ fn validate_string(
     Ok(())
 }
 
-/// Validate a numeric node against a schema rule
-fn validate_number(
+/// Report the `assert` expressions of an object rule that do not hold
+fn check_assertions(
     value: &Value,
     rule: &SchemaRule,
+    compiled: &CompiledRule,
     path: &str,
     errors: &mut Vec<ValidationError>,
-) -> ConfigGuardResult<()> {
-    // Helper function to get a numeric value as f64
-    fn as_f64(value: &Value) -> Option<f64> {
-        match value {
-            Value::Number(n) => n.as_f64(),
-            _ => None,
-        }
+) {
+    let assertions = rule.assertions.iter().flatten();
+    for (assertion, expr) in assertions.zip(&compiled.assertions) {
+        let error = match expr.evaluate(value) {
+            Ok(Value::Bool(true)) => continue,
+            Ok(Value::Bool(false)) => {
+                // Show the values the expression read, e.g. "min = 5, max = 3"
+                let values: Vec<String> = expr
+                    .references()
+                    .into_iter()
+                    .map(|(field, field_expr)| {
+                        let shown = field_expr
+                            .evaluate(value)
+                            .map_or_else(|e| e, |v| display_value(&v));
+                        format!("{} = {}", field, shown)
+                    })
+                    .collect();
+                let actual = if values.is_empty() {
+                    "false".to_string()
+                } else {
+                    values.join(", ")
+                };
+                ValidationError::new(
+                    path,
+                    assertion.message.as_deref().unwrap_or("Assertion failed"),
+                    assertion.expr.as_str(),
+                    actual,
+                )
+            }
+            Ok(other) => ValidationError::new(
+                path,
+                "Assertion could not be evaluated",
+                assertion.expr.as_str(),
+                format!("A {} instead of true or false", value_type_name(&other)),
+            ),
+            Err(e) => ValidationError::new(
+                path,
+                "Assertion could not be evaluated",
+                assertion.expr.as_str(),
+                e,
+            ),
+        };
+        errors.push(error.with_description(rule.description.clone()));
     }
+}
 
-    if let Some(num) = as_f64(value) {
-        // Check for NaN or infinite values
-        if num.is_nan() {
-            errors.push(ValidationError {
-                path: path.to_string(),
-                message: "Invalid numeric value".to_string(),
-                expected: "A valid number".to_string(),
-                actual: "NaN (Not a Number)".to_string(),
-                description: rule.description.clone(),
-                line: None,
-            });
-            return Ok(());
-        }
+/// Warn about a key marked `deprecated` or `renamed_to`
+///
+/// Past its `deprecated_until` date the key is reported as an error instead.
+fn check_deprecation(
+    ctx: &ValidationContext,
+    key_name: &str,
+    rule: &SchemaRule,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    let (message, expected) = match (&rule.deprecated, &rule.renamed_to) {
+        (None, None) => return,
+        (hint, Some(renamed_to)) => (
+            format!("Key '{}' was renamed to '{}'", key_name, renamed_to),
+            hint.clone()
+                .unwrap_or_else(|| format!("Use '{}' instead", renamed_to)),
+        ),
+        (Some(hint), None) => (format!("Key '{}' is deprecated", key_name), hint.clone()),
+    };
 
-        if num.is_infinite() {
-            errors.push(ValidationError {
-                path: path.to_string(),
-                message: "Invalid numeric value".to_string(),
-                expected: "A finite number".to_string(),
-                actual: if num.is_sign_positive() {
-                    "Positive infinity"
-                } else {
-                    "Negative infinity"
-                }
-                .to_string(),
-                description: rule.description.clone(),
-                line: None,
-            });
-            return Ok(());
-        }
+    let error = match &rule.deprecated_until {
+        Some(until) if ctx.today.as_str() > until.as_str() => ValidationError::new(
+            path,
+            format!("{} and is no longer accepted after {}", message, until),
+            expected,
+            "Deprecated key is set",
+        ),
+        _ => ValidationError::new(path, message, expected, "Deprecated key is set")
+            .with_severity(ctx.schema.schema().check_severity.deprecated),
+    };
+    errors.push(error.with_description(rule.description.clone()));
+}
 
-        // Check min constraint
-        if let Some(min) = &rule.min {
-            if let Some(min_val) = min.as_f64() {
-                // Use <= for inclusive minimum check
-                if num < min_val {
-                    errors.push(ValidationError {
-                        path: path.to_string(),
-                        message: "Value too small".to_string(),
-                        expected: format!("At least {}", min_val),
-                        actual: format!("{}", num),
-                        description: rule.description.clone(),
-                        line: None,
-                    });
-                }
-            }
-        }
+/// Report required keys of a rule that are missing or empty in an object
+fn check_required_keys(
+    map: &serde_yaml::Mapping,
+    rule: &SchemaRule,
+    compiled: &CompiledRule,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    let Some(keys) = &rule.keys else {
+        return;
+    };
 
-        // Check max constraint
-        if let Some(max) = &rule.max {
-            if let Some(max_val) = max.as_f64() {
-                if num > max_val {
-                    errors.push(ValidationError {
-                        path: path.to_string(),
-                        message: "Value too large".to_string(),
-                        expected: format!("At most {}", max_val),
-                        actual: format!("{}", num),
-                        description: rule.description.clone(),
-                        line: None,
-                    });
+    for key_name in &compiled.required_keys {
+        let key_rule = &keys[key_name];
+        if !map.contains_key(Value::String(key_name.clone())) {
+            let field_desc = key_rule.description.clone();
+            errors.push(key_problem(
+                ValidationError::new(
+                    key_path(path, key_name),
+                    "Required key missing",
+                    "Key to be present",
+                    "Key is absent",
+                )
+                .with_description(field_desc),
+                key_rule,
+            ));
+        } else if key_rule.data_type == SchemaType::Object {
+            // Check if the required object is empty when it shouldn't be
+            if let Some(Value::Mapping(inner_map)) = map.get(Value::String(key_name.clone())) {
+                if inner_map.is_empty()
+                    && key_rule.keys.is_some()
+                    && !key_rule.keys.as_ref().unwrap().is_empty()
+                {
+                    let field_desc = key_rule.description.clone();
+                    errors.push(key_problem(
+                        ValidationError::new(
+                            key_path(path, key_name),
+                            "Required object is empty",
+                            "Object with required fields",
+                            "Empty object",
+                        )
+                        .with_description(field_desc),
+                        key_rule,
+                    ));
                 }
             }
         }
+    }
+}
 
-        // Check enum constraint
-        if let Some(enum_values) = &rule.enum_values {
-            let mut found = false;
+/// Apply a key's `severity` and custom text to a problem its parent object reports about it
+fn key_problem(mut error: ValidationError, key_rule: &SchemaRule) -> ValidationError {
+    if let Some(severity) = key_rule.severity {
+        error.severity = severity;
+    }
+    let actual = error.actual.clone();
+    apply_custom_text(&mut error, key_rule, &actual);
+    error
+}
 
-            for enum_val in enum_values {
-                if let Some(enum_num) = as_f64(enum_val) {
-                    // More robust floating-point comparison
-                    if (num - enum_num).abs() <= f64::EPSILON {
-                        found = true;
-                        break;
-                    }
-                } else {
-                    // Add an error for non-numeric enum values when validating numbers
-                    errors.push(ValidationError {
-                        path: path.to_string(),
-                        message: "Invalid enum value type".to_string(),
-                        expected: "Numeric value for numeric field".to_string(),
-                        actual: format!("Non-numeric value: {:?}", enum_val),
-                        description: rule.description.clone(),
-                        line: None,
-                    });
-                    // Don't continue checking other enum values if we found an invalid type
-                    return Ok(());
-                }
-            }
+/// Rules that apply to the value of a key
+///
+/// Keys listed in `keys` (of the object or its discriminator variant) use those
+/// rules; otherwise every matching `pattern_keys` rule applies, and `values` is
+/// the fallback for the remaining keys.
+fn rules_for_key<'a>(
+    rule: &'a SchemaRule,
+    compiled: &CompiledRule,
+    key_sets: &[&'a HashMap<String, SchemaRule>],
+    key_name: &str,
+) -> Vec<&'a SchemaRule> {
+    let listed: Vec<&SchemaRule> = key_sets
+        .iter()
+        .filter_map(|keys| keys.get(key_name))
+        .collect();
+    if !listed.is_empty() {
+        return listed;
+    }
 
-            if !found {
-                let allowed_values = enum_values
-                    .iter()
-                    .map(|v| format!("{:?}", v))
-                    .collect::<Vec<_>>()
-                    .join(", ");
-
-                errors.push(ValidationError {
-                    path: path.to_string(),
-                    message: "Value not in allowed set".to_string(),
-                    expected: format!("One of: {}", allowed_values),
-                    actual: format!("{}", num),
-                    description: rule.description.clone(),
-                    line: None,
-                });
-            }
+    if let Some(pattern_keys) = &rule.pattern_keys {
+        let matched: Vec<&SchemaRule> = compiled
+            .pattern_keys
+            .iter()
+            .filter(|(regex, _)| regex.is_match(key_name))
+            .map(|(_, pattern)| &pattern_keys[pattern])
+            .collect();
+        if !matched.is_empty() {
+            return matched;
         }
     }
 
-    Ok(())
+    rule.values.as_deref().into_iter().collect()
 }
 
-/// Check if a value matches the expected type
-fn validate_type(value: &Value, expected_type: &SchemaType) -> bool {
-    match expected_type {
-        SchemaType::String => matches!(value, Value::String(_)),
-        SchemaType::Integer => {
-            if let Value::Number(n) = value {
-                n.is_i64() || n.is_u64()
-            } else {
-                false
-            }
-        }
-        SchemaType::Float => {
-            if let Value::Number(n) = value {
-                n.is_f64()
-            } else {
-                false
-            }
+/// Check the `min_keys` and `max_keys` bounds of an object
+fn check_key_count(
+    map: &serde_yaml::Mapping,
+    rule: &SchemaRule,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    if let Some(min_keys) = rule.min_keys {
+        if map.len() < min_keys {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    "Object has too few keys",
+                    format!("At least {} keys", min_keys),
+                    format!("{} keys", map.len()),
+                )
+                .with_description(rule.description.clone()),
+            );
         }
-        SchemaType::Boolean => matches!(value, Value::Bool(_)),
-        SchemaType::Object => matches!(value, Value::Mapping(_)),
-        SchemaType::List => matches!(value, Value::Sequence(_)),
-        SchemaType::Null => matches!(value, Value::Null),
-        SchemaType::Any => true,
     }
-}
 
-/// Get a human-readable name for a value's type
-fn value_type_name(value: &Value) -> String {
-    match value {
-        Value::String(_) => "string".to_string(),
-        Value::Number(n) => {
-            if n.is_i64() || n.is_u64() {
-                "integer".to_string()
-            } else {
-                "float".to_string()
-            }
+    if let Some(max_keys) = rule.max_keys {
+        if map.len() > max_keys {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    "Object has too many keys",
+                    format!("At most {} keys", max_keys),
+                    format!("{} keys", map.len()),
+                )
+                .with_description(rule.description.clone()),
+            );
         }
-        Value::Bool(_) => "boolean".to_string(),
-        Value::Mapping(_) => "object".to_string(),
-        Value::Sequence(_) => "list".to_string(),
-        Value::Null => "null".to_string(),
-        _ => "unknown".to_string(),
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::config::{Config, ConfigFormat};
-    use crate::schema::{Schema, SchemaRule, SchemaType};
-    use std::collections::HashMap;
+/// Check the `exclusive_keys`, `dependent_keys` and `one_required_of` groups of a rule
+///
+/// Errors are reported at the object's path and list every key in the group.
+fn check_key_groups(
+    map: &serde_yaml::Mapping,
+    rule: &SchemaRule,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    let present_keys = |group: &[String]| -> Vec<String> {
+        group
+            .iter()
+            .filter(|key| map.contains_key(Value::String(key.to_string())))
+            .cloned()
+            .collect()
+    };
 
-    // Helper to create a Config from YAML string
-    fn config_from_yaml(yaml: &str) -> Config {
-        let data = serde_yaml::from_str(yaml).unwrap();
-        Config {
-            data,
-            format: ConfigFormat::Yaml,
-            path: None,
-            content: Some(yaml.to_string()),
+    for group in rule.exclusive_keys.iter().flatten() {
+        let present = present_keys(group);
+        if present.len() > 1 {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    "Mutually exclusive keys present together",
+                    format!("At most one of: {}", group.join(", ")),
+                    format!("Present: {}", present.join(", ")),
+                )
+                .with_description(rule.description.clone()),
+            );
         }
     }
 
-    // Helper to create a Schema from YAML string
-    fn schema_from_yaml(yaml: &str) -> Schema {
-        let rule: SchemaRule = serde_yaml::from_str(yaml).unwrap();
-        Schema { root: rule }
+    for group in rule.dependent_keys.iter().flatten() {
+        let present = present_keys(group);
+        if !present.is_empty() && present.len() < group.len() {
+            let missing: Vec<&str> = group
+                .iter()
+                .filter(|key| !present.contains(key))
+                .map(String::as_str)
+                .collect();
+            errors.push(
+                ValidationError::new(
+                    path,
+                    "Dependent keys must be present together",
+                    format!("All or none of: {}", group.join(", ")),
+                    format!(
+                        "Present: {}; missing: {}",
+                        present.join(", "),
+                        missing.join(", ")
+                    ),
+                )
+                .with_description(rule.description.clone()),
+            );
+        }
     }
 
-    #[test]
-    fn test_validate_valid_simple_config() {
-        let schema_str = r#"
-            type: object
-            keys:
-                name:
-                    type: string
-                    required: true
-                age:
-                    type: integer
-                    min: 0
-                    max: 120
-        "#;
+    for group in rule.one_required_of.iter().flatten() {
+        if present_keys(group).is_empty() {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    "None of the alternative keys is present",
+                    format!("At least one of: {}", group.join(", ")),
+                    "None present",
+                )
+                .with_description(rule.description.clone()),
+            );
+        }
+    }
+}
 
-        let config_str = r#"
-            name: John Doe
-            age: 30
-        "#;
+/// Report keys whose `required_if` or `forbidden_if` condition holds
+fn check_conditional_keys(
+    map: &serde_yaml::Mapping,
+    rule: &SchemaRule,
+    compiled: &CompiledRule,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    let Some(keys) = &rule.keys else {
+        return;
+    };
 
-        let schema = schema_from_yaml(schema_str);
-        let config = config_from_yaml(config_str);
+    for key_name in &compiled.conditional_keys {
+        let key_rule = &keys[key_name];
+        let present = map.contains_key(Value::String(key_name.clone()));
 
-        let result = validate(&config, &schema, false).unwrap();
-        assert!(matches!(result, ValidationResult::Valid));
+        if let Some(conditions) = &key_rule.required_if {
+            if !present && conditions_hold(map, conditions) {
+                let condition = describe_conditions(path, conditions);
+                errors.push(key_problem(
+                    ValidationError::new(
+                        key_path(path, key_name),
+                        format!("Required key missing (when {})", condition),
+                        format!("Key to be present when {}", condition),
+                        "Key is absent",
+                    )
+                    .with_description(key_rule.description.clone()),
+                    key_rule,
+                ));
+            }
+        }
+
+        if let Some(conditions) = &key_rule.forbidden_if {
+            if present && conditions_hold(map, conditions) {
+                let condition = describe_conditions(path, conditions);
+                errors.push(key_problem(
+                    ValidationError::new(
+                        key_path(path, key_name),
+                        format!("Key not allowed (when {})", condition),
+                        format!("Key to be absent when {}", condition),
+                        "Key is present",
+                    )
+                    .with_description(key_rule.description.clone()),
+                    key_rule,
+                ));
+            }
+        }
     }
+}
 
-    #[test]
-    fn test_validate_missing_required_field() {
-        let schema_str = r#"
-            type: object
-            keys:
-                name:
-                    type: string
-                    required: true
-                age:
-                    type: integer
-                    required: true
-        "#;
+/// Whether every `field: value` condition matches the object
+///
+/// Fields may be dotted paths into nested objects (e.g. `tls.enabled`).
+fn conditions_hold(map: &serde_yaml::Mapping, conditions: &HashMap<String, Value>) -> bool {
+    conditions
+        .iter()
+        .all(|(field, expected)| lookup_field(map, field) == Some(expected))
+}
 
-        let config_str = r#"
-            name: John Doe
-            # age is missing
-        "#;
+/// Find the value of a dotted field path inside an object
+fn lookup_field<'a>(map: &'a serde_yaml::Mapping, field: &str) -> Option<&'a Value> {
+    let mut parts = field.split('.');
+    let mut current = map.get(Value::String(parts.next()?.to_string()))?;
+    for part in parts {
+        current = current.as_mapping()?.get(Value::String(part.to_string()))?;
+    }
+    Some(current)
+}
 
-        let schema = schema_from_yaml(schema_str);
-        let config = config_from_yaml(config_str);
+/// Describe `field: value` conditions with their full paths, e.g. `.tls.enabled = true`
+fn describe_conditions(path: &str, conditions: &HashMap<String, Value>) -> String {
+    let mut parts: Vec<String> = conditions
+        .iter()
+        .map(|(field, value)| format!("{}.{} = {}", path, field, display_value(value)))
+        .collect();
+    parts.sort();
+    parts.join(" and ")
+}
 
-        let result = validate(&config, &schema, false);
+/// Apply `then` or `else` depending on whether the value satisfies `if`
+///
+/// Errors from the applied branch name the condition that selected it.
+fn validate_conditional(
+    ctx: &ValidationContext,
+    value: &Value,
+    rule: &SchemaRule,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+    strict: bool,
+) -> ConfigGuardResult<()> {
+    let Some(if_rule) = &rule.if_rule else {
+        return Ok(());
+    };
 
-        assert!(result.is_err());
-        match result {
-            Ok(_) => panic!("Validation should have failed due to missing required field"),
-            Err(err) => match err {
-                ConfigGuardError::AllValidationErrors { errors } => {
-                    assert_eq!(errors.len(), 1);
-                    assert_eq!(errors[0].path, ".age");
-                    assert_eq!(errors[0].message, "Required key missing");
+    // Unknown keys never decide a condition; `if` only looks at the keys it names
+    let mut condition_errors = Vec::new();
+    let outer = ctx.ignore_unknown_keys.replace(true);
+    let checked = validate_node(ctx, value, if_rule, path, &mut condition_errors, false);
+    ctx.ignore_unknown_keys.set(outer);
+    checked?;
+    let matched = !has_errors(&condition_errors);
+
+    let (branch, prefix) = if matched {
+        (&rule.then_rule, "when")
+    } else {
+        (&rule.else_rule, "when not")
+    };
+    let Some(branch) = branch else {
+        return Ok(());
+    };
+
+    let mut branch_errors = Vec::new();
+    validate_node(ctx, value, branch, path, &mut branch_errors, strict)?;
+    if !branch_errors.is_empty() {
+        let condition = describe_if(if_rule, path);
+        for mut error in branch_errors {
+            error.message = format!("{} ({} {})", error.message, prefix, condition);
+            errors.push(error);
+        }
+    }
+
+    Ok(())
+}
+
+/// Describe an `if` rule for error messages
+///
+/// Uses the rule's description when present; otherwise single-value `enum`s on
+/// object keys are spelled out as `.path.key = value`.
+fn describe_if(if_rule: &SchemaRule, path: &str) -> String {
+    if let Some(description) = &if_rule.description {
+        return description.clone();
+    }
+
+    if let Some(keys) = &if_rule.keys {
+        let mut parts: Vec<String> = keys
+            .iter()
+            .map(|(key_name, key_rule)| {
+                match (&key_rule.const_value, key_rule.enum_values.as_deref()) {
+                    (Some(only), _) | (None, Some([only])) => {
+                        format!("{} = {}", key_path(path, key_name), display_value(only))
+                    }
+                    _ => format!("{} matches {}", key_path(path, key_name), key_rule.label()),
                 }
-                _ => panic!("Expected AllValidationErrors, got {:?}", err),
-            },
+            })
+            .collect();
+        parts.sort();
+        if !parts.is_empty() {
+            return parts.join(" and ");
         }
     }
 
+    let location = if path.is_empty() { "." } else { path };
+    format!("{} matches {}", location, if_rule.label())
+}
+
+/// Pick the discriminator variant named by the object's tag field
+///
+/// Returns `None` (after reporting an error) when the tag is missing or unknown.
+/// A variant that is only a `$ref` is resolved to the definition holding its keys.
+fn select_variant<'a>(
+    ctx: &ValidationContext<'a>,
+    map: &serde_yaml::Mapping,
+    rule: &SchemaRule,
+    discriminator: &'a Discriminator,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) -> ConfigGuardResult<Option<&'a SchemaRule>> {
+    let tag_path = key_path(path, &discriminator.field);
+    let mut values: Vec<&str> = discriminator.mapping.keys().map(String::as_str).collect();
+    values.sort();
+    let expected = format!("One of: {}", values.join(", "));
+
+    let tag = match map.get(Value::String(discriminator.field.clone())) {
+        None => {
+            errors.push(
+                ValidationError::new(
+                    tag_path,
+                    "Discriminator field missing",
+                    expected,
+                    "Key is absent",
+                )
+                .with_description(rule.description.clone()),
+            );
+            return Ok(None);
+        }
+        Some(Value::String(tag)) => tag,
+        Some(other) => {
+            errors.push(
+                ValidationError::new(
+                    tag_path,
+                    "Discriminator value must be a string",
+                    expected,
+                    value_type_name(other),
+                )
+                .with_description(rule.description.clone()),
+            );
+            return Ok(None);
+        }
+    };
+
+    let Some(mut variant) = discriminator.mapping.get(tag) else {
+        let message = match did_you_mean(tag, values.iter().copied()) {
+            Some(suggestion) => format!(
+                "Unknown discriminator value '{}' (did you mean '{}'?)",
+                tag, suggestion
+            ),
+            None => format!("Unknown discriminator value '{}'", tag),
+        };
+        errors.push(
+            ValidationError::new(tag_path, message, expected, tag.as_str())
+                .with_description(rule.description.clone()),
+        );
+        return Ok(None);
+    };
+
+    while variant.keys.is_none() {
+        let Some(name) = &ctx.compiled(variant)?.reference else {
+            break;
+        };
+        variant = ctx.schema.definition(name).ok_or_else(|| {
+            ConfigGuardError::Internal(format!("Unresolved reference to definition '{}'", name))
+        })?;
+    }
+
+    Ok(Some(variant))
+}
+
+/// Suggest the candidate closest to a misspelled value, if any is close enough
+fn did_you_mean<'a>(value: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let threshold = (value.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(value, candidate), candidate))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Edit distance between two strings, counting a swap of adjacent characters as one edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+/// Validate a list node against a schema rule
+fn validate_list(
+    ctx: &ValidationContext,
+    value: &Value,
+    rule: &SchemaRule,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) -> ConfigGuardResult<()> {
+    if let Value::Sequence(items) = value {
+        // Check list length constraints
+        if let Some(min_length) = rule.min_length {
+            if items.len() < min_length {
+                errors.push(
+                    ValidationError::new(
+                        path,
+                        "List too short",
+                        format!("At least {} items", min_length),
+                        format!("{} items", items.len()),
+                    )
+                    .with_description(rule.description.clone()),
+                );
+
+                // If the list is empty and items are required, don't try to validate items
+                if items.is_empty() && min_length > 0 {
+                    return Ok(());
+                }
+            }
+        }
+
+        if let Some(max_length) = rule.max_length {
+            if items.len() > max_length {
+                errors.push(
+                    ValidationError::new(
+                        path,
+                        "List too long",
+                        format!("At most {} items", max_length),
+                        format!("{} items", items.len()),
+                    )
+                    .with_description(rule.description.clone()),
+                );
+            }
+        }
+
+        check_unique_items(items, rule, path, errors);
+        check_contains(ctx, items, rule, path, errors)?;
+
+        if let Some(prefix_items) = &rule.prefix_items {
+            return validate_tuple(ctx, items, rule, prefix_items, path, errors);
+        }
+
+        // Validate each item if a rule is defined
+        if let Some(item_rule) = &rule.items {
+            for (i, item) in items.iter().enumerate() {
+                let item_path = format!("{}[{}]", path, i);
+                validate_node(ctx, item, item_rule, &item_path, errors, false)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Report items that repeat an earlier item (`unique_items`) or its `unique_by` fields
+///
+/// Each duplicate is reported at its own index and names the index of the first
+/// occurrence. Items lacking one of the `unique_by` fields are skipped.
+fn check_unique_items(
+    items: &[Value],
+    rule: &SchemaRule,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    if rule.unique_items {
+        let mut first_seen: HashMap<&Value, usize> = HashMap::new();
+        for (i, item) in items.iter().enumerate() {
+            if let Some(&first) = first_seen.get(item) {
+                errors.push(
+                    ValidationError::new(
+                        format!("{}[{}]", path, i),
+                        "Duplicate list item",
+                        "Unique items",
+                        format!("{} (same as {}[{}])", display_value(item), path, first),
+                    )
+                    .with_description(rule.description.clone()),
+                );
+            } else {
+                first_seen.insert(item, i);
+            }
+        }
+    }
+
+    if let Some(fields) = &rule.unique_by {
+        let mut first_seen: HashMap<Vec<&Value>, usize> = HashMap::new();
+        for (i, item) in items.iter().enumerate() {
+            let Some(map) = item.as_mapping() else {
+                continue;
+            };
+            let Some(key) = fields
+                .iter()
+                .map(|field| lookup_field(map, field))
+                .collect::<Option<Vec<&Value>>>()
+            else {
+                continue;
+            };
+
+            if let Some(&first) = first_seen.get(&key) {
+                let shown: Vec<String> = key.iter().map(|value| display_value(value)).collect();
+                errors.push(
+                    ValidationError::new(
+                        format!("{}[{}]", path, i),
+                        format!("Duplicate value for {}", fields.join(", ")),
+                        format!("Unique {} across items", fields.join(", ")),
+                        format!("{} (same as {}[{}])", shown.join(", "), path, first),
+                    )
+                    .with_description(rule.description.clone()),
+                );
+            } else {
+                first_seen.insert(key, i);
+            }
+        }
+    }
+}
+
+/// Check how many items satisfy the `contains` rule
+fn check_contains(
+    ctx: &ValidationContext,
+    items: &[Value],
+    rule: &SchemaRule,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) -> ConfigGuardResult<()> {
+    let Some(contains) = &rule.contains else {
+        return Ok(());
+    };
+
+    let mut matching = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        let mut item_errors = Vec::new();
+        validate_node(
+            ctx,
+            item,
+            contains,
+            &format!("{}[{}]", path, i),
+            &mut item_errors,
+            false,
+        )?;
+        if !has_errors(&item_errors) {
+            matching.push(i);
+        }
+    }
+
+    let found = if matching.is_empty() {
+        "No matching items".to_string()
+    } else {
+        format!(
+            "{} matching items (at {})",
+            matching.len(),
+            matching
+                .iter()
+                .map(|i| format!("[{}]", i))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+
+    let min_contains = rule.min_contains.unwrap_or(1);
+    if matching.len() < min_contains {
+        errors.push(
+            ValidationError::new(
+                path,
+                "List does not contain enough matching items",
+                format!(
+                    "At least {} items matching {}",
+                    min_contains,
+                    contains.label()
+                ),
+                found.clone(),
+            )
+            .with_description(rule.description.clone()),
+        );
+    }
+
+    if let Some(max_contains) = rule.max_contains {
+        if matching.len() > max_contains {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    "List contains too many matching items",
+                    format!(
+                        "At most {} items matching {}",
+                        max_contains,
+                        contains.label()
+                    ),
+                    found,
+                )
+                .with_description(rule.description.clone()),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate a list whose leading items have positional rules
+///
+/// Lists shorter than `prefix_items` are rejected unless `min_length` allows
+/// it. Items beyond the prefix use the `additional_items` rule, or `items` when
+/// additional items are simply allowed.
+fn validate_tuple(
+    ctx: &ValidationContext,
+    items: &[Value],
+    rule: &SchemaRule,
+    prefix_items: &[SchemaRule],
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) -> ConfigGuardResult<()> {
+    let arity = format!(
+        "{} items ({})",
+        prefix_items.len(),
+        prefix_items
+            .iter()
+            .map(SchemaRule::label)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    if items.len() < prefix_items.len() && rule.min_length.is_none() {
+        errors.push(
+            ValidationError::new(
+                format!("{}[{}]", path, items.len()),
+                "Missing list item",
+                arity.clone(),
+                format!("{} items", items.len()),
+            )
+            .with_description(rule.description.clone()),
+        );
+    }
+
+    for (i, (item, item_rule)) in items.iter().zip(prefix_items).enumerate() {
+        let item_path = format!("{}[{}]", path, i);
+        validate_node(ctx, item, item_rule, &item_path, errors, false)?;
+    }
+
+    let additional_rule = match &rule.additional_items {
+        Some(AdditionalItems::Allowed(false)) => {
+            if items.len() > prefix_items.len() {
+                errors.push(
+                    ValidationError::new(
+                        format!("{}[{}]", path, prefix_items.len()),
+                        "Unexpected list item",
+                        arity,
+                        format!("{} items", items.len()),
+                    )
+                    .with_description(rule.description.clone()),
+                );
+            }
+            return Ok(());
+        }
+        Some(AdditionalItems::Rule(additional)) => Some(additional.as_ref()),
+        Some(AdditionalItems::Allowed(true)) | None => rule.items.as_deref(),
+    };
+
+    if let Some(additional_rule) = additional_rule {
+        for (i, item) in items.iter().enumerate().skip(prefix_items.len()) {
+            let item_path = format!("{}[{}]", path, i);
+            validate_node(ctx, item, additional_rule, &item_path, errors, false)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate a string node against a schema rule
+fn validate_string(
+    value: &Value,
+    rule: &SchemaRule,
+    compiled: &CompiledRule,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) -> ConfigGuardResult<()> {
+    if let Value::String(s) = value {
+        // Check string length constraints
+        if let Some(min_length) = rule.min_length {
+            if s.len() < min_length {
+                errors.push(
+                    ValidationError::new(
+                        path,
+                        "String too short",
+                        format!("At least {} characters", min_length),
+                        format!("{} characters", s.len()),
+                    )
+                    .with_description(rule.description.clone()),
+                );
+            }
+        }
+
+        if let Some(max_length) = rule.max_length {
+            if s.len() > max_length {
+                errors.push(
+                    ValidationError::new(
+                        path,
+                        "String too long",
+                        format!("At most {} characters", max_length),
+                        format!("{} characters", s.len()),
+                    )
+                    .with_description(rule.description.clone()),
+                );
+            }
+        }
+
+        if let Some(format) = &rule.format {
+            validate_format(s, *format, rule, path, errors);
+        }
+
+        // Check pattern constraint (compiled when the schema was compiled)
+        if let Some(pattern) = &rule.pattern {
+            let regex = compiled.pattern.as_ref().ok_or_else(|| {
+                ConfigGuardError::Internal(format!("Pattern '{}' was not compiled", pattern))
+            })?;
+            if !regex.is_match(s) {
+                errors.push(
+                    ValidationError::new(
+                        path,
+                        "String doesn't match pattern",
+                        format!("Pattern: {}", pattern),
+                        s.clone(),
+                    )
+                    .with_description(rule.description.clone()),
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Report a `ref_to` string that names none of the values its path selects
+fn check_reference(
+    ctx: &ValidationContext,
+    name: &str,
+    target: &DocumentPath,
+    rule: &SchemaRule,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    let Some(target_path) = &rule.ref_to else {
+        return;
+    };
+    let names = ctx
+        .ref_targets
+        .borrow_mut()
+        .entry(target_path.clone())
+        .or_insert_with(|| Rc::new(reference_names(target.select(ctx.document))))
+        .clone();
+
+    if !names.iter().any(|n| n == name) {
+        errors.push(
+            unknown_reference(path, name, &names, target_path)
+                .with_description(rule.description.clone()),
+        );
+    }
+}
+
+/// The distinct names among selected values, in document order
+///
+/// Strings, numbers and booleans are names; lists, objects and nulls are skipped.
+fn reference_names<'a>(values: impl IntoIterator<Item = &'a Value>) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for value in values {
+        let name = match value {
+            Value::String(s) => s.clone(),
+            Value::Number(_) | Value::Bool(_) => display_value(value),
+            _ => continue,
+        };
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// The error for a reference that names none of the values selected by `target_path`
+fn unknown_reference(
+    path: &str,
+    name: &str,
+    names: &[String],
+    target_path: &str,
+) -> ValidationError {
+    let message = match did_you_mean(name, names.iter().map(String::as_str)) {
+        Some(suggestion) => format!(
+            "Reference to unknown '{}' (did you mean '{}'?)",
+            name, suggestion
+        ),
+        None => format!("Reference to unknown '{}'", name),
+    };
+    let expected = if names.is_empty() {
+        format!("A value of {} (none are defined)", target_path)
+    } else {
+        format!("One of {}: {}", target_path, names.join(", "))
+    };
+    ValidationError::new(path, message, expected, name)
+}
+
+/// Check a value against `const` and `enum`
+fn validate_allowed_values(
+    value: &Value,
+    rule: &SchemaRule,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    if let Some(allowed) = &rule.const_value {
+        if !values_equal(value, allowed, rule.case_insensitive) {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    "Value does not match constant",
+                    format!("Exactly: {}", display_value(allowed)),
+                    display_value(value),
+                )
+                .with_description(rule.description.clone()),
+            );
+        }
+    }
+
+    if let Some(enum_values) = &rule.enum_values {
+        if !enum_values
+            .iter()
+            .any(|allowed| values_equal(value, allowed, rule.case_insensitive))
+        {
+            let allowed_values = enum_values
+                .iter()
+                .map(display_value)
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            errors.push(
+                ValidationError::new(
+                    path,
+                    "Value not in allowed set",
+                    format!("One of: {}", allowed_values),
+                    display_value(value),
+                )
+                .with_description(rule.description.clone()),
+            );
+        }
+    }
+}
+
+/// Compare two values structurally, as `enum` and `const` do
+///
+/// Numbers are equal when they have the same value, so `1` matches `1.0`.
+/// With `case_insensitive`, strings (also inside lists and objects) are
+/// compared ignoring case; object keys are always compared exactly.
+pub(crate) fn values_equal(a: &Value, b: &Value, case_insensitive: bool) -> bool {
+    match (a, b) {
+        (Value::String(x), Value::String(y)) if case_insensitive => {
+            x.to_lowercase() == y.to_lowercase()
+        }
+        (Value::Number(_), Value::Number(_)) => {
+            match (Numeric::from_value(a), Numeric::from_value(b)) {
+                (Some(x), Some(y)) => x.compare(y) == Some(Ordering::Equal),
+                _ => false,
+            }
+        }
+        (Value::Sequence(x), Value::Sequence(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .zip(y)
+                    .all(|(x, y)| values_equal(x, y, case_insensitive))
+        }
+        (Value::Mapping(x), Value::Mapping(y)) => {
+            x.len() == y.len()
+                && x.iter().all(|(key, x)| {
+                    y.get(key)
+                        .is_some_and(|y| values_equal(x, y, case_insensitive))
+                })
+        }
+        (Value::Tagged(x), Value::Tagged(y)) => {
+            x.tag == y.tag && values_equal(&x.value, &y.value, case_insensitive)
+        }
+        _ => a == b,
+    }
+}
+
+/// Check a string against its `format` (and `uri_schemes` for URIs)
+fn validate_format(
+    s: &str,
+    format: StringFormat,
+    rule: &SchemaRule,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    let expected = format!("{} (e.g. {})", format.name(), format.example());
+
+    if let Err(reason) = format.check(s) {
+        errors.push(
+            ValidationError::new(
+                path,
+                format!("Invalid {}: {}", format.name(), reason),
+                expected,
+                s,
+            )
+            .with_description(rule.description.clone()),
+        );
+        return;
+    }
+
+    if let (Some(schemes), Some(scheme)) = (&rule.uri_schemes, uri_scheme(s)) {
+        if !schemes
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(scheme))
+        {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    format!("URI scheme '{}' is not allowed", scheme),
+                    format!("URI with scheme: {}", schemes.join(", ")),
+                    s,
+                )
+                .with_description(rule.description.clone()),
+            );
+        }
+    }
+}
+
+/// Validate a duration, byte size or percentage against a schema rule
+///
+/// Bounds are compared after normalizing to seconds, bytes or percent, and
+/// errors show both the value as written and its normalized amount.
+fn validate_quantity(
+    value: &Value,
+    quantity: Quantity,
+    rule: &SchemaRule,
+    compiled: &CompiledRule,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    let raw = display_value(value);
+    let amount = match quantity.parse_value(value) {
+        Ok(amount) => amount,
+        Err(reason) => {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    format!("Invalid {}: {}", quantity.name(), reason),
+                    format!(
+                        "A {} such as {}",
+                        quantity.name(),
+                        quantity_example(quantity)
+                    ),
+                    raw,
+                )
+                .with_description(rule.description.clone()),
+            );
+            return;
+        }
+    };
+
+    // Show "30s (30 seconds)" unless the value is already in normalized form
+    let with_amount = |raw: &str, amount: f64| {
+        let normalized = quantity.describe(amount);
+        if normalized == raw {
+            normalized
+        } else {
+            format!("{} ({})", raw, normalized)
+        }
+    };
+
+    if let (Some(min), Some(bound)) = (compiled.min.map(Numeric::as_f64), &rule.min) {
+        if amount < min {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    "Value too small",
+                    format!("At least {}", with_amount(&display_value(bound), min)),
+                    with_amount(&raw, amount),
+                )
+                .with_description(rule.description.clone()),
+            );
+        }
+    }
+
+    if let (Some(max), Some(bound)) = (compiled.max.map(Numeric::as_f64), &rule.max) {
+        if amount > max {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    "Value too large",
+                    format!("At most {}", with_amount(&display_value(bound), max)),
+                    with_amount(&raw, amount),
+                )
+                .with_description(rule.description.clone()),
+            );
+        }
+    }
+}
+
+/// Example values for quantity parse errors
+fn quantity_example(quantity: Quantity) -> &'static str {
+    match quantity {
+        Quantity::Duration => "30s, 500ms or 1h30m",
+        Quantity::ByteSize => "512Mi, 2GB or 1024",
+        Quantity::Percentage => "75%",
+    }
+}
+
+/// Whether a number is a whole multiple of `step` (exactly for integers)
+fn is_multiple_of(number: Numeric, step: Numeric) -> bool {
+    match (number, step) {
+        (Numeric::Int(n), Numeric::Int(step)) => n % step == 0,
+        (number, step) => {
+            let quotient = number.as_f64() / step.as_f64();
+            (quotient - quotient.round()).abs() <= 1e-9 * quotient.abs().max(1.0)
+        }
+    }
+}
+
+/// Number of digits after the decimal point in the shortest form of a float
+fn decimal_places(value: f64) -> usize {
+    value
+        .to_string()
+        .split_once('.')
+        .map_or(0, |(_, fraction)| fraction.len())
+}
+
+/// Validate a numeric node against a schema rule
+fn validate_number(
+    value: &Value,
+    number_type: &SchemaType,
+    rule: &SchemaRule,
+    compiled: &CompiledRule,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+) -> ConfigGuardResult<()> {
+    // Helper function to get a numeric value as f64
+    fn as_f64(value: &Value) -> Option<f64> {
+        match value {
+            Value::Number(n) => n.as_f64(),
+            _ => None,
+        }
+    }
+
+    if let Some(num) = as_f64(value) {
+        // Check for NaN or infinite values
+        if num.is_nan() {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    "Invalid numeric value",
+                    "A valid number",
+                    "NaN (Not a Number)",
+                )
+                .with_description(rule.description.clone()),
+            );
+            return Ok(());
+        }
+
+        if num.is_infinite() {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    "Invalid numeric value",
+                    "A finite number",
+                    if num.is_sign_positive() {
+                        "Positive infinity"
+                    } else {
+                        "Negative infinity"
+                    }
+                    .to_string(),
+                )
+                .with_description(rule.description.clone()),
+            );
+            return Ok(());
+        }
+
+        // Integers are compared exactly, so large u64 values keep their precision
+        let number = Numeric::from_value(value).unwrap_or(Numeric::Float(num));
+
+        // Check the range of fixed-width integer types
+        if let (Some((low, high)), Numeric::Int(n)) = (number_type.integer_range(), number) {
+            if n < low || n > high {
+                errors.push(
+                    ValidationError::new(
+                        path,
+                        format!("Value out of range for {}", number_type),
+                        format!("Between {} and {}", low, high),
+                        n.to_string(),
+                    )
+                    .with_description(rule.description.clone()),
+                );
+                return Ok(());
+            }
+        }
+
+        // Check min constraint (inclusive)
+        if let Some(min_val) = compiled.min {
+            if number.compare(min_val) == Some(Ordering::Less) {
+                errors.push(
+                    ValidationError::new(
+                        path,
+                        "Value too small",
+                        format!("At least {}", min_val),
+                        number.to_string(),
+                    )
+                    .with_description(rule.description.clone()),
+                );
+            }
+        }
+
+        // Check max constraint (inclusive)
+        if let Some(max_val) = compiled.max {
+            if number.compare(max_val) == Some(Ordering::Greater) {
+                errors.push(
+                    ValidationError::new(
+                        path,
+                        "Value too large",
+                        format!("At most {}", max_val),
+                        number.to_string(),
+                    )
+                    .with_description(rule.description.clone()),
+                );
+            }
+        }
+
+        // Check exclusive bounds
+        if let Some(min_val) = compiled.exclusive_min {
+            if number.compare(min_val) != Some(Ordering::Greater) {
+                errors.push(
+                    ValidationError::new(
+                        path,
+                        "Value too small",
+                        format!("Greater than {}", min_val),
+                        number.to_string(),
+                    )
+                    .with_description(rule.description.clone()),
+                );
+            }
+        }
+
+        if let Some(max_val) = compiled.exclusive_max {
+            if number.compare(max_val) != Some(Ordering::Less) {
+                errors.push(
+                    ValidationError::new(
+                        path,
+                        "Value too large",
+                        format!("Less than {}", max_val),
+                        number.to_string(),
+                    )
+                    .with_description(rule.description.clone()),
+                );
+            }
+        }
+
+        if let Some(step) = compiled.multiple_of {
+            if !is_multiple_of(number, step) {
+                errors.push(
+                    ValidationError::new(
+                        path,
+                        "Value is not a multiple of the required step",
+                        format!("A multiple of {}", step),
+                        number.to_string(),
+                    )
+                    .with_description(rule.description.clone()),
+                );
+            }
+        }
+
+        if let (Some(max_places), Numeric::Float(f)) = (rule.max_decimal_places, number) {
+            let places = decimal_places(f);
+            if places > max_places {
+                errors.push(
+                    ValidationError::new(
+                        path,
+                        "Too many decimal places",
+                        format!("At most {} decimal places", max_places),
+                        format!("{} ({} decimal places)", f, places),
+                    )
+                    .with_description(rule.description.clone()),
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check if a value matches the expected type
+pub(crate) fn validate_type(value: &Value, expected_type: &SchemaType) -> bool {
+    match expected_type {
+        SchemaType::String => matches!(value, Value::String(_)),
+        SchemaType::Integer
+        | SchemaType::U8
+        | SchemaType::U16
+        | SchemaType::U32
+        | SchemaType::U64
+        | SchemaType::I8
+        | SchemaType::I16
+        | SchemaType::I32
+        | SchemaType::I64 => {
+            if let Value::Number(n) = value {
+                n.is_i64() || n.is_u64()
+            } else {
+                false
+            }
+        }
+        SchemaType::Float => {
+            if let Value::Number(n) = value {
+                n.is_f64()
+            } else {
+                false
+            }
+        }
+        SchemaType::Boolean => matches!(value, Value::Bool(_)),
+        SchemaType::Object => matches!(value, Value::Mapping(_)),
+        SchemaType::List => matches!(value, Value::Sequence(_)),
+        SchemaType::Null => matches!(value, Value::Null),
+        SchemaType::Any => true,
+        SchemaType::Duration | SchemaType::ByteSize | SchemaType::Percentage => {
+            Quantity::of(expected_type).is_some_and(|quantity| quantity.accepts(value))
+        }
+        SchemaType::Union(types) => types.iter().any(|t| validate_type(value, t)),
+    }
+}
+
+/// Get a human-readable name for a value's type
+pub(crate) fn value_type_name(value: &Value) -> String {
+    match value {
+        Value::String(_) => "string".to_string(),
+        Value::Number(n) => {
+            if n.is_i64() || n.is_u64() {
+                "integer".to_string()
+            } else {
+                "float".to_string()
+            }
+        }
+        Value::Bool(_) => "boolean".to_string(),
+        Value::Mapping(_) => "object".to_string(),
+        Value::Sequence(_) => "list".to_string(),
+        Value::Null => "null".to_string(),
+        _ => "unknown".to_string(),
+    }
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, ConfigFormat};
+    use crate::schema::{Schema, SchemaRule, SchemaType};
+    use std::collections::HashMap;
+
+    // Helper to create a Config from YAML string
+    fn config_from_yaml(yaml: &str) -> Config {
+        let data = serde_yaml::from_str(yaml).unwrap();
+        Config {
+            data,
+            format: ConfigFormat::Yaml,
+            path: None,
+            content: Some(yaml.to_string()),
+            source_map: None,
+        }
+    }
+
+    // Helper to create a Schema from YAML string
+    fn schema_from_yaml(yaml: &str) -> Schema {
+        let rule: SchemaRule = serde_yaml::from_str(yaml).unwrap();
+        Schema::new(rule)
+    }
+
+    #[test]
+    fn test_validate_valid_simple_config() {
+        let schema_str = r#"
+            type: object
+            keys:
+                name:
+                    type: string
+                    required: true
+                age:
+                    type: integer
+                    min: 0
+                    max: 120
+        "#;
+
+        let config_str = r#"
+            name: John Doe
+            age: 30
+        "#;
+
+        let schema = schema_from_yaml(schema_str);
+        let config = config_from_yaml(config_str);
+
+        let result = validate(&config, &schema, false).unwrap();
+        assert!(matches!(result, ValidationResult::Valid));
+    }
+
+    #[test]
+    fn test_validate_missing_required_field() {
+        let schema_str = r#"
+            type: object
+            keys:
+                name:
+                    type: string
+                    required: true
+                age:
+                    type: integer
+                    required: true
+        "#;
+
+        let config_str = r#"
+            name: John Doe
+            # age is missing
+        "#;
+
+        let schema = schema_from_yaml(schema_str);
+        let config = config_from_yaml(config_str);
+
+        let result = validate(&config, &schema, false);
+
+        assert!(result.is_err());
+        match result {
+            Ok(_) => panic!("Validation should have failed due to missing required field"),
+            Err(err) => match err {
+                ConfigGuardError::AllValidationErrors { errors } => {
+                    assert_eq!(errors.len(), 1);
+                    assert_eq!(errors[0].path, ".age");
+                    assert_eq!(errors[0].message, "Required key missing");
+                }
+                _ => panic!("Expected AllValidationErrors, got {:?}", err),
+            },
+        }
+    }
+
+    #[test]
+    fn test_validate_type_mismatch() {
+        let schema_str = r#"
+            type: object
+            keys:
+                name:
+                    type: string
+                age:
+                    type: integer
+        "#;
+
+        let config_str = r#"
+            name: John Doe
+            age: "thirty" # Should be an integer
+        "#;
+
+        let schema = schema_from_yaml(schema_str);
+        let config = config_from_yaml(config_str);
+
+        let result = validate(&config, &schema, false);
+
+        assert!(result.is_err());
+        match result {
+            Ok(_) => panic!("Validation should have failed due to type mismatch"),
+            Err(err) => match err {
+                ConfigGuardError::AllValidationErrors { errors } => {
+                    assert_eq!(errors.len(), 1);
+                    assert_eq!(errors[0].path, ".age");
+                    assert_eq!(errors[0].message, "Type mismatch");
+                }
+                _ => panic!("Expected AllValidationErrors, got {:?}", err),
+            },
+        }
+    }
+
+    #[test]
+    fn test_validate_range_constraints() {
+        let schema_str = r#"
+            type: object
+            keys:
+                count:
+                    type: integer
+                    min: 1
+                    max: 10
+        "#;
+
+        // Test value too small
+        let config_str = "count: 0"; // Below minimum
+        let schema = schema_from_yaml(schema_str);
+        let config = config_from_yaml(config_str);
+
+        let result = validate(&config, &schema, false);
+        assert!(result.is_err());
+        match result {
+            Ok(_) => panic!("Validation should have failed due to value below minimum"),
+            Err(err) => match err {
+                ConfigGuardError::AllValidationErrors { errors } => {
+                    assert_eq!(errors.len(), 1);
+                    assert_eq!(errors[0].message, "Value too small");
+                }
+                _ => panic!("Expected AllValidationErrors, got {:?}", err),
+            },
+        }
+
+        // Test value too large
+        let config_str = "count: 11"; // Above maximum
+        let config = config_from_yaml(config_str);
+
+        let result = validate(&config, &schema, false);
+        assert!(result.is_err());
+        match result {
+            Ok(_) => panic!("Validation should have failed due to value above maximum"),
+            Err(err) => match err {
+                ConfigGuardError::AllValidationErrors { errors } => {
+                    assert_eq!(errors.len(), 1);
+                    assert_eq!(errors[0].message, "Value too large");
+                }
+                _ => panic!("Expected AllValidationErrors, got {:?}", err),
+            },
+        }
+    }
+
+    #[test]
+    fn test_validate_string_pattern() {
+        let schema_str = r#"
+            type: object
+            keys:
+                code:
+                    type: string
+                    pattern: "^[A-Z]{3}-\\d{4}$"
+        "#;
+
+        // Valid pattern
+        let config_str = "code: ABC-1234";
+        let schema = schema_from_yaml(schema_str);
+        let config = config_from_yaml(config_str);
+
+        let result = validate(&config, &schema, false);
+        assert!(result.is_ok());
+        assert!(matches!(result.unwrap(), ValidationResult::Valid));
+
+        // Invalid pattern
+        let config_str = "code: abc-123"; // Doesn't match pattern
+        let config = config_from_yaml(config_str);
+
+        let result = validate(&config, &schema, false);
+        assert!(result.is_err());
+        match result {
+            Ok(_) => panic!("Validation should have failed due to pattern mismatch"),
+            Err(err) => match err {
+                ConfigGuardError::AllValidationErrors { errors } => {
+                    assert_eq!(errors.len(), 1);
+                    assert_eq!(errors[0].message, "String doesn't match pattern");
+                }
+                _ => panic!("Expected AllValidationErrors, got {:?}", err),
+            },
+        }
+    }
+
+    #[test]
+    fn test_validate_enum_values() {
+        let schema_str = r#"
+            type: object
+            keys:
+                color:
+                    type: string
+                    enum: [red, green, blue]
+        "#;
+
+        // Valid enum value
+        let config_str = "color: green";
+        let schema = schema_from_yaml(schema_str);
+        let config = config_from_yaml(config_str);
+
+        let result = validate(&config, &schema, false);
+        assert!(result.is_ok());
+        assert!(matches!(result.unwrap(), ValidationResult::Valid));
+
+        // Invalid enum value
+        let config_str = "color: yellow"; // Not in enum
+        let config = config_from_yaml(config_str);
+
+        let result = validate(&config, &schema, false);
+        assert!(result.is_err());
+        match result {
+            Ok(_) => panic!("Validation should have failed due to invalid enum value"),
+            Err(err) => match err {
+                ConfigGuardError::AllValidationErrors { errors } => {
+                    assert_eq!(errors.len(), 1);
+                    assert_eq!(errors[0].message, "Value not in allowed set");
+                }
+                _ => panic!("Expected AllValidationErrors, got {:?}", err),
+            },
+        }
+    }
+
+    #[test]
+    fn test_validate_strict_mode() {
+        let schema_str = r#"
+            type: object
+            keys:
+                name:
+                    type: string
+                    required: true
+        "#;
+
+        let config_str = r#"
+            name: Test
+            extra_field: This shouldn't be here
+        "#;
+
+        let schema = schema_from_yaml(schema_str);
+        let config = config_from_yaml(config_str);
+
+        // With strict mode off (default), unknown keys are allowed
+        let result = validate(&config, &schema, false);
+        assert!(result.is_ok());
+        assert!(matches!(result.unwrap(), ValidationResult::Valid));
+
+        // With strict mode on, unknown keys should cause a validation error
+        let result = validate(&config, &schema, true);
+        assert!(result.is_err());
+        match result {
+            Ok(_) => panic!("Validation should have failed in strict mode due to unknown keys"),
+            Err(err) => match err {
+                ConfigGuardError::AllValidationErrors { errors } => {
+                    assert_eq!(errors.len(), 1);
+                    assert_eq!(errors[0].path, ".extra_field");
+                    assert_eq!(errors[0].message, "Unknown key");
+                }
+                _ => panic!("Expected AllValidationErrors, got {:?}", err),
+            },
+        }
+    }
+
+    #[test]
+    fn test_validate_nested_structures() {
+        // Create a schema with nested structure requirements
+        let mut schema_keys = HashMap::new();
+
+        // Add the metadata object with required name field
+        let mut metadata_keys = HashMap::new();
+        metadata_keys.insert(
+            "name".to_string(),
+            SchemaRule {
+                data_type: SchemaType::String,
+                description: Some("The name of the resource".to_string()),
+                required: true,
+                allow_unknown_keys: true,
+                pattern: None,
+                enum_values: None,
+                min: None,
+                max: None,
+                min_length: None,
+                max_length: None,
+                items: None,
+                keys: None,
+                ..Default::default()
+            },
+        );
+
+        schema_keys.insert(
+            "metadata".to_string(),
+            SchemaRule {
+                data_type: SchemaType::Object,
+                description: Some("Metadata for the resource".to_string()),
+                required: true,
+                allow_unknown_keys: true,
+                pattern: None,
+                enum_values: None,
+                min: None,
+                max: None,
+                min_length: None,
+                max_length: None,
+                items: None,
+                keys: Some(metadata_keys),
+                ..Default::default()
+            },
+        );
+
+        // Add a containers list with minimum length requirement
+        schema_keys.insert(
+            "containers".to_string(),
+            SchemaRule {
+                data_type: SchemaType::List,
+                description: Some("List of containers".to_string()),
+                required: true,
+                allow_unknown_keys: true,
+                pattern: None,
+                enum_values: None,
+                min: None,
+                max: None,
+                min_length: Some(1),
+                max_length: None,
+                items: Some(Box::new(SchemaRule {
+                    data_type: SchemaType::Object,
+                    description: None,
+                    required: false,
+                    allow_unknown_keys: true,
+                    pattern: None,
+                    enum_values: None,
+                    min: None,
+                    max: None,
+                    min_length: None,
+                    max_length: None,
+                    items: None,
+                    keys: None,
+                    ..Default::default()
+                })),
+                keys: None,
+                ..Default::default()
+            },
+        );
+
+        let schema = Schema::new(SchemaRule {
+            data_type: SchemaType::Object,
+            description: None,
+            required: false,
+            allow_unknown_keys: true,
+            pattern: None,
+            enum_values: None,
+            min: None,
+            max: None,
+            min_length: None,
+            max_length: None,
+            items: None,
+            keys: Some(schema_keys),
+            ..Default::default()
+        });
+
+        // Valid nested config
+        let valid_nested_config = r#"
+            metadata:
+              name: test-app
+            containers:
+              - name: web
+                image: nginx
+        "#;
+
+        let config = Config {
+            data: serde_yaml::from_str(valid_nested_config).unwrap(),
+            format: ConfigFormat::Yaml,
+            path: None,
+            content: Some(valid_nested_config.to_string()),
+            source_map: None,
+        };
+
+        let result = validate(&config, &schema, false);
+        assert!(result.is_ok());
+        match result.unwrap() {
+            ValidationResult::Valid => {}
+            ValidationResult::Invalid(_) => panic!("Expected valid result"),
+        }
+
+        // Invalid nested config - missing required field
+        let invalid_nested_config = r#"
+            metadata: {}
+            containers:
+              - name: web
+                image: nginx
+        "#;
+
+        let config = Config {
+            data: serde_yaml::from_str(invalid_nested_config).unwrap(),
+            format: ConfigFormat::Yaml,
+            path: None,
+            content: Some(invalid_nested_config.to_string()),
+            source_map: None,
+        };
+
+        let result = validate(&config, &schema, false);
+        assert!(result.is_err());
+        match result {
+            Ok(_) => panic!("Expected validation error"),
+            Err(err) => match err {
+                ConfigGuardError::AllValidationErrors { errors } => {
+                    // We're getting two errors: one for the missing key and one for the empty object
+                    // Find the error for the missing required field
+                    let missing_key_error =
+                        errors.iter().find(|e| e.message == "Required key missing");
+                    assert!(missing_key_error.is_some());
+                    let error = missing_key_error.unwrap();
+                    assert_eq!(error.path, ".metadata.name");
+                    assert_eq!(error.message, "Required key missing");
+                }
+                _ => panic!("Expected AllValidationErrors, got {:?}", err),
+            },
+        }
+
+        // Invalid nested config - empty list
+        let empty_list_config = r#"
+            metadata:
+              name: test-app
+            containers: []
+        "#;
+
+        let config = Config {
+            data: serde_yaml::from_str(empty_list_config).unwrap(),
+            format: ConfigFormat::Yaml,
+            path: None,
+            content: Some(empty_list_config.to_string()),
+            source_map: None,
+        };
+
+        let result = validate(&config, &schema, false);
+        assert!(result.is_err());
+        match result {
+            Ok(_) => panic!("Expected validation error"),
+            Err(err) => match err {
+                ConfigGuardError::AllValidationErrors { errors } => {
+                    assert_eq!(errors.len(), 1);
+                    assert_eq!(errors[0].path, ".containers");
+                    assert_eq!(errors[0].message, "List too short");
+                }
+                _ => panic!("Expected AllValidationErrors, got {:?}", err),
+            },
+        }
+    }
+
+    #[test]
+    fn test_validator_reports_errors_without_failing() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+                name:
+                    type: string
+                    required: true
+                port:
+                    type: integer
+                    max: 65535
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+
+        let report = validator
+            .validate_str("name: web\nport: 8080", ConfigFormat::Yaml)
+            .unwrap();
+        assert!(report.is_valid());
+        assert!(matches!(report.into_result(), ValidationResult::Valid));
+
+        let report = validator
+            .validate_str("port: 70000", ConfigFormat::Yaml)
+            .unwrap();
+        assert!(!report.is_valid());
+        assert_eq!(report.errors.len(), 2);
+        assert!(report.errors.iter().any(|e| e.path == ".name"));
+        assert!(report.errors.iter().any(|e| e.message == "Value too large"));
+    }
+
+    #[test]
+    fn test_validator_strict_mode() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+                name:
+                    type: string
+        "#,
+        );
+        let config = config_from_yaml("name: web\nextra: true");
+
+        let report = Validator::new(schema.clone().compile().unwrap())
+            .validate(&config)
+            .unwrap();
+        assert!(report.is_valid());
+
+        let validator = Validator::new(schema.compile().unwrap()).strict(true);
+        assert!(validator.is_strict());
+        let report = validator.validate(&config).unwrap();
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].message, "Unknown key");
+    }
+
+    #[test]
+    fn test_validator_parse_errors_are_failures() {
+        let validator = Validator::new(schema_from_yaml("type: object").compile().unwrap());
+        let result = validator.validate_str("{name: test}", ConfigFormat::Json);
+        assert!(matches!(result, Err(ConfigGuardError::ParseJson(_))));
+    }
+
+    #[test]
+    fn test_validator_shared_across_threads() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+                code:
+                    type: string
+                    pattern: "^[A-Z]{3}-\\d{4}$"
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let validator = validator.clone();
+                std::thread::spawn(move || {
+                    let content = if i % 2 == 0 {
+                        "code: ABC-1234"
+                    } else {
+                        "code: abc"
+                    };
+                    validator
+                        .validate_str(content, ConfigFormat::Yaml)
+                        .unwrap()
+                        .is_valid()
+                })
+            })
+            .collect();
+
+        let results: Vec<bool> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(results, vec![true, false, true, false]);
+    }
+
+    #[test]
+    fn test_validate_toml_config_with_line_numbers() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+                title:
+                    type: string
+                released:
+                    type: string
+                server:
+                    type: object
+                    keys:
+                        port:
+                            type: integer
+                            max: 65535
+                        ratio:
+                            type: float
+                backup:
+                    type: object
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+
+        // `backup.port` shares its name with the invalid `server.port`
+        let content = "title = \"app\"\nreleased = 1979-05-27\n\n[server]\nport = 70000\nratio = 1\n\n[backup]\nport = 1\n";
+        let report = validator.validate_str(content, ConfigFormat::Toml).unwrap();
+
+        assert_eq!(report.errors.len(), 2);
+        let port = report
+            .errors
+            .iter()
+            .find(|e| e.path == ".server.port")
+            .unwrap();
+        assert_eq!(port.message, "Value too large");
+        assert_eq!(port.line, Some(5));
+        let ratio = report
+            .errors
+            .iter()
+            .find(|e| e.path == ".server.ratio")
+            .unwrap();
+        assert_eq!(ratio.message, "Type mismatch");
+        assert_eq!(ratio.line, Some(6));
+        assert_eq!(ratio.location().unwrap(), "line 6, column 1");
+    }
+
+    #[test]
+    fn test_validate_error_spans() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+                name: { type: string }
+                containers:
+                    type: list
+                    items:
+                        type: object
+                        keys:
+                            name: { type: string, min_length: 3 }
+                            image: { type: string, required: true }
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+        let span = |error: &ValidationError| {
+            error.span.map(|span| {
+                (
+                    span.start.line,
+                    span.start.column,
+                    span.end.line,
+                    span.end.column,
+                )
+            })
+        };
+
+        // Both containers have a `name`; each error points at its own
+        let yaml = "\
+name: web
+containers:
+  - name: app
+    image: nginx
+  - name: db
+";
+        let report = validator.validate_str(yaml, ConfigFormat::Yaml).unwrap();
+        let errors: Vec<_> = report
+            .errors
+            .iter()
+            .map(|e| (e.path.as_str(), e.line, span(e)))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                // A missing key points at the object that lacks it
+                (".containers[1].image", Some(5), Some((5, 5, 5, 13))),
+                (".containers[1].name", Some(5), Some((5, 5, 5, 13))),
+            ]
+        );
+
+        let json =
+            "{\n  \"name\": 1,\n  \"containers\": [{ \"name\": \"app\", \"image\": \"a\" }, 5]\n}";
+        let report = validator.validate_str(json, ConfigFormat::Json).unwrap();
+        let errors: Vec<_> = report
+            .errors
+            .iter()
+            .map(|e| (e.path.as_str(), span(e)))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (".name", Some((2, 3, 2, 12))),
+                (".containers[1]", Some((3, 51, 3, 52))),
+            ]
+        );
+        assert_eq!(report.errors[1].location().unwrap(), "line 3, column 51");
+    }
+
+    #[test]
+    fn test_validate_escaped_key_paths() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+                labels:
+                    type: object
+                    values: { type: string }
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+
+        // A key holding dots is not confused with the nested keys it reads like
+        let yaml = "\
+labels:
+  app.kubernetes.io/name: 1
+  app:
+    kubernetes.io: x
+";
+        let report = validator.validate_str(yaml, ConfigFormat::Yaml).unwrap();
+        let errors: Vec<_> = report
+            .errors
+            .iter()
+            .map(|e| (e.path.as_str(), e.line))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (".labels['app.kubernetes.io/name']", Some(2)),
+                (".labels.app", Some(3)),
+            ]
+        );
+    }
+
     #[test]
-    fn test_validate_type_mismatch() {
-        let schema_str = r#"
+    fn test_validate_references() {
+        let schema = schema_from_yaml(
+            r##"
+            type: object
+            definitions:
+                port:
+                    type: integer
+                    min: 1
+                    max: 65535
+                node:
+                    type: object
+                    keys:
+                        name: { type: string, required: true }
+                        children:
+                            type: list
+                            items: { $ref: "#/definitions/node" }
+            keys:
+                port: { $ref: "#/definitions/port", required: true }
+                tree: { $ref: "#/definitions/node" }
+        "##,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+
+        let valid = r#"
+            port: 8080
+            tree:
+                name: root
+                children:
+                    - name: a
+                      children:
+                          - name: a1
+        "#;
+        let report = validator.validate_str(valid, ConfigFormat::Yaml).unwrap();
+        assert!(report.is_valid());
+
+        let invalid = r#"
+            port: 0
+            tree:
+                name: root
+                children:
+                    - children:
+                          - name: 5
+        "#;
+        let report = validator.validate_str(invalid, ConfigFormat::Yaml).unwrap();
+        let paths: Vec<&str> = report.errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(report.errors.len(), 3, "{:?}", report.errors);
+        assert!(paths.contains(&".port"));
+        assert!(paths.contains(&".tree.children[0].name"));
+        assert!(paths.contains(&".tree.children[0].children[0].name"));
+    }
+
+    #[test]
+    fn test_validate_combinators() {
+        let schema = schema_from_yaml(
+            r#"
             type: object
             keys:
+                listen:
+                    type: any
+                    any_of:
+                        - { type: integer, min: 1, max: 65535, description: port }
+                        - { type: string, pattern: "^[a-z0-9.]+:[0-9]+$", description: address }
+                storage:
+                    type: string
+                    one_of:
+                        - { type: string, pattern: "^/", description: local }
+                        - { type: string, pattern: "^[a-z]+://", description: remote }
+                        - { type: string, pattern: "^/mnt/", description: mount }
                 name:
                     type: string
-                age:
-                    type: integer
+                    all_of:
+                        - { type: string, min_length: 3 }
+                        - { type: string, pattern: "^[a-z]+$" }
+                    not: { type: string, enum: [admin, root] }
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+
+        let valid = r#"
+            listen: "localhost:8080"
+            storage: /data
+            name: web
         "#;
+        let report = validator.validate_str(valid, ConfigFormat::Yaml).unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
 
-        let config_str = r#"
-            name: John Doe
-            age: "thirty" # Should be an integer
+        // The closest branch (the integer one) explains why the value failed
+        let report = validator
+            .validate_str("listen: 70000", ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(report.errors.len(), 2, "{:?}", report.errors);
+        assert_eq!(
+            report.errors[0].message,
+            "Value does not match any of the allowed alternatives"
+        );
+        assert_eq!(report.errors[0].expected, "One of: #1 (port), #2 (address)");
+        assert_eq!(
+            report.errors[0].actual,
+            "integer (closest match: #1 (port))"
+        );
+        assert_eq!(report.errors[1].message, "Value too large");
+
+        let report = validator
+            .validate_str("storage: /mnt/data", ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert_eq!(
+            report.errors[0].message,
+            "Value matches more than one alternative"
+        );
+        assert_eq!(report.errors[0].actual, "Matched #1 (local), #3 (mount)");
+
+        let report = validator
+            .validate_str("name: Ab", ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(report.errors.len(), 2, "{:?}", report.errors);
+
+        let report = validator
+            .validate_str("name: admin", ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert_eq!(report.errors[0].message, "Value matches a disallowed rule");
+        assert_eq!(report.errors[0].actual, "admin");
+    }
+
+    #[test]
+    fn test_validate_discriminator() {
+        let schema = schema_from_yaml(
+            r##"
+            type: object
+            definitions:
+                kafka:
+                    type: object
+                    keys:
+                        brokers: { type: list, required: true, items: { type: string } }
+                        topic: { type: string, required: true }
+            keys:
+                sinks:
+                    type: list
+                    items:
+                        type: object
+                        allow_unknown_keys: false
+                        keys:
+                            name: { type: string, required: true }
+                        discriminator:
+                            field: type
+                            mapping:
+                                s3:
+                                    type: object
+                                    keys:
+                                        bucket: { type: string, required: true }
+                                kafka: { $ref: "#/definitions/kafka" }
+        "##,
+        );
+        let validator = Validator::new(schema.compile().unwrap()).strict(true);
+
+        let valid = r#"
+            sinks:
+                - { name: archive, type: s3, bucket: logs }
+                - { name: stream, type: kafka, brokers: [k1], topic: events }
         "#;
+        let report = validator.validate_str(valid, ConfigFormat::Yaml).unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
 
-        let schema = schema_from_yaml(schema_str);
-        let config = config_from_yaml(config_str);
+        // Only the keys of the selected variant are allowed
+        let invalid = r#"
+            sinks:
+                - { name: archive, type: s3, topic: events }
+        "#;
+        let report = validator.validate_str(invalid, ConfigFormat::Yaml).unwrap();
+        let mut found: Vec<(&str, &str)> = report
+            .errors
+            .iter()
+            .map(|e| (e.path.as_str(), e.message.as_str()))
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                (".sinks[0].bucket", "Required key missing"),
+                (".sinks[0].topic", "Unknown key"),
+            ]
+        );
 
-        let result = validate(&config, &schema, false);
+        let report = validator
+            .validate_str("sinks: [{ name: a, type: s4 }]", ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert_eq!(report.errors[0].path, ".sinks[0].type");
+        assert_eq!(
+            report.errors[0].message,
+            "Unknown discriminator value 's4' (did you mean 's3'?)"
+        );
+        assert_eq!(report.errors[0].expected, "One of: kafka, s3");
 
-        assert!(result.is_err());
-        match result {
-            Ok(_) => panic!("Validation should have failed due to type mismatch"),
-            Err(err) => match err {
-                ConfigGuardError::AllValidationErrors { errors } => {
-                    assert_eq!(errors.len(), 1);
-                    assert_eq!(errors[0].path, ".age");
-                    assert_eq!(errors[0].message, "Type mismatch");
-                }
-                _ => panic!("Expected AllValidationErrors, got {:?}", err),
-            },
-        }
+        let report = validator
+            .validate_str("sinks: [{ name: a, type: ftp }]", ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(
+            report.errors[0].message,
+            "Unknown discriminator value 'ftp'"
+        );
+
+        let report = validator
+            .validate_str("sinks: [{ name: a }]", ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert_eq!(report.errors[0].message, "Discriminator field missing");
     }
 
     #[test]
-    fn test_validate_range_constraints() {
-        let schema_str = r#"
+    fn test_did_you_mean() {
+        let candidates = ["kafka", "s3", "postgres"];
+        assert_eq!(did_you_mean("kafak", candidates), Some("kafka"));
+        assert_eq!(did_you_mean("postgre", candidates), Some("postgres"));
+        assert_eq!(did_you_mean("mysql", candidates), None);
+    }
+
+    #[test]
+    fn test_validate_required_if_and_forbidden_if() {
+        let schema = schema_from_yaml(
+            r#"
             type: object
             keys:
-                count:
-                    type: integer
-                    min: 1
-                    max: 10
+                mode: { type: string, enum: [standalone, cluster] }
+                peers: { type: list, forbidden_if: { mode: standalone } }
+                tls:
+                    type: object
+                    keys:
+                        enabled: { type: boolean }
+                        cert_file: { type: string, required_if: { enabled: true } }
+                        key_file: { type: string, required_if: { enabled: true } }
+                ca_file: { type: string, required_if: { tls.enabled: true } }
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+
+        let valid = r#"
+            mode: cluster
+            peers: [a, b]
+            tls: { enabled: false }
         "#;
+        let report = validator.validate_str(valid, ConfigFormat::Yaml).unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
 
-        // Test value too small
-        let config_str = "count: 0"; // Below minimum
-        let schema = schema_from_yaml(schema_str);
-        let config = config_from_yaml(config_str);
+        let invalid = r#"
+            mode: standalone
+            peers: [a]
+            tls: { enabled: true, cert_file: cert.pem }
+        "#;
+        let report = validator.validate_str(invalid, ConfigFormat::Yaml).unwrap();
+        let mut found: Vec<(&str, &str)> = report
+            .errors
+            .iter()
+            .map(|e| (e.path.as_str(), e.message.as_str()))
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    ".ca_file",
+                    "Required key missing (when .tls.enabled = true)"
+                ),
+                (".peers", "Key not allowed (when .mode = standalone)"),
+                (
+                    ".tls.key_file",
+                    "Required key missing (when .tls.enabled = true)"
+                ),
+            ]
+        );
+    }
 
-        let result = validate(&config, &schema, false);
-        assert!(result.is_err());
-        match result {
-            Ok(_) => panic!("Validation should have failed due to value below minimum"),
-            Err(err) => match err {
-                ConfigGuardError::AllValidationErrors { errors } => {
-                    assert_eq!(errors.len(), 1);
-                    assert_eq!(errors[0].message, "Value too small");
-                }
-                _ => panic!("Expected AllValidationErrors, got {:?}", err),
-            },
-        }
+    #[test]
+    fn test_validate_if_then_else() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+                mode: { type: string }
+                replicas: { type: integer }
+            if:
+                type: object
+                keys:
+                    mode: { type: string, enum: [cluster] }
+            then:
+                type: object
+                keys:
+                    replicas: { type: integer, required: true, min: 3 }
+            else:
+                type: object
+                keys:
+                    replicas: { type: integer, max: 1 }
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
 
-        // Test value too large
-        let config_str = "count: 11"; // Above maximum
-        let config = config_from_yaml(config_str);
+        let report = validator
+            .validate_str("{ mode: cluster, replicas: 3 }", ConfigFormat::Yaml)
+            .unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
 
-        let result = validate(&config, &schema, false);
-        assert!(result.is_err());
-        match result {
-            Ok(_) => panic!("Validation should have failed due to value above maximum"),
-            Err(err) => match err {
-                ConfigGuardError::AllValidationErrors { errors } => {
-                    assert_eq!(errors.len(), 1);
-                    assert_eq!(errors[0].message, "Value too large");
-                }
-                _ => panic!("Expected AllValidationErrors, got {:?}", err),
-            },
-        }
+        let report = validator
+            .validate_str("{ mode: cluster, replicas: 1 }", ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert_eq!(report.errors[0].path, ".replicas");
+        assert_eq!(
+            report.errors[0].message,
+            "Value too small (when .mode = cluster)"
+        );
+
+        let report = validator
+            .validate_str("{ mode: cluster }", ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(
+            report.errors[0].message,
+            "Required key missing (when .mode = cluster)"
+        );
+
+        let report = validator
+            .validate_str("{ mode: standalone, replicas: 2 }", ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert_eq!(
+            report.errors[0].message,
+            "Value too large (when not .mode = cluster)"
+        );
+
+        // Keys the `if` does not name are ignored by it, even in strict mode
+        let report = validator
+            .strict(true)
+            .validate_str("{ mode: cluster, replicas: 1 }", ConfigFormat::Yaml)
+            .unwrap();
+        assert!(
+            report
+                .errors
+                .iter()
+                .any(|e| e.message == "Value too small (when .mode = cluster)"),
+            "{:?}",
+            report.errors
+        );
     }
 
     #[test]
-    fn test_validate_string_pattern() {
-        let schema_str = r#"
+    fn test_validate_key_groups() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            exclusive_keys: [[password, password_file]]
+            dependent_keys: [[cert, key]]
+            one_required_of: [[endpoints, discovery], [password, password_file]]
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+
+        let valid = r#"
+            password_file: /run/secrets/db
+            cert: tls.crt
+            key: tls.key
+            discovery: dns
+        "#;
+        let report = validator.validate_str(valid, ConfigFormat::Yaml).unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
+
+        let invalid = r#"
+            password: hunter2
+            password_file: /run/secrets/db
+            cert: tls.crt
+        "#;
+        let report = validator.validate_str(invalid, ConfigFormat::Yaml).unwrap();
+        assert_eq!(report.errors.len(), 3, "{:?}", report.errors);
+        assert!(report.errors.iter().all(|e| e.path.is_empty()));
+        assert_eq!(
+            report.errors[0].expected,
+            "At most one of: password, password_file"
+        );
+        assert_eq!(report.errors[0].actual, "Present: password, password_file");
+        assert_eq!(report.errors[1].expected, "All or none of: cert, key");
+        assert_eq!(report.errors[1].actual, "Present: cert; missing: key");
+        assert_eq!(
+            report.errors[2].expected,
+            "At least one of: endpoints, discovery"
+        );
+    }
+
+    #[test]
+    fn test_validate_dynamic_maps() {
+        let schema = schema_from_yaml(
+            r#"
             type: object
             keys:
-                code:
-                    type: string
-                    pattern: "^[A-Z]{3}-\\d{4}$"
+                labels:
+                    type: object
+                    key_pattern: "^[a-z0-9./-]+$"
+                    max_keys: 3
+                    values: { type: string, max_length: 63 }
+                plugins:
+                    type: object
+                    min_keys: 1
+                    keys:
+                        core: { type: boolean }
+                    pattern_keys:
+                        "^ext-": { type: object }
+                    values: { type: string }
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+
+        let valid = r#"
+            labels: { app: web, team.io/owner: infra }
+            plugins: { core: true, ext-auth: { enabled: true }, legacy: "off" }
         "#;
+        let report = validator.validate_str(valid, ConfigFormat::Yaml).unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
 
-        // Valid pattern
-        let config_str = "code: ABC-1234";
-        let schema = schema_from_yaml(schema_str);
-        let config = config_from_yaml(config_str);
+        let invalid = r#"
+            labels: { App: web, tier: 3, a: x, b: y }
+            plugins: { core: yes-please, ext-auth: on }
+        "#;
+        let report = validator.validate_str(invalid, ConfigFormat::Yaml).unwrap();
+        let mut found: Vec<(&str, &str)> = report
+            .errors
+            .iter()
+            .map(|e| (e.path.as_str(), e.message.as_str()))
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                (".labels", "Object has too many keys"),
+                (".labels.App", "Key name doesn't match pattern"),
+                (".labels.tier", "Type mismatch"),
+                (".plugins.core", "Type mismatch"),
+                (".plugins.ext-auth", "Type mismatch"),
+            ]
+        );
 
-        let result = validate(&config, &schema, false);
-        assert!(result.is_ok());
-        assert!(matches!(result.unwrap(), ValidationResult::Valid));
+        let report = validator
+            .validate_str("plugins: {}", ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert_eq!(report.errors[0].message, "Object has too few keys");
+    }
 
-        // Invalid pattern
-        let config_str = "code: abc-123"; // Doesn't match pattern
-        let config = config_from_yaml(config_str);
+    #[test]
+    fn test_validate_tuple_lists() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+                endpoint:
+                    type: list
+                    prefix_items:
+                        - { type: string, description: host }
+                        - { type: integer, min: 1, max: 65535, description: port }
+                    additional_items: false
+                range:
+                    type: list
+                    min_length: 1
+                    prefix_items:
+                        - { type: integer, description: min }
+                        - { type: integer, description: max }
+                    additional_items: { type: string }
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
 
-        let result = validate(&config, &schema, false);
-        assert!(result.is_err());
-        match result {
-            Ok(_) => panic!("Validation should have failed due to pattern mismatch"),
-            Err(err) => match err {
-                ConfigGuardError::AllValidationErrors { errors } => {
-                    assert_eq!(errors.len(), 1);
-                    assert_eq!(errors[0].message, "String doesn't match pattern");
-                }
-                _ => panic!("Expected AllValidationErrors, got {:?}", err),
-            },
-        }
+        let valid = r#"
+            endpoint: [localhost, 8080]
+            range: [1, 10, inclusive, closed]
+        "#;
+        let report = validator.validate_str(valid, ConfigFormat::Yaml).unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
+
+        // `min_length` allows trailing prefix items to be left out
+        let report = validator
+            .validate_str("range: [1]", ConfigFormat::Yaml)
+            .unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
+
+        let report = validator
+            .validate_str("endpoint: [localhost]", ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert_eq!(report.errors[0].path, ".endpoint[1]");
+        assert_eq!(report.errors[0].message, "Missing list item");
+        assert_eq!(report.errors[0].expected, "2 items (host, port)");
+        assert_eq!(report.errors[0].actual, "1 items");
+
+        let report = validator
+            .validate_str("endpoint: [localhost, 80, extra]", ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert_eq!(report.errors[0].path, ".endpoint[2]");
+        assert_eq!(report.errors[0].message, "Unexpected list item");
+        assert_eq!(report.errors[0].actual, "3 items");
+
+        let report = validator
+            .validate_str(
+                "{ endpoint: [8080, localhost], range: [1, 2, 3] }",
+                ConfigFormat::Yaml,
+            )
+            .unwrap();
+        let paths: Vec<&str> = report.errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec![".endpoint[0]", ".endpoint[1]", ".range[2]"]);
+    }
+
+    #[test]
+    fn test_validate_unique_items() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+                tags: { type: list, unique_items: true }
+                containers:
+                    type: list
+                    unique_by: name
+                    items:
+                        type: object
+                        keys:
+                            name: { type: string }
+                ports:
+                    type: list
+                    unique_by: [containerPort, protocol]
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+
+        let valid = r#"
+            tags: [a, b]
+            containers: [{ name: web }, { name: sidecar }]
+            ports:
+                - { containerPort: 53, protocol: TCP }
+                - { containerPort: 53, protocol: UDP }
+        "#;
+        let report = validator.validate_str(valid, ConfigFormat::Yaml).unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
+
+        let invalid = r#"
+            tags: [a, b, a]
+            containers: [{ name: web }, { name: sidecar }, { name: web }]
+            ports:
+                - { containerPort: 53, protocol: TCP }
+                - { containerPort: 53, protocol: TCP }
+        "#;
+        let report = validator.validate_str(invalid, ConfigFormat::Yaml).unwrap();
+        assert_eq!(report.errors.len(), 3, "{:?}", report.errors);
+
+        assert_eq!(report.errors[0].path, ".tags[2]");
+        assert_eq!(report.errors[0].message, "Duplicate list item");
+        assert_eq!(report.errors[0].actual, "a (same as .tags[0])");
+
+        assert_eq!(report.errors[1].path, ".containers[2]");
+        assert_eq!(report.errors[1].message, "Duplicate value for name");
+        assert_eq!(report.errors[1].actual, "web (same as .containers[0])");
+
+        assert_eq!(report.errors[2].path, ".ports[1]");
+        assert_eq!(
+            report.errors[2].message,
+            "Duplicate value for containerPort, protocol"
+        );
+        assert_eq!(report.errors[2].actual, "53, TCP (same as .ports[0])");
+    }
+
+    #[test]
+    fn test_validate_contains() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+                listeners:
+                    type: list
+                    contains:
+                        type: object
+                        description: default listener
+                        keys:
+                            default: { type: boolean, enum: [true], required: true }
+                    max_contains: 1
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+
+        let report = validator
+            .validate_str(
+                "listeners: [{ port: 80 }, { port: 443, default: true }]",
+                ConfigFormat::Yaml,
+            )
+            .unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
+
+        let report = validator
+            .validate_str("listeners: [{ port: 80 }]", ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert_eq!(
+            report.errors[0].expected,
+            "At least 1 items matching default listener"
+        );
+        assert_eq!(report.errors[0].actual, "No matching items");
+
+        let report = validator
+            .validate_str(
+                "listeners: [{ default: true }, { port: 1 }, { default: true }]",
+                ConfigFormat::Yaml,
+            )
+            .unwrap();
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert_eq!(
+            report.errors[0].message,
+            "List contains too many matching items"
+        );
+        assert_eq!(report.errors[0].actual, "2 matching items (at [0], [2])");
+    }
+
+    #[test]
+    fn test_validate_string_formats() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+                listen: { type: string, format: ip }
+                network: { type: string, format: cidr }
+                endpoint: { type: string, format: uri, uri_schemes: [https] }
+                version: { type: string, format: semver }
+                name: { type: string, format: dns-1123-label }
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+
+        let valid = r#"
+            listen: "::1"
+            network: 10.0.0.0/8
+            endpoint: https://api.example.com/v1
+            version: 1.2.3
+            name: my-app
+        "#;
+        let report = validator.validate_str(valid, ConfigFormat::Yaml).unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
+
+        let invalid = r#"
+            listen: 10.0.0.256
+            network: 10.0.0.0/40
+            endpoint: http://api.example.com/v1
+            version: v1.2.3
+            name: My_App
+        "#;
+        let report = validator.validate_str(invalid, ConfigFormat::Yaml).unwrap();
+        let messages: Vec<&str> = report.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Invalid IP address: octet 256 is out of range 0-255",
+                "Invalid CIDR network: prefix length 40 is out of range 0-32 for IPv4",
+                "URI scheme 'http' is not allowed",
+                "Invalid semantic version: the 'v' prefix is not part of a semantic version",
+                "Invalid DNS-1123 label: invalid character 'M' (only lowercase letters, digits and '-' are allowed)",
+            ]
+        );
+        assert_eq!(report.errors[2].expected, "URI with scheme: https");
+        assert_eq!(
+            report.errors[3].expected,
+            "semantic version (e.g. 1.4.0-rc.1)"
+        );
+    }
+
+    #[test]
+    fn test_validate_quantities() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+                timeout: { type: duration, min: 1s, max: 5m }
+                memory: { type: byte_size, max: 1Gi }
+                ratio: { type: percentage, max: 100% }
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+
+        let valid = r#"
+            timeout: 1m30s
+            memory: 512Mi
+            ratio: 75%
+        "#;
+        let report = validator.validate_str(valid, ConfigFormat::Yaml).unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
+
+        let invalid = r#"
+            timeout: 10m
+            memory: 2GB
+            ratio: 120%
+        "#;
+        let report = validator.validate_str(invalid, ConfigFormat::Yaml).unwrap();
+        assert_eq!(report.errors.len(), 3, "{:?}", report.errors);
+        assert_eq!(report.errors[0].message, "Value too large");
+        assert_eq!(report.errors[0].expected, "At most 5m (300 seconds)");
+        assert_eq!(report.errors[0].actual, "10m (600 seconds)");
+        assert_eq!(report.errors[1].expected, "At most 1Gi (1073741824 bytes)");
+        assert_eq!(report.errors[1].actual, "2GB (2000000000 bytes)");
+        assert_eq!(report.errors[2].expected, "At most 100%");
+        assert_eq!(report.errors[2].actual, "120%");
+
+        let invalid = r#"
+            timeout: 30
+            memory: 12XB
+            ratio: 0.5
+        "#;
+        let report = validator.validate_str(invalid, ConfigFormat::Yaml).unwrap();
+        let messages: Vec<&str> = report.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Type mismatch",
+                "Invalid byte size: unknown unit 'XB' (use e.g. B, KB, KiB, MB, MiB, GB, GiB)",
+                "Type mismatch",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_numeric_constraints() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+                port: { type: u16, exclusive_min: 0 }
+                id: { type: u64, max: 18446744073709551614 }
+                offset: { type: i8 }
+                buffer: { type: integer, multiple_of: 1024 }
+                ratio: { type: float, exclusive_max: 1.0, max_decimal_places: 2 }
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+
+        let valid = r#"
+            port: 8080
+            id: 18446744073709551614
+            offset: -128
+            buffer: 4096
+            ratio: 0.25
+        "#;
+        let report = validator.validate_str(valid, ConfigFormat::Yaml).unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
+
+        let invalid = r#"
+            port: 0
+            id: 18446744073709551615
+            offset: 200
+            buffer: 1000
+            ratio: 0.125
+        "#;
+        let report = validator.validate_str(invalid, ConfigFormat::Yaml).unwrap();
+        let found: Vec<(&str, &str, &str)> = report
+            .errors
+            .iter()
+            .map(|e| (e.message.as_str(), e.expected.as_str(), e.actual.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Value too small", "Greater than 0", "0"),
+                (
+                    "Value too large",
+                    "At most 18446744073709551614",
+                    "18446744073709551615"
+                ),
+                ("Value out of range for i8", "Between -128 and 127", "200"),
+                (
+                    "Value is not a multiple of the required step",
+                    "A multiple of 1024",
+                    "1000"
+                ),
+                (
+                    "Too many decimal places",
+                    "At most 2 decimal places",
+                    "0.125 (3 decimal places)"
+                ),
+            ]
+        );
+
+        let report = validator
+            .validate_str("{ port: 70000, ratio: 1.0 }", ConfigFormat::Yaml)
+            .unwrap();
+        let messages: Vec<&str> = report.errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["Value out of range for u16", "Value too large"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_fills_defaults() {
+        let schema = schema_from_yaml(
+            r##"
+            type: object
+            definitions:
+              backend:
+                type: object
+                keys:
+                  host:
+                    type: string
+                    required: true
+                  weight:
+                    type: integer
+                    default: 1
+            keys:
+              name:
+                type: string
+                required: true
+              port:
+                type: integer
+                default: 8080
+              log:
+                type: object
+                default: {}
+                keys:
+                  level:
+                    type: string
+                    default: info
+              backends:
+                type: list
+                items:
+                  $ref: "#/definitions/backend"
+        "##,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+
+        let config = Config::from_str(
+            "name: web\nport: 80\nbackends:\n  - host: a\n  - host: b\n    weight: 5\n",
+            ConfigFormat::Yaml,
+        )
+        .unwrap();
+        let resolved = validator.resolve(&config).unwrap();
+
+        assert_eq!(resolved.format, ConfigFormat::Yaml);
+        assert_eq!(
+            resolved.render().unwrap(),
+            "name: web\nport: 80\nbackends:\n- host: a\n  weight: 1\n- host: b\n  weight: 5\nlog:\n  level: info\n"
+        );
+        assert!(validator.validate(&resolved).unwrap().is_valid());
+    }
+
+    #[test]
+    fn test_validate_deprecated_keys() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+              listen_port:
+                type: integer
+                renamed_to: server.port
+              log_file:
+                type: string
+                deprecated: "log to stdout instead"
+                deprecated_until: 2000-01-01
+              server:
+                type: object
+                keys:
+                  port:
+                    type: integer
+              mode:
+                type: any
+                one_of:
+                  - type: object
+                    keys:
+                      legacy:
+                        type: boolean
+                        deprecated: "remove it"
+                  - type: string
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+
+        let report = validator
+            .validate_str(
+                "listen_port: 80\nmode:\n  legacy: true\n",
+                ConfigFormat::Yaml,
+            )
+            .unwrap();
+        assert!(report.is_valid());
+        let warnings: Vec<(&str, &str, &str)> = report
+            .warnings
+            .iter()
+            .map(|w| (w.path.as_str(), w.message.as_str(), w.expected.as_str()))
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                (
+                    ".listen_port",
+                    "Key 'listen_port' was renamed to 'server.port'",
+                    "Use 'server.port' instead"
+                ),
+                (".mode.legacy", "Key 'legacy' is deprecated", "remove it"),
+            ]
+        );
+
+        // Past its cutoff date a deprecated key fails validation
+        let report = validator
+            .validate_str("log_file: /var/log/app.log", ConfigFormat::Yaml)
+            .unwrap();
+        assert!(report.warnings.is_empty());
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(
+            report.errors[0].message,
+            "Key 'log_file' is deprecated and is no longer accepted after 2000-01-01"
+        );
+        assert_eq!(report.errors[0].severity, Severity::Error);
     }
 
     #[test]
-    fn test_validate_enum_values() {
-        let schema_str = r#"
+    fn test_validate_rule_severity() {
+        let schema = schema_from_yaml(
+            r#"
             type: object
+            check_severity:
+              unknown_key: info
             keys:
-                color:
-                    type: string
-                    enum: [red, green, blue]
-        "#;
+              name:
+                type: string
+                max_length: 3
+                severity: warning
+              owner:
+                type: string
+                required: true
+                severity: info
+              port:
+                type: integer
+                min: 1024
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap()).strict(true);
 
-        // Valid enum value
-        let config_str = "color: green";
-        let schema = schema_from_yaml(schema_str);
-        let config = config_from_yaml(config_str);
+        let report = validator
+            .validate_str("name: abcdef\nport: 8080\nextra: 1\n", ConfigFormat::Yaml)
+            .unwrap();
+        assert!(report.is_valid());
+        assert!(report.fails_on(Severity::Warning));
+        let messages = |diagnostics: &[ValidationError]| -> Vec<String> {
+            diagnostics
+                .iter()
+                .map(|e| format!("{} {}", e.path, e.message))
+                .collect()
+        };
+        assert_eq!(messages(&report.warnings), vec![".name String too long"]);
+        assert_eq!(
+            messages(&report.info),
+            vec![".owner Required key missing", ".extra Unknown key"]
+        );
 
-        let result = validate(&config, &schema, false);
-        assert!(result.is_ok());
-        assert!(matches!(result.unwrap(), ValidationResult::Valid));
+        let report = validator
+            .validate_str("owner: ops\nport: 80\n", ConfigFormat::Yaml)
+            .unwrap();
+        assert!(!report.is_valid());
+        assert_eq!(messages(&report.errors), vec![".port Value too small"]);
+    }
 
-        // Invalid enum value
-        let config_str = "color: yellow"; // Not in enum
-        let config = config_from_yaml(config_str);
+    #[test]
+    fn test_validate_custom_error_messages() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+              apiVersion:
+                type: string
+                required: true
+                pattern: "^v1"
+                error_message: "apiVersion must start with v1, got '{value}' at {path}"
+                hint: "Set apiVersion to v1"
+                docs_url: https://example.com/docs/api-version
+              replicas:
+                type: integer
+                min: 1
+                error_message: "replicas must be at least {min}"
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
 
-        let result = validate(&config, &schema, false);
-        assert!(result.is_err());
-        match result {
-            Ok(_) => panic!("Validation should have failed due to invalid enum value"),
-            Err(err) => match err {
-                ConfigGuardError::AllValidationErrors { errors } => {
-                    assert_eq!(errors.len(), 1);
-                    assert_eq!(errors[0].message, "Value not in allowed set");
-                }
-                _ => panic!("Expected AllValidationErrors, got {:?}", err),
-            },
-        }
+        let report = validator
+            .validate_str("apiVersion: v2\nreplicas: 0\n", ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(report.errors.len(), 2);
+        let api_version = &report.errors[0];
+        assert_eq!(
+            api_version.message,
+            "apiVersion must start with v1, got 'v2' at .apiVersion"
+        );
+        assert_eq!(api_version.expected, "Pattern: ^v1");
+        assert_eq!(api_version.hint.as_deref(), Some("Set apiVersion to v1"));
+        assert_eq!(
+            api_version.docs_url.as_deref(),
+            Some("https://example.com/docs/api-version")
+        );
+        assert_eq!(report.errors[1].message, "replicas must be at least 1");
+        assert_eq!(report.errors[1].hint, None);
+
+        // A missing required key is reported with the key's own text
+        let report = validator
+            .validate_str("replicas: 2", ConfigFormat::Yaml)
+            .unwrap();
+        assert_eq!(
+            report.errors[0].message,
+            "apiVersion must start with v1, got 'Key is absent' at .apiVersion"
+        );
+        assert_eq!(
+            report.errors[0].hint.as_deref(),
+            Some("Set apiVersion to v1")
+        );
     }
 
     #[test]
-    fn test_validate_strict_mode() {
-        let schema_str = r#"
+    fn test_validate_union_member_constraints() {
+        let schema = schema_from_yaml(
+            r#"
             type: object
             keys:
-                name:
-                    type: string
-                    required: true
-        "#;
+              size:
+                type: [string, integer]
+                min_length: 2
+                min: 0
+              workers:
+                type: [integer, string]
+                default: auto
+                min: 0
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
 
-        let config_str = r#"
-            name: Test
-            extra_field: This shouldn't be here
-        "#;
+        let report = validator
+            .validate_str("size: ab\nworkers: 4\n", ConfigFormat::Yaml)
+            .unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
 
-        let schema = schema_from_yaml(schema_str);
-        let config = config_from_yaml(config_str);
+        // Each value is checked with the constraints of the type it has
+        let report = validator
+            .validate_str("size: a\nworkers: -1\n", ConfigFormat::Yaml)
+            .unwrap();
+        let errors: Vec<(&str, &str)> = report
+            .errors
+            .iter()
+            .map(|e| (e.path.as_str(), e.message.as_str()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (".size", "String too short"),
+                (".workers", "Value too small")
+            ]
+        );
+    }
 
-        // With strict mode off (default), unknown keys are allowed
-        let result = validate(&config, &schema, false);
-        assert!(result.is_ok());
-        assert!(matches!(result.unwrap(), ValidationResult::Valid));
+    #[test]
+    fn test_validate_union_and_nullable_types() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+              name:
+                type: [string, null]
+                max_length: 5
+              port:
+                type: u16
+                nullable: true
+                min: 1
+              timeout:
+                type: [integer, string]
+                min: 1
+              retries:
+                type: u8
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
 
-        // With strict mode on, unknown keys should cause a validation error
-        let result = validate(&config, &schema, true);
-        assert!(result.is_err());
-        match result {
-            Ok(_) => panic!("Validation should have failed in strict mode due to unknown keys"),
-            Err(err) => match err {
-                ConfigGuardError::AllValidationErrors { errors } => {
-                    assert_eq!(errors.len(), 1);
-                    assert_eq!(errors[0].path, ".extra_field");
-                    assert_eq!(errors[0].message, "Unknown key");
-                }
-                _ => panic!("Expected AllValidationErrors, got {:?}", err),
-            },
+        for config in [
+            "name:\nport:\ntimeout: forever\n",
+            "name: web\nport: 8080\ntimeout: 30\n",
+        ] {
+            let report = validator.validate_str(config, ConfigFormat::Yaml).unwrap();
+            assert!(report.is_valid(), "{}: {:?}", config, report.errors);
         }
+
+        let report = validator
+            .validate_str("name: 42\nport: 70000\ntimeout: 0\n", ConfigFormat::Yaml)
+            .unwrap();
+        let errors: Vec<(&str, &str, &str, &str)> = report
+            .errors
+            .iter()
+            .map(|e| {
+                (
+                    e.path.as_str(),
+                    e.message.as_str(),
+                    e.expected.as_str(),
+                    e.actual.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (".name", "Type mismatch", "string or null", "integer"),
+                (
+                    ".port",
+                    "Value out of range for u16",
+                    "Between 0 and 65535",
+                    "70000"
+                ),
+                (".timeout", "Value too small", "At least 1", "0"),
+            ]
+        );
+
+        let report = validator
+            .validate_str(
+                "name: abcdefg\nport: true\nretries: many\n",
+                ConfigFormat::Yaml,
+            )
+            .unwrap();
+        assert_eq!(report.errors.len(), 3);
+        assert_eq!(report.errors[0].message, "String too long");
+        assert_eq!(report.errors[1].expected, "u16 or null");
+        // Type names are written as in the schema
+        assert_eq!(report.errors[2].expected, "u8");
     }
 
     #[test]
-    fn test_validate_nested_structures() {
-        // Create a schema with nested structure requirements
-        let mut schema_keys = HashMap::new();
-
-        // Add the metadata object with required name field
-        let mut metadata_keys = HashMap::new();
-        metadata_keys.insert(
-            "name".to_string(),
-            SchemaRule {
-                data_type: SchemaType::String,
-                description: Some("The name of the resource".to_string()),
-                required: true,
-                allow_unknown_keys: true,
-                pattern: None,
-                enum_values: None,
-                min: None,
-                max: None,
-                min_length: None,
-                max_length: None,
-                items: None,
-                keys: None,
-            },
+    fn test_validate_const_and_enum() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+              kind:
+                type: string
+                enum: [Deployment, Service]
+                case_insensitive: true
+              replicas:
+                type: [string, integer]
+                enum: [auto, 0, 1]
+              ratio:
+                type: float
+                enum: [0.1, 0.2]
+              api:
+                type: object
+                const: { version: 2, stable: true }
+              tags:
+                type: list
+                const: [a, b]
+        "#,
         );
+        let validator = Validator::new(schema.compile().unwrap());
 
-        schema_keys.insert(
-            "metadata".to_string(),
-            SchemaRule {
-                data_type: SchemaType::Object,
-                description: Some("Metadata for the resource".to_string()),
-                required: true,
-                allow_unknown_keys: true,
-                pattern: None,
-                enum_values: None,
-                min: None,
-                max: None,
-                min_length: None,
-                max_length: None,
-                items: None,
-                keys: Some(metadata_keys),
-            },
+        let report = validator
+            .validate_str(
+                "kind: deployment\nreplicas: auto\nratio: 0.2\napi: { stable: true, version: 2.0 }\ntags: [a, b]\n",
+                ConfigFormat::Yaml,
+            )
+            .unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
+
+        let report = validator
+            .validate_str(
+                "kind: Job\nreplicas: 2\nratio: 0.3\napi: { version: 1, stable: true }\ntags: [b, a]\n",
+                ConfigFormat::Yaml,
+            )
+            .unwrap();
+        let errors: Vec<(&str, &str, &str, &str)> = report
+            .errors
+            .iter()
+            .map(|e| {
+                (
+                    e.path.as_str(),
+                    e.message.as_str(),
+                    e.expected.as_str(),
+                    e.actual.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    ".kind",
+                    "Value not in allowed set",
+                    "One of: Deployment, Service",
+                    "Job"
+                ),
+                (
+                    ".replicas",
+                    "Value not in allowed set",
+                    "One of: auto, 0, 1",
+                    "2"
+                ),
+                (
+                    ".ratio",
+                    "Value not in allowed set",
+                    "One of: 0.1, 0.2",
+                    "0.3"
+                ),
+                (
+                    ".api",
+                    "Value does not match constant",
+                    "Exactly: {\"version\":2,\"stable\":true}",
+                    "{\"version\":1,\"stable\":true}"
+                ),
+                (
+                    ".tags",
+                    "Value does not match constant",
+                    "Exactly: [\"a\",\"b\"]",
+                    "[\"b\",\"a\"]"
+                ),
+            ]
         );
+    }
 
-        // Add a containers list with minimum length requirement
-        schema_keys.insert(
-            "containers".to_string(),
-            SchemaRule {
-                data_type: SchemaType::List,
-                description: Some("List of containers".to_string()),
-                required: true,
-                allow_unknown_keys: true,
-                pattern: None,
-                enum_values: None,
-                min: None,
-                max: None,
-                min_length: Some(1),
-                max_length: None,
-                items: Some(Box::new(SchemaRule {
-                    data_type: SchemaType::Object,
-                    description: None,
-                    required: false,
-                    allow_unknown_keys: true,
-                    pattern: None,
-                    enum_values: None,
-                    min: None,
-                    max: None,
-                    min_length: None,
-                    max_length: None,
-                    items: None,
-                    keys: None,
-                })),
-                keys: None,
-            },
+    #[test]
+    fn test_validate_assertions() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+              min_replicas: { type: integer }
+              max_replicas: { type: integer }
+              replication_factor: { type: integer }
+              brokers: { type: list, items: { type: string } }
+              weights: { type: list, items: { type: integer } }
+            assert:
+              - min_replicas <= max_replicas
+              - expr: len(brokers) >= replication_factor
+                message: Not enough brokers for the replication factor
+              - sum(weights) == 100
+        "#,
         );
+        let validator = Validator::new(schema.compile().unwrap());
 
-        let schema = Schema {
-            root: SchemaRule {
-                data_type: SchemaType::Object,
-                description: None,
-                required: false,
-                allow_unknown_keys: true,
-                pattern: None,
-                enum_values: None,
-                min: None,
-                max: None,
-                min_length: None,
-                max_length: None,
-                items: None,
-                keys: Some(schema_keys),
-            },
-        };
+        let report = validator
+            .validate_str(
+                "min_replicas: 1\nmax_replicas: 3\nreplication_factor: 2\nbrokers: [a, b]\nweights: [60, 40]\n",
+                ConfigFormat::Yaml,
+            )
+            .unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
 
-        // Valid nested config
-        let valid_nested_config = r#"
-            metadata:
-              name: test-app
-            containers:
-              - name: web
-                image: nginx
-        "#;
+        let report = validator
+            .validate_str(
+                "min_replicas: 5\nmax_replicas: 3\nreplication_factor: 3\nbrokers: [a]\nweights: [60, oops]\n",
+                ConfigFormat::Yaml,
+            )
+            .unwrap();
+        // A field with the wrong type is reported on its own, without the assertions
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].path, ".weights[1]");
 
-        let config = Config {
-            data: serde_yaml::from_str(valid_nested_config).unwrap(),
-            format: ConfigFormat::Yaml,
-            path: None,
-            content: Some(valid_nested_config.to_string()),
-        };
+        let report = validator
+            .validate_str(
+                "min_replicas: 5\nmax_replicas: 3\nreplication_factor: 3\nbrokers: [a]\n",
+                ConfigFormat::Yaml,
+            )
+            .unwrap();
+        let errors: Vec<(&str, &str, &str, &str)> = report
+            .errors
+            .iter()
+            .map(|e| {
+                (
+                    e.path.as_str(),
+                    e.message.as_str(),
+                    e.expected.as_str(),
+                    e.actual.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    "",
+                    "Assertion failed",
+                    "min_replicas <= max_replicas",
+                    "min_replicas = 5, max_replicas = 3"
+                ),
+                (
+                    "",
+                    "Not enough brokers for the replication factor",
+                    "len(brokers) >= replication_factor",
+                    "brokers = [\"a\"], replication_factor = 3"
+                ),
+                (
+                    "",
+                    "Assertion could not be evaluated",
+                    "sum(weights) == 100",
+                    "sum() does not accept null"
+                ),
+            ]
+        );
+    }
 
-        let result = validate(&config, &schema, false);
-        assert!(result.is_ok());
-        match result.unwrap() {
-            ValidationResult::Valid => {}
-            ValidationResult::Invalid(_) => panic!("Expected valid result"),
-        }
+    #[test]
+    fn test_validate_ref_to() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            keys:
+              backends:
+                type: list
+                items:
+                  type: object
+                  keys:
+                    name: { type: string }
+              routes:
+                type: list
+                items:
+                  type: object
+                  keys:
+                    backend: { type: string, ref_to: "$.backends[*].name" }
+              mirrors:
+                type: list
+                items: { type: string, ref_to: "$.volumes[*].name" }
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
 
-        // Invalid nested config - missing required field
-        let invalid_nested_config = r#"
-            metadata: {}
-            containers:
-              - name: web
-                image: nginx
-        "#;
+        let report = validator
+            .validate_str(
+                "backends: [{ name: api }, { name: auth }]\nroutes: [{ backend: auth }, { backend: api }]\n",
+                ConfigFormat::Yaml,
+            )
+            .unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
 
-        let config = Config {
-            data: serde_yaml::from_str(invalid_nested_config).unwrap(),
-            format: ConfigFormat::Yaml,
-            path: None,
-            content: Some(invalid_nested_config.to_string()),
-        };
+        let report = validator
+            .validate_str(
+                "backends: [{ name: api }, { name: auth }]\nroutes: [{ backend: atuh }, { backend: web }]\nmirrors: [logs]\n",
+                ConfigFormat::Yaml,
+            )
+            .unwrap();
+        let errors: Vec<(&str, &str, &str)> = report
+            .errors
+            .iter()
+            .map(|e| (e.path.as_str(), e.message.as_str(), e.expected.as_str()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    ".routes[0].backend",
+                    "Reference to unknown 'atuh' (did you mean 'auth'?)",
+                    "One of $.backends[*].name: api, auth"
+                ),
+                (
+                    ".routes[1].backend",
+                    "Reference to unknown 'web'",
+                    "One of $.backends[*].name: api, auth"
+                ),
+                (
+                    ".mirrors[0]",
+                    "Reference to unknown 'logs'",
+                    "A value of $.volumes[*].name (none are defined)"
+                ),
+            ]
+        );
+    }
 
-        let result = validate(&config, &schema, false);
-        assert!(result.is_err());
-        match result {
-            Ok(_) => panic!("Expected validation error"),
-            Err(err) => match err {
-                ConfigGuardError::AllValidationErrors { errors } => {
-                    // We're getting two errors: one for the missing key and one for the empty object
-                    // Find the error for the missing required field
-                    let missing_key_error =
-                        errors.iter().find(|e| e.message == "Required key missing");
-                    assert!(missing_key_error.is_some());
-                    let error = missing_key_error.unwrap();
-                    assert_eq!(error.path, ".metadata.name");
-                    assert_eq!(error.message, "Required key missing");
-                }
-                _ => panic!("Expected AllValidationErrors, got {:?}", err),
-            },
-        }
+    #[test]
+    fn test_validate_project() {
+        let schema = schema_from_yaml(
+            r#"
+            type: object
+            project:
+              unique: ["$.metadata.name"]
+              references:
+                - { path: "$.depends_on[*]", ref_to: "$.metadata.name" }
+              exactly_one:
+                - { path: "$.default", equals: true }
+        "#,
+        );
+        let validator = Validator::new(schema.compile().unwrap());
+        let config = |name: &str, content: &str| {
+            let mut config = Config::from_str(content, ConfigFormat::Yaml).unwrap();
+            config.path = Some(PathBuf::from(name));
+            config
+        };
 
-        // Invalid nested config - empty list
-        let empty_list_config = r#"
-            metadata:
-              name: test-app
-            containers: []
-        "#;
+        let report = validator
+            .validate_project(&[
+                config("api.yaml", "metadata: { name: api }\ndefault: true\n"),
+                config("web.yaml", "metadata: { name: web }\ndepends_on: [api]\n"),
+            ])
+            .unwrap();
+        assert!(report.is_valid(), "{:?}", report.errors);
 
-        let config = Config {
-            data: serde_yaml::from_str(empty_list_config).unwrap(),
-            format: ConfigFormat::Yaml,
-            path: None,
-            content: Some(empty_list_config.to_string()),
-        };
+        let report = validator
+            .validate_project(&[
+                config("api.yaml", "metadata: { name: api }\ndefault: true\n"),
+                config(
+                    "web.yaml",
+                    "metadata:\n  name: api\ndepends_on: [apj]\ndefault: true\n",
+                ),
+            ])
+            .unwrap();
+        let errors: Vec<_> = report
+            .errors
+            .iter()
+            .map(|e| {
+                (
+                    e.file.as_deref(),
+                    e.path.as_str(),
+                    e.message.as_str(),
+                    e.actual.as_str(),
+                    e.line,
+                )
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    Some("web.yaml"),
+                    ".metadata.name",
+                    "Duplicate value 'api' for $.metadata.name",
+                    "Also in 'api.yaml' at .metadata.name",
+                    Some(2)
+                ),
+                (
+                    Some("web.yaml"),
+                    ".depends_on[0]",
+                    "Reference to unknown 'apj' (did you mean 'api'?)",
+                    "apj",
+                    Some(3)
+                ),
+                (
+                    Some("web.yaml"),
+                    ".default",
+                    "More than one file has $.default = true",
+                    "Also in 'api.yaml' at .default",
+                    Some(4)
+                ),
+            ]
+        );
 
-        let result = validate(&config, &schema, false);
-        assert!(result.is_err());
-        match result {
-            Ok(_) => panic!("Expected validation error"),
-            Err(err) => match err {
-                ConfigGuardError::AllValidationErrors { errors } => {
-                    assert_eq!(errors.len(), 1);
-                    assert_eq!(errors[0].path, ".containers");
-                    assert_eq!(errors[0].message, "List too short");
-                }
-                _ => panic!("Expected AllValidationErrors, got {:?}", err),
-            },
-        }
+        let report = validator
+            .validate_project(&[config("web.yaml", "metadata: { name: web }\n")])
+            .unwrap();
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].message, "No file has $.default = true");
+        assert_eq!(report.errors[0].file, None);
     }
 }
@@ -250,6 +250,138 @@ fn test_directory_validation() -> Result<()> {
         "Expected validation summary with skipped files"
     );
 
+    // Without project constraints, JSON output is the summary of each directory
+    let (status, stdout, _) = run_configguard(
+        &[
+            "validate",
+            "--schema",
+            "schema.yaml",
+            "--directory",
+            "configs",
+            "--format",
+            "json",
+        ],
+        temp_dir.path(),
+    )?;
+    assert_eq!(status, 0, "Expected success status code");
+    let parsed: serde_json::Value = serde_json::from_str(&stdout)?;
+    assert_eq!(
+        parsed,
+        serde_json::json!({
+            "directory": "configs",
+            "processed": 1,
+            "valid": 1,
+            "invalid": 0,
+            "skipped": 0
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_directory_project_constraints() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    // Names must be unique across every file in the directory
+    let schema = r#"
+    type: object
+    project:
+      unique:
+        - $.metadata.name
+    keys:
+      metadata:
+        type: object
+        required: true
+        keys:
+          name:
+            type: string
+            required: true
+          replicas:
+            type: integer
+    "#;
+    create_temp_file(temp_dir.path(), "schema.yaml", schema)?;
+
+    let configs_dir = temp_dir.path().join("configs");
+    fs::create_dir(&configs_dir)?;
+    create_temp_file(&configs_dir, "a.yaml", "metadata:\n  name: web\n")?;
+    create_temp_file(&configs_dir, "b.yaml", "metadata:\n  name: api\n")?;
+    let args = [
+        "validate",
+        "--schema",
+        "schema.yaml",
+        "--directory",
+        "configs",
+    ];
+
+    let (status, stdout, _) = run_configguard(&args, temp_dir.path())?;
+    assert_eq!(status, 0, "Expected distinct names to pass");
+    assert!(stdout.contains("Project constraints: Valid"));
+
+    // A second file reusing a name fails the project, though each file is valid
+    create_temp_file(&configs_dir, "c.yaml", "metadata:\n  name: web\n")?;
+
+    let (status, _, stderr) = run_configguard(&args, temp_dir.path())?;
+    assert_eq!(status, 10, "Expected validation failure exit code");
+    // The duplicate is reported in the later file, and cites the first one
+    let a_file = Path::new("configs").join("a.yaml").display().to_string();
+    let c_file = Path::new("configs").join("c.yaml").display().to_string();
+    assert!(stderr.contains(&format!(
+        "Error in '{}' at path '.metadata.name' (line 2, column 3): Duplicate value 'web' for $.metadata.name",
+        c_file
+    )));
+    assert!(stderr.contains(&format!("Found: Also in '{}' at .metadata.name", a_file)));
+
+    // A file can fail its own schema and the project at the same time
+    create_temp_file(
+        &configs_dir,
+        "c.yaml",
+        "metadata:\n  name: web\n  replicas: many\n",
+    )?;
+
+    let (status, _, stderr) = run_configguard(&args, temp_dir.path())?;
+    assert_eq!(status, 10, "Expected validation failure exit code");
+    assert!(stderr.contains(&format!("❌ {}: Error", c_file)));
+    assert!(stderr.contains("Duplicate value 'web' for $.metadata.name"));
+
+    // A file that cannot be loaded is a project error, not silently left out
+    create_temp_file(&configs_dir, "d.yaml", "metadata: [web\n")?;
+
+    let (status, _, stderr) = run_configguard(&args, temp_dir.path())?;
+    assert_eq!(status, 10, "Expected validation failure exit code");
+    let d_file = Path::new("configs").join("d.yaml").display().to_string();
+    assert!(stderr.contains(&format!(
+        "Error in '{}' at path '': File could not be checked against the project constraints",
+        d_file
+    )));
+
+    // JSON output is a single document with every file and the project
+    let (status, stdout, _) = run_configguard(
+        &[&args[..], &["--format", "json"]].concat(),
+        temp_dir.path(),
+    )?;
+    assert_eq!(status, 10, "Expected validation failure exit code");
+    let parsed: serde_json::Value = serde_json::from_str(&stdout)?;
+    assert_eq!(parsed["files"].as_array().map(Vec::len), Some(4));
+    assert_eq!(parsed["files"][2]["file"], c_file.as_str());
+    assert_eq!(
+        parsed["files"][2]["errors"][0]["path"],
+        ".metadata.replicas"
+    );
+    assert_eq!(parsed["files"][3]["valid"], false);
+    assert_eq!(parsed["total"]["invalid"], 2);
+
+    let project = &parsed["project"];
+    assert_eq!(project["valid"], false);
+    assert_eq!(project["error_count"], 2);
+    assert_eq!(project["errors"][0]["file"], c_file.as_str());
+    assert_eq!(project["errors"][0]["path"], ".metadata.name");
+    assert_eq!(
+        project["errors"][0]["actual"],
+        format!("Also in '{}' at .metadata.name", a_file).as_str()
+    );
+    assert_eq!(project["errors"][1]["file"], d_file.as_str());
+
     Ok(())
 }
 
@@ -308,3 +440,192 @@ fn test_validate_strict_mode() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_validate_toml_config() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    // Create schema file
+    create_temp_file(temp_dir.path(), "schema.yaml", get_minimal_schema())?;
+
+    // Create valid TOML config file
+    let valid_toml = r#"
+    apiVersion = "v1"
+    kind = "Service"
+
+    [metadata]
+    name = "test-service"
+    "#;
+    create_temp_file(temp_dir.path(), "Config.toml", valid_toml)?;
+
+    // Run configguard on the single file
+    let (status, stdout, _) = run_configguard(
+        &["validate", "--schema", "schema.yaml", "Config.toml"],
+        temp_dir.path(),
+    )?;
+    assert_eq!(status, 0, "Expected successful exit code (0)");
+    assert!(stdout.contains("Configuration validation passed"));
+
+    // Directory mode should pick up the TOML file as well
+    let configs_dir = temp_dir.path().join("configs");
+    fs::create_dir(&configs_dir)?;
+    create_temp_file(&configs_dir, "Config.toml", valid_toml)?;
+    create_temp_file(&configs_dir, "invalid.toml", "apiVersion = \"v2\"\n")?;
+
+    let (status, stdout, _) = run_configguard(
+        &[
+            "validate",
+            "--schema",
+            "schema.yaml",
+            "--directory",
+            "configs",
+        ],
+        temp_dir.path(),
+    )?;
+    assert_eq!(status, 10, "Expected validation failure exit code (10)");
+    assert!(stdout.contains("Processed: 2 files"));
+    assert!(stdout.contains("Valid: 1 files"));
+
+    Ok(())
+}
+
+#[test]
+fn test_validate_with_imported_schema_fragments() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let common_dir = temp_dir.path().join("common");
+    fs::create_dir(&common_dir)?;
+
+    // Shared fragment published by another team
+    let tls_schema = r#"
+    definitions:
+      tls:
+        type: object
+        keys:
+          enabled: { type: boolean, required: true }
+          cert_file: { type: string, min_length: 1 }
+    "#;
+    create_temp_file(&common_dir, "tls.yaml", tls_schema)?;
+
+    let schema = r##"
+    type: object
+    keys:
+      name: { type: string, required: true }
+      tls: { $ref: "./common/tls.yaml#/definitions/tls", required: true }
+    "##;
+    create_temp_file(temp_dir.path(), "schema.yaml", schema)?;
+
+    create_temp_file(
+        temp_dir.path(),
+        "valid.yaml",
+        "name: api\ntls:\n  enabled: true\n  cert_file: /etc/tls/cert.pem\n",
+    )?;
+    create_temp_file(
+        temp_dir.path(),
+        "invalid.yaml",
+        "name: api\ntls:\n  cert_file: \"\"\n",
+    )?;
+
+    let (status, stdout, _) = run_configguard(
+        &["validate", "--schema", "schema.yaml", "valid.yaml"],
+        temp_dir.path(),
+    )?;
+    assert_eq!(status, 0, "Expected successful exit code (0)");
+    assert!(stdout.contains("Configuration validation passed"));
+
+    let (status, _, stderr) = run_configguard(
+        &["validate", "--schema", "schema.yaml", "invalid.yaml"],
+        temp_dir.path(),
+    )?;
+    assert_eq!(status, 10, "Expected error exit code (10)");
+    assert!(stderr.contains(".tls.enabled"));
+    assert!(stderr.contains(".tls.cert_file"));
+
+    Ok(())
+}
+
+#[test]
+fn test_resolve_prints_config_with_defaults() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    let schema = r#"
+    type: object
+    keys:
+      name:
+        type: string
+        required: true
+      replicas:
+        type: integer
+        min: 1
+        default: 2
+    "#;
+    create_temp_file(temp_dir.path(), "schema.yaml", schema)?;
+    create_temp_file(temp_dir.path(), "config.json", r#"{"name": "web"}"#)?;
+    create_temp_file(temp_dir.path(), "invalid.json", r#"{"replicas": 0}"#)?;
+
+    let (status, stdout, _) = run_configguard(
+        &["resolve", "--schema", "schema.yaml", "config.json"],
+        temp_dir.path(),
+    )?;
+    assert_eq!(status, 0, "Expected successful exit code (0)");
+    let resolved: serde_json::Value = serde_json::from_str(&stdout)?;
+    assert_eq!(resolved, serde_json::json!({"name": "web", "replicas": 2}));
+
+    // Configurations that fail validation are reported instead of printed
+    let (status, stdout, stderr) = run_configguard(
+        &["resolve", "--schema", "schema.yaml", "invalid.json"],
+        temp_dir.path(),
+    )?;
+    assert_eq!(status, 10, "Expected validation failure exit code (10)");
+    assert!(stdout.is_empty());
+    assert!(stderr.contains("Error at path '.name'"));
+
+    Ok(())
+}
+
+#[test]
+fn test_deprecated_keys_are_warnings() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    let schema = r#"
+    type: object
+    keys:
+      listen_port:
+        type: integer
+        renamed_to: server.port
+      server:
+        type: object
+        keys:
+          port:
+            type: integer
+    "#;
+    create_temp_file(temp_dir.path(), "schema.yaml", schema)?;
+    create_temp_file(temp_dir.path(), "config.yaml", "listen_port: 8080\n")?;
+
+    let (status, stdout, stderr) = run_configguard(
+        &["validate", "--schema", "schema.yaml", "config.yaml"],
+        temp_dir.path(),
+    )?;
+    assert_eq!(status, 0, "Expected successful exit code (0)");
+    assert!(stdout.contains("Configuration validation passed with 1 warning"));
+    assert!(stdout.contains(
+        "Warning at path '.listen_port' (line 1, column 1): Key 'listen_port' was renamed to 'server.port'"
+    ));
+    assert!(stderr.is_empty());
+
+    // With --fail-on warning the same configuration is rejected
+    let (status, _, stderr) = run_configguard(
+        &[
+            "validate",
+            "--schema",
+            "schema.yaml",
+            "--fail-on",
+            "warning",
+            "config.yaml",
+        ],
+        temp_dir.path(),
+    )?;
+    assert_eq!(status, 10, "Expected validation failure exit code (10)");
+    assert!(stderr.contains("Configuration validation failed with 1 warning"));
+
+    Ok(())
+}